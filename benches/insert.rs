@@ -0,0 +1,40 @@
+use criterion::{criterion_group, criterion_main, BatchSize, Criterion};
+use stealth_lib::merkle_tree::MerkleTree;
+
+const DEPTH: u8 = 20;
+
+// Each iteration inserts one leaf into a tree already holding 1000, so the
+// frontier is partly filled as it is on a live pool.
+fn prefilled(frontier_only: bool) -> MerkleTree {
+    let mut tree = MerkleTree::new(DEPTH);
+    if frontier_only {
+        tree = tree.with_frontier_only();
+    }
+    for leaf in 1..=1000 {
+        tree.insert(leaf).unwrap();
+    }
+    tree
+}
+
+fn insert(c: &mut Criterion) {
+    let mut group = c.benchmark_group("insert_depth_20");
+    group.bench_function("insert", |b| {
+        b.iter_batched_ref(|| prefilled(false), |tree| tree.insert(1001).unwrap(), BatchSize::SmallInput)
+    });
+    group.bench_function("insert_frontier_only", |b| {
+        b.iter_batched_ref(|| prefilled(true), |tree| tree.insert_frontier_only(1001).unwrap(), BatchSize::SmallInput)
+    });
+    #[cfg(feature = "solana")]
+    group.bench_function("tree_account", |b| {
+        use stealth_lib::merkle_tree::account::TreeAccount;
+        b.iter_batched_ref(
+            || TreeAccount::from_tree(&prefilled(true)).unwrap(),
+            |account| account.insert(1001).unwrap(),
+            BatchSize::SmallInput
+        )
+    });
+    group.finish();
+}
+
+criterion_group!(benches, insert);
+criterion_main!(benches);
@@ -0,0 +1,5 @@
+fn main() {
+    // Linker flags for loading the cdylib as a Node addon.
+    #[cfg(feature = "napi")]
+    napi_build::setup();
+}
@@ -0,0 +1,83 @@
+use std::sync::OnceLock;
+
+use primitive_types::{U256, U512};
+
+use crate::curve::babyjubjub::{reduce_scalar, Point, SUBGROUP_ORDER};
+use crate::hasher::pedersen::derive_generator;
+use crate::utils::SolanaError;
+
+pub const BLINDING_GENERATOR_PREFIX: &str = "PedersenCommitmentBlinding";
+
+/// Pedersen commitment `value * G + blinding * H` on BabyJubJub, with `G = Base8`
+/// and `H` derived by hashing so that nobody knows `log_G(H)`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Commitment(pub Point);
+
+pub fn value_generator() -> Point {
+    Point::base8()
+}
+
+pub fn blinding_generator() -> Point {
+    static GENERATOR: OnceLock<Point> = OnceLock::new();
+    *GENERATOR.get_or_init(|| derive_generator(BLINDING_GENERATOR_PREFIX, 0))
+}
+
+pub fn commit(value: U256, blinding: U256) -> Commitment {
+    let value_part = value_generator().mul_scalar(reduce_scalar(value));
+    let blinding_part = blinding_generator().mul_scalar(reduce_scalar(blinding));
+    Commitment(value_part.add(&blinding_part))
+}
+
+/// Adds two openings modulo the subgroup order, matching `Commitment::add`.
+pub fn add_scalars(a: U256, b: U256) -> U256 {
+    let sum = (U512::from(reduce_scalar(a)) + U512::from(reduce_scalar(b))) % U512::from(SUBGROUP_ORDER);
+    U256::try_from(sum).expect("Reduced scalar fits in 256 bits")
+}
+
+impl Commitment {
+    pub fn add(&self, other: &Commitment) -> Commitment {
+        Commitment(self.0.add(&other.0))
+    }
+
+    pub fn sub(&self, other: &Commitment) -> Commitment {
+        Commitment(self.0.sub(&other.0))
+    }
+
+    pub fn verify_opening(&self, value: U256, blinding: U256) -> bool {
+        *self == commit(value, blinding)
+    }
+
+    pub fn compress(&self) -> [u8; 32] {
+        self.0.compress()
+    }
+
+    pub fn decompress(bytes: &[u8; 32]) -> Result<Commitment, SolanaError> {
+        Point::decompress(bytes).map(Commitment)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_opening() {
+        let commitment = commit(U256::from(100u64), U256::from(12345u64));
+        assert!(commitment.verify_opening(U256::from(100u64), U256::from(12345u64)));
+        assert!(!commitment.verify_opening(U256::from(101u64), U256::from(12345u64)));
+        assert!(!commitment.verify_opening(U256::from(100u64), U256::from(12346u64)));
+        assert!(blinding_generator().in_subgroup());
+        assert_eq!(Commitment::decompress(&commitment.compress()).unwrap(), commitment);
+    }
+
+    #[test]
+    fn test_homomorphism() {
+        let a = commit(U256::from(30u64), U256::from(7u64));
+        let b = commit(U256::from(12u64), SUBGROUP_ORDER - U256::from(2u64));
+        let blinding = add_scalars(U256::from(7u64), SUBGROUP_ORDER - U256::from(2u64));
+
+        assert_eq!(blinding, U256::from(5u64));
+        assert!(a.add(&b).verify_opening(U256::from(42u64), blinding));
+        assert!(a.add(&b).sub(&b).verify_opening(U256::from(30u64), U256::from(7u64)));
+    }
+}
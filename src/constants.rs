@@ -0,0 +1,160 @@
+use crate::hasher::Hasher;
+
+pub const FIELD_SIZE: u128 = 340282366920938463463374607431768211455;
+
+/// Whether `value` is a canonical field element, i.e. below `FIELD_SIZE`.
+pub fn in_field(value: u128) -> bool {
+    // FIELD_SIZE is u128::MAX, so it is the only u128 outside the field.
+    value != FIELD_SIZE
+}
+
+pub const MIMC_ROUNDS: u8 = 10;
+
+pub const MIMC_ROUND_CONSTANTS: [u128; 20] = [
+    0,
+    25823191961023811529686723375255045,
+    48376936063113800887806988124358800,
+    75580405153655082660116863095114839,
+    66651710483985382365580181188706173,
+    45887003413921204775397977044284378,
+    14399999722617037892747232478295923,
+    29376176727758177809204424209125257,
+    13768859312518298840937540532277016,
+    54749662990362840569021981534456448,
+    25161436470718351277017231215227846,
+    90370030464179443930112165274275271,
+    92014788260850167582827910417652439,
+    40376490640073034398204558905403523,
+    90379224439153137712327643289289624,
+    11220341520269979188892857030918685,
+    11480168113674888067906254878279274,
+    11144081894867681653997893051446803,
+    64965960071752809090438003157362764,
+    98428510787134995495896453413714864
+];
+
+/// Where a constants table comes from, so its values can be re-derived or audited.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Provenance {
+    pub name: &'static str,
+    pub derivation_seed: Option<&'static str>,
+    pub generation_method: &'static str,
+    pub source: &'static str
+}
+
+pub const PROVENANCE: [Provenance; 11] = [
+    Provenance {
+        name: "field_size",
+        derivation_seed: None,
+        generation_method: "2^128 - 1, the largest value representable by u128",
+        source: "constants::FIELD_SIZE"
+    },
+    Provenance {
+        name: "mimc_rounds",
+        derivation_seed: None,
+        generation_method: "fixed at 10 rounds for both the Feistel permutation and the sponge",
+        source: "constants::MIMC_ROUNDS"
+    },
+    Provenance {
+        name: "mimc_round_constants",
+        derivation_seed: None,
+        generation_method: "hard-coded table carried over from the first release; the seed was not recorded, \
+                            and the values fit in 128 bits, so they are not circomlib's BN254 MiMCSponge constants",
+        source: "constants::MIMC_ROUND_CONSTANTS"
+    },
+    Provenance {
+        name: "zero_values",
+        derivation_seed: Some("0"),
+        generation_method: "zeros(0) = 0 and zeros(i + 1) = Hasher::mimc_sponge(zeros(i), 0, FIELD_SIZE)",
+        source: "constants::zero_values, MerkleTree::zeros"
+    },
+    Provenance {
+        name: "mimc_sponge_constants",
+        derivation_seed: Some("mimcsponge"),
+        generation_method: "220 links of the keccak256 chain from the seed, reduced mod the BN254 scalar field, \
+                            with the first and last constants set to zero (circomlib mimcsponge.getConstants)",
+        source: "hasher::mimc_sponge::constants"
+    },
+    Provenance {
+        name: "tornado_zeros",
+        derivation_seed: Some("tornado"),
+        generation_method: "zeros(0) = keccak256(seed) mod p and zeros(i + 1) = hashLeftRight(zeros(i), zeros(i)) \
+                            over MiMCSponge, as in Tornado Cash MerkleTreeWithHistory.zeros",
+        source: "hasher::presets::tornado_zeros"
+    },
+    Provenance {
+        name: "poseidon2_round_constants",
+        derivation_seed: None,
+        generation_method: "Grain LFSR initialised for a 254-bit prime field, x^5, t = 3, 8 full and 56 partial rounds, \
+                            rejecting samples above p (HorizenLabs poseidon2 reference scripts)",
+        source: "hasher::poseidon2::Poseidon2::bn254"
+    },
+    Provenance {
+        name: "poseidon_constants",
+        derivation_seed: None,
+        generation_method: "Grain LFSR initialised for a 254-bit prime field, x^5, t, 8 full rounds and circomlib's \
+                            partial rounds; round constants by rejection sampling, then the Cauchy MDS points \
+                            reduced mod p (circomlib poseidon_constants)",
+        source: "hasher::poseidon::Poseidon::circom"
+    },
+    Provenance {
+        name: "neptune_constants",
+        derivation_seed: None,
+        generation_method: "Grain round constants as for poseidon_constants with Neptune's round numbers; \
+                            Cauchy MDS over xs = 0..t and ys = t..2t",
+        source: "hasher::neptune::Neptune::new"
+    },
+    Provenance {
+        name: "gmimc_round_constants",
+        derivation_seed: Some("gmimc"),
+        generation_method: "101 links of the keccak256 chain from the seed, reduced mod FIELD_SIZE, \
+                            with the first and last constants set to zero (Hasher::from_seed)",
+        source: "hasher::gmimc::Gmimc::default"
+    },
+    Provenance {
+        name: "rescue_round_constants",
+        derivation_seed: Some("Rescue-XLIX(p,m,capacity,security_level)"),
+        generation_method: "SHAKE256 of the seed with the parameters filled in, read as 2 * m * rounds little-endian \
+                            integers of ceil(bits(p) / 8) + 1 bytes, each reduced mod p (Rescue-Prime reference)",
+        source: "hasher::rescue::RescuePrime::new"
+    }
+];
+
+pub fn provenance(name: &str) -> Option<&'static Provenance> {
+    PROVENANCE.iter().find(|provenance| provenance.name == name)
+}
+
+pub fn zero_values(levels: u8) -> Vec<u128> {
+    let mut values = Vec::with_capacity(levels as usize);
+    let mut result = 0;
+    for _ in 0..levels {
+        values.push(result);
+        result = Hasher::mimc_sponge(result, 0, FIELD_SIZE);
+    }
+    values
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_zero_values_match_tree() {
+        let values = zero_values(4);
+        for (level, value) in values.iter().enumerate() {
+            assert_eq!(*value, MerkleTree::zeros(level as u8));
+        }
+    }
+
+    #[test]
+    fn test_provenance_lookup() {
+        assert_eq!(FIELD_SIZE, u128::MAX);
+        assert!(provenance("mimc_round_constants").is_some());
+        for (i, entry) in PROVENANCE.iter().enumerate() {
+            assert_eq!(provenance(entry.name).unwrap(), &PROVENANCE[i]);
+        }
+        assert_eq!(provenance("tornado_zeros").unwrap().derivation_seed, Some("tornado"));
+        assert!(provenance("poseidon").is_none());
+    }
+}
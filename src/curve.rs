@@ -0,0 +1,3 @@
+pub mod babyjubjub;
+pub mod elgamal;
+pub mod field;
@@ -0,0 +1,231 @@
+use primitive_types::U256;
+
+use crate::curve::field::Fr;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+pub const A: u64 = 168700;
+pub const D: u64 = 168696;
+
+/// Order of the prime-order subgroup generated by `Point::base8()`.
+pub const SUBGROUP_ORDER: U256 = U256([0x677297dc392126f1, 0xab3eedb83920ee0a, 0x370a08b6d0302b0b, 0x060c89ce5c263405]);
+
+pub const COFACTOR: u64 = 8;
+
+const GENERATOR_X: U256 = U256([0x40f41a59f4d4b45e, 0xb494b1255b1162bb, 0x38bcba38f25645ad, 0x023343e3445b673d]);
+const GENERATOR_Y: U256 = U256([0x50f87d64fc000001, 0x4a0cfa121e6e5c24, 0x6e14116da0605617, 0x0c19139cb84c680a]);
+const BASE8_X: U256 = U256([0x2893f3f6bb957051, 0x2ab8d8010534e0b6, 0x4eacb2e09d6277c1, 0x0bb77a6ad63e739b]);
+const BASE8_Y: U256 = U256([0x4b3c257a872d7d8b, 0xfce0051fb9e13377, 0x25572e1cd16bf9ed, 0x25797203f7a0b249]);
+
+/// Affine point on the BabyJubJub twisted Edwards curve `a*x^2 + y^2 = 1 + d*x^2*y^2`
+/// with the circomlib parameters.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Point {
+    pub x: Fr,
+    pub y: Fr
+}
+
+// Projective (X : Y : Z) representation so additions avoid field inversions.
+#[derive(Clone, Copy)]
+struct ProjectivePoint {
+    x: Fr,
+    y: Fr,
+    z: Fr
+}
+
+impl ProjectivePoint {
+    fn add(&self, other: &ProjectivePoint) -> ProjectivePoint {
+        let a = self.z * other.z;
+        let b = a.square();
+        let c = self.x * other.x;
+        let d = self.y * other.y;
+        let e = Fr::from(D) * c * d;
+        let f = b - e;
+        let g = b + e;
+
+        ProjectivePoint {
+            x: a * f * ((self.x + self.y) * (other.x + other.y) - c - d),
+            y: a * g * (d - Fr::from(A) * c),
+            z: f * g
+        }
+    }
+
+    fn to_affine(self) -> Point {
+        let z_inv = self.z.inverse().expect("Projective point has a non-zero Z");
+        Point {
+            x: self.x * z_inv,
+            y: self.y * z_inv
+        }
+    }
+}
+
+impl Point {
+    pub fn identity() -> Point {
+        Point {
+            x: Fr::zero(),
+            y: Fr::one()
+        }
+    }
+
+    /// circomlib's `Generator`, which generates the full curve group.
+    pub fn generator() -> Point {
+        Point {
+            x: Fr::from_u256(GENERATOR_X),
+            y: Fr::from_u256(GENERATOR_Y)
+        }
+    }
+
+    /// circomlib's `Base8 = 8 * Generator`, which generates the prime-order subgroup.
+    pub fn base8() -> Point {
+        Point {
+            x: Fr::from_u256(BASE8_X),
+            y: Fr::from_u256(BASE8_Y)
+        }
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Point::identity()
+    }
+
+    pub fn is_on_curve(&self) -> bool {
+        let x2 = self.x.square();
+        let y2 = self.y.square();
+        Fr::from(A) * x2 + y2 == Fr::one() + Fr::from(D) * x2 * y2
+    }
+
+    pub fn in_subgroup(&self) -> bool {
+        self.is_on_curve() && self.mul_scalar(SUBGROUP_ORDER).is_identity()
+    }
+
+    pub fn add(&self, other: &Point) -> Point {
+        self.to_projective().add(&other.to_projective()).to_affine()
+    }
+
+    pub fn double(&self) -> Point {
+        self.add(self)
+    }
+
+    pub fn neg(&self) -> Point {
+        Point { x: -self.x, y: self.y }
+    }
+
+    pub fn sub(&self, other: &Point) -> Point {
+        self.add(&other.neg())
+    }
+
+    /// Double-and-add scalar multiplication. The scalar is used as given, without
+    /// reduction, so multiplying by the subgroup order is meaningful.
+    pub fn mul_scalar(&self, scalar: U256) -> Point {
+        let base = self.to_projective();
+        let mut result = Point::identity().to_projective();
+        for i in (0..scalar.bits()).rev() {
+            result = result.add(&result);
+            if scalar.bit(i) {
+                result = result.add(&base);
+            }
+        }
+        result.to_affine()
+    }
+
+    /// circomlib `packPoint`: little-endian `y` with the top bit set when `x` is negative.
+    pub fn compress(&self) -> [u8; 32] {
+        let mut bytes = self.y.to_le_bytes();
+        if self.x.is_negative() {
+            bytes[31] |= 0x80;
+        }
+        bytes
+    }
+
+    /// circomlib `unpackPoint`.
+    pub fn decompress(bytes: &[u8; 32]) -> Result<Point, SolanaError> {
+        let invalid_point = || utils::err_with_code(ErrorCode::InvalidPoint, "Invalid BabyJubJub point encoding");
+
+        let mut y_bytes = *bytes;
+        let sign = y_bytes[31] & 0x80 != 0;
+        y_bytes[31] &= 0x7f;
+
+        let y = Fr::from_le_bytes(&y_bytes).ok_or_else(invalid_point)?;
+        let y2 = y.square();
+        let denominator = (Fr::from(A) - Fr::from(D) * y2).inverse().ok_or_else(invalid_point)?;
+        let mut x = ((Fr::one() - y2) * denominator).sqrt().ok_or_else(invalid_point)?;
+        if sign {
+            x = -x;
+        }
+
+        Ok(Point { x, y })
+    }
+
+    fn to_projective(self) -> ProjectivePoint {
+        ProjectivePoint {
+            x: self.x,
+            y: self.y,
+            z: Fr::one()
+        }
+    }
+}
+
+pub fn reduce_scalar(scalar: U256) -> U256 {
+    scalar % SUBGROUP_ORDER
+}
+
+pub fn public_key(secret: U256) -> Point {
+    Point::base8().mul_scalar(reduce_scalar(secret))
+}
+
+/// Diffie-Hellman shared point `secret * public_key`. Keys outside the
+/// prime-order subgroup are rejected so a peer cannot force the result into a
+/// small subgroup.
+pub fn derive_shared_secret(secret: U256, public_key: &Point) -> Result<Point, SolanaError> {
+    if public_key.is_identity() || !public_key.in_subgroup() {
+        return Err(utils::err_with_code(ErrorCode::InvalidPoint, "Public key is not in the BabyJubJub subgroup"));
+    }
+    if reduce_scalar(secret).is_zero() {
+        return Err(utils::err("Secret scalar must not be zero"));
+    }
+    Ok(public_key.mul_scalar(reduce_scalar(secret)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_base_points() {
+        assert!(Point::generator().is_on_curve());
+        assert!(Point::base8().in_subgroup());
+        assert_eq!(Point::generator().mul_scalar(U256::from(COFACTOR)), Point::base8());
+        assert!(!Point::generator().in_subgroup());
+    }
+
+    #[test]
+    fn test_arithmetic() {
+        let base8 = Point::base8();
+        let p3 = base8.mul_scalar(U256::from(3));
+        assert_eq!(p3, base8.double().add(&base8));
+        assert_eq!(p3.sub(&base8), base8.double());
+        assert!(base8.add(&base8.neg()).is_identity());
+        assert_eq!(base8.mul_scalar(SUBGROUP_ORDER + U256::from(5)), base8.mul_scalar(U256::from(5)));
+    }
+
+    #[test]
+    fn test_shared_secret() {
+        let alice = U256::from(123456789u64);
+        let bob = U256::from(987654321u64);
+        let shared = derive_shared_secret(alice, &public_key(bob)).unwrap();
+        assert_eq!(shared, derive_shared_secret(bob, &public_key(alice)).unwrap());
+
+        assert!(derive_shared_secret(alice, &Point::identity()).is_err());
+        assert!(derive_shared_secret(alice, &Point::generator()).is_err());
+        assert!(derive_shared_secret(SUBGROUP_ORDER, &public_key(bob)).is_err());
+    }
+
+    #[test]
+    fn test_compression_round_trip() {
+        for scalar in [1u64, 2, 7, 123456789] {
+            let point = Point::base8().mul_scalar(U256::from(scalar));
+            assert_eq!(Point::decompress(&point.compress()).unwrap(), point);
+            let negated = point.neg();
+            assert_eq!(Point::decompress(&negated.compress()).unwrap(), negated);
+        }
+        assert!(Point::decompress(&[0xff; 32]).is_err());
+    }
+}
@@ -0,0 +1,163 @@
+use primitive_types::U256;
+
+use crate::curve::babyjubjub::{reduce_scalar, Point};
+use crate::utils::{self, ErrorCode, Map, SolanaError};
+
+/// Largest `max_value` `decrypt` searches, keeping the baby-step table at
+/// 2^20 points.
+pub const MAX_DECRYPT_VALUE: u64 = 1 << 40;
+
+/// EC-ElGamal ciphertext of `value * Base8`: `c1 = r * Base8`, `c2 = value * Base8 + r * pk`.
+///
+/// Ciphertexts are additively homomorphic, and decryption recovers small
+/// values with a baby-step giant-step search bounded by the caller.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Ciphertext {
+    pub c1: Point,
+    pub c2: Point
+}
+
+pub fn encode(value: u64) -> Point {
+    Point::base8().mul_scalar(U256::from(value))
+}
+
+pub fn encrypt(public_key: &Point, value: u64, randomness: U256) -> Result<Ciphertext, SolanaError> {
+    check_public_key(public_key)?;
+    let randomness = check_randomness(randomness)?;
+
+    Ok(Ciphertext {
+        c1: Point::base8().mul_scalar(randomness),
+        c2: encode(value).add(&public_key.mul_scalar(randomness))
+    })
+}
+
+/// Returns `value * Base8` without solving the discrete logarithm.
+pub fn decrypt_point(secret: U256, ciphertext: &Ciphertext) -> Point {
+    ciphertext.c2.sub(&ciphertext.c1.mul_scalar(reduce_scalar(secret)))
+}
+
+/// Decrypts a value known to be at most `max_value`, which may not exceed
+/// `MAX_DECRYPT_VALUE`.
+pub fn decrypt(secret: U256, ciphertext: &Ciphertext, max_value: u64) -> Result<u64, SolanaError> {
+    if max_value > MAX_DECRYPT_VALUE {
+        return Err(utils::err_with_code(ErrorCode::InvalidParameters, "max_value is above the decryption search bound")
+            .with_compared_values(MAX_DECRYPT_VALUE, max_value));
+    }
+    if !ciphertext.c1.is_on_curve() || !ciphertext.c2.is_on_curve() {
+        return Err(utils::err_with_code(ErrorCode::InvalidPoint, "Ciphertext is not on the BabyJubJub curve"));
+    }
+    discrete_log(&decrypt_point(secret, ciphertext), max_value)
+        .ok_or_else(|| utils::err("Decrypted value is out of range"))
+}
+
+/// Produces a fresh-looking encryption of the same value under the same key.
+pub fn rerandomize(public_key: &Point, ciphertext: &Ciphertext, randomness: U256) -> Result<Ciphertext, SolanaError> {
+    let zero = encrypt(public_key, 0, randomness)?;
+    Ok(ciphertext.add(&zero))
+}
+
+impl Ciphertext {
+    pub fn add(&self, other: &Ciphertext) -> Ciphertext {
+        Ciphertext {
+            c1: self.c1.add(&other.c1),
+            c2: self.c2.add(&other.c2)
+        }
+    }
+
+    pub fn compress(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.c1.compress());
+        bytes[32..].copy_from_slice(&self.c2.compress());
+        bytes
+    }
+
+    pub fn decompress(bytes: &[u8; 64]) -> Result<Ciphertext, SolanaError> {
+        let mut c1 = [0u8; 32];
+        let mut c2 = [0u8; 32];
+        c1.copy_from_slice(&bytes[..32]);
+        c2.copy_from_slice(&bytes[32..]);
+
+        Ok(Ciphertext {
+            c1: Point::decompress(&c1)?,
+            c2: Point::decompress(&c2)?
+        })
+    }
+}
+
+fn check_public_key(public_key: &Point) -> Result<(), SolanaError> {
+    if public_key.is_identity() || !public_key.in_subgroup() {
+        return Err(utils::err_with_code(ErrorCode::InvalidPoint, "Public key is not in the BabyJubJub subgroup"));
+    }
+    Ok(())
+}
+
+fn check_randomness(randomness: U256) -> Result<U256, SolanaError> {
+    let randomness = reduce_scalar(randomness);
+    if randomness.is_zero() {
+        return Err(utils::err("Encryption randomness must not be zero"));
+    }
+    Ok(randomness)
+}
+
+// Baby-step giant-step search for `value` with `value * Base8 == point`.
+fn discrete_log(point: &Point, max_value: u64) -> Option<u64> {
+    let steps = ((max_value as f64 + 1.0).sqrt().ceil() as u64).max(1);
+
+    let mut baby_steps = Map::new();
+    let mut current = Point::identity();
+    for j in 0..steps {
+        baby_steps.entry(current.compress()).or_insert(j);
+        current = current.add(&Point::base8());
+    }
+
+    let giant_step = Point::base8().mul_scalar(U256::from(steps)).neg();
+    let mut gamma = *point;
+    for i in 0..steps {
+        if let Some(j) = baby_steps.get(&gamma.compress()) {
+            let value = i * steps + j;
+            return if value <= max_value { Some(value) } else { None };
+        }
+        gamma = gamma.add(&giant_step);
+    }
+    None
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::babyjubjub;
+
+    #[test]
+    fn test_encrypt_decrypt() {
+        let secret = U256::from(123456789u64);
+        let public_key = babyjubjub::public_key(secret);
+
+        for value in [0u64, 1, 77, 999] {
+            let ciphertext = encrypt(&public_key, value, U256::from(42u64 + value)).unwrap();
+            assert_eq!(decrypt(secret, &ciphertext, 1000).unwrap(), value);
+        }
+
+        let ciphertext = encrypt(&public_key, 1001, U256::from(42u64)).unwrap();
+        assert!(decrypt(secret, &ciphertext, 1000).is_err());
+        assert!(encrypt(&Point::generator(), 1, U256::one()).is_err());
+        assert!(encrypt(&public_key, 1, U256::zero()).is_err());
+
+        let ciphertext = encrypt(&public_key, 5, U256::from(42u64)).unwrap();
+        assert_eq!(decrypt(secret, &ciphertext, MAX_DECRYPT_VALUE + 1).unwrap_err().code(), ErrorCode::InvalidParameters);
+        assert_eq!(decrypt(secret, &ciphertext, u64::MAX).unwrap_err().code(), ErrorCode::InvalidParameters);
+    }
+
+    #[test]
+    fn test_homomorphism_and_rerandomize() {
+        let secret = U256::from(987654321u64);
+        let public_key = babyjubjub::public_key(secret);
+        let a = encrypt(&public_key, 30, U256::from(5u64)).unwrap();
+        let b = encrypt(&public_key, 12, U256::from(6u64)).unwrap();
+        assert_eq!(decrypt(secret, &a.add(&b), 100).unwrap(), 42);
+
+        let rerandomized = rerandomize(&public_key, &a, U256::from(7u64)).unwrap();
+        assert_ne!(rerandomized, a);
+        assert_eq!(decrypt(secret, &rerandomized, 100).unwrap(), 30);
+        assert_eq!(Ciphertext::decompress(&rerandomized.compress()).unwrap(), rerandomized);
+    }
+}
@@ -0,0 +1,196 @@
+use std::ops::{Add, Mul, Neg, Sub};
+
+use primitive_types::{U256, U512};
+
+/// BN254 scalar field modulus, which is the base field of BabyJubJub.
+pub const MODULUS: U256 = U256([0x43e1f593f0000001, 0x2833e84879b97091, 0xb85045b68181585d, 0x30644e72e131a029]);
+
+// p - 1 = 2^28 * t with t odd, used by the Tonelli-Shanks square root.
+const TWO_ADICITY: u32 = 28;
+const QUADRATIC_NON_RESIDUE: u64 = 5;
+
+/// Element of the BN254 scalar field, always kept in canonical form (`< MODULUS`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub struct Fr(U256);
+
+impl Fr {
+    pub const fn zero() -> Fr {
+        Fr(U256([0, 0, 0, 0]))
+    }
+
+    pub const fn one() -> Fr {
+        Fr(U256([1, 0, 0, 0]))
+    }
+
+    /// Reduces an arbitrary 256-bit integer into the field.
+    pub fn from_u256(value: U256) -> Fr {
+        Fr(value % MODULUS)
+    }
+
+    /// Returns `None` unless `value` is already canonical.
+    pub fn from_canonical(value: U256) -> Option<Fr> {
+        if value < MODULUS {
+            Some(Fr(value))
+        } else {
+            None
+        }
+    }
+
+    pub fn from_le_bytes(bytes: &[u8; 32]) -> Option<Fr> {
+        Fr::from_canonical(U256::from_little_endian(bytes))
+    }
+
+    pub fn to_u256(self) -> U256 {
+        self.0
+    }
+
+    pub fn to_le_bytes(self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        self.0.to_little_endian(&mut bytes);
+        bytes
+    }
+
+    pub fn is_zero(&self) -> bool {
+        self.0.is_zero()
+    }
+
+    pub fn square(self) -> Fr {
+        self * self
+    }
+
+    pub fn double(self) -> Fr {
+        self + self
+    }
+
+    pub fn pow(self, exponent: U256) -> Fr {
+        let mut result = Fr::one();
+        for i in (0..exponent.bits()).rev() {
+            result = result.square();
+            if exponent.bit(i) {
+                result = result * self;
+            }
+        }
+        result
+    }
+
+    /// Multiplicative inverse, `None` for zero.
+    pub fn inverse(self) -> Option<Fr> {
+        if self.is_zero() {
+            return None;
+        }
+        Some(self.pow(MODULUS - U256::from(2)))
+    }
+
+    pub fn is_quadratic_residue(self) -> bool {
+        self.is_zero() || self.pow((MODULUS - U256::one()) >> 1) == Fr::one()
+    }
+
+    /// Square root via Tonelli-Shanks. Returns the root that is `<= (p - 1) / 2`
+    /// so the result is deterministic.
+    pub fn sqrt(self) -> Option<Fr> {
+        if self.is_zero() {
+            return Some(self);
+        }
+        if !self.is_quadratic_residue() {
+            return None;
+        }
+
+        let trace = (MODULUS - U256::one()) >> TWO_ADICITY;
+        let mut m = TWO_ADICITY;
+        let mut c = Fr::from(QUADRATIC_NON_RESIDUE).pow(trace);
+        let mut t = self.pow(trace);
+        let mut root = self.pow((trace + U256::one()) >> 1);
+
+        while t != Fr::one() {
+            let mut i = 0;
+            let mut t2i = t;
+            while t2i != Fr::one() {
+                t2i = t2i.square();
+                i += 1;
+            }
+            let mut b = c;
+            for _ in 0..(m - i - 1) {
+                b = b.square();
+            }
+            m = i;
+            c = b.square();
+            t = t * c;
+            root = root * b;
+        }
+
+        if root.is_negative() {
+            root = -root;
+        }
+        Some(root)
+    }
+
+    /// Whether the element lies in the upper half of the field, the sign
+    /// convention used by circomlib's point compression.
+    pub fn is_negative(&self) -> bool {
+        self.0 > MODULUS >> 1
+    }
+}
+
+impl From<u64> for Fr {
+    fn from(value: u64) -> Fr {
+        Fr::from_u256(U256::from(value))
+    }
+}
+
+impl From<u128> for Fr {
+    fn from(value: u128) -> Fr {
+        Fr::from_u256(U256::from(value))
+    }
+}
+
+impl Add for Fr {
+    type Output = Fr;
+
+    fn add(self, other: Fr) -> Fr {
+        let (sum, overflow) = self.0.overflowing_add(other.0);
+        if overflow || sum >= MODULUS {
+            Fr(sum.overflowing_sub(MODULUS).0)
+        } else {
+            Fr(sum)
+        }
+    }
+}
+
+impl Sub for Fr {
+    type Output = Fr;
+
+    fn sub(self, other: Fr) -> Fr {
+        if self.0 >= other.0 {
+            Fr(self.0 - other.0)
+        } else {
+            Fr(MODULUS - (other.0 - self.0))
+        }
+    }
+}
+
+impl Neg for Fr {
+    type Output = Fr;
+
+    fn neg(self) -> Fr {
+        Fr::zero() - self
+    }
+}
+
+impl Mul for Fr {
+    type Output = Fr;
+
+    fn mul(self, other: Fr) -> Fr {
+        Fr(mul_mod(self.0, other.0, MODULUS))
+    }
+}
+
+/// `a * b mod modulus` through a 512-bit intermediate product, or through the
+/// installed precompile with the `precompile` feature.
+pub fn mul_mod(a: U256, b: U256, modulus: U256) -> U256 {
+    #[cfg(feature = "precompile")]
+    if let Some(accelerated) = crate::precompile::mul_mod() {
+        return accelerated(a, b, modulus);
+    }
+    let product = a.full_mul(b) % U512::from(modulus);
+    U256::try_from(product).expect("Reduced product fits in 256 bits")
+}
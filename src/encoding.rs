@@ -0,0 +1,34 @@
+#[cfg(feature = "base58")]
+pub mod base58;
+#[cfg(feature = "base64")]
+pub mod base64;
+#[cfg(feature = "bech32")]
+pub mod bech32;
+#[cfg(feature = "num-bigint")]
+pub mod bigint;
+pub mod bytes32;
+pub mod hex_utils;
+#[cfg(feature = "rlp")]
+pub mod rlp;
+#[cfg(feature = "rmp")]
+pub mod rmp;
+#[cfg(feature = "ssz")]
+pub mod ssz;
+#[cfg(feature = "u256")]
+pub mod u256;
+
+use crate::constants::in_field;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// Field value from exactly 16 big-endian bytes, as the text encodings carry
+/// commitments and other field values.
+pub fn field_from_slice(bytes: &[u8]) -> Result<u128, SolanaError> {
+    let bytes: [u8; 16] = bytes
+        .try_into()
+        .map_err(|_| utils::err_with_code(ErrorCode::InvalidEncoding, format!("Field value must be 16 bytes, got {}", bytes.len())))?;
+    let value = u128::from_be_bytes(bytes);
+    if !in_field(value) {
+        return Err(utils::err_with_code(ErrorCode::FieldOverflow, "Encoded value is outside the field"));
+    }
+    Ok(value)
+}
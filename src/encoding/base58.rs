@@ -0,0 +1,64 @@
+use crate::encoding::field_from_slice;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+fn decode_error(e: bs58::decode::Error) -> SolanaError {
+    let code = match e {
+        bs58::decode::Error::InvalidChecksum { .. } | bs58::decode::Error::NoChecksum => ErrorCode::InvalidChecksum,
+        _ => ErrorCode::InvalidEncoding
+    };
+    utils::err_with_code(code, format!("Invalid base58: {}", e))
+}
+
+/// Bitcoin alphabet, the one Solana uses for keys and signatures.
+pub fn encode(data: &[u8]) -> String {
+    bs58::encode(data).into_string()
+}
+
+pub fn decode(value: &str) -> Result<Vec<u8>, SolanaError> {
+    bs58::decode(value).into_vec().map_err(decode_error)
+}
+
+/// Base58Check: the payload followed by the first four bytes of its double
+/// SHA-256.
+pub fn encode_check(data: &[u8]) -> String {
+    bs58::encode(data).with_check().into_string()
+}
+
+pub fn decode_check(value: &str) -> Result<Vec<u8>, SolanaError> {
+    bs58::decode(value).with_check(None).into_vec().map_err(decode_error)
+}
+
+/// A commitment or other field value as its 16 big-endian bytes.
+pub fn encode_field(value: u128) -> String {
+    encode(&value.to_be_bytes())
+}
+
+pub fn decode_field(value: &str) -> Result<u128, SolanaError> {
+    field_from_slice(&decode(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::note::Note;
+
+    #[test]
+    fn test_base58() {
+        assert_eq!(encode(b"hello world"), "StV1DL6CwTryKyV");
+        assert_eq!(decode("StV1DL6CwTryKyV").unwrap(), b"hello world");
+        assert_eq!(encode(&[0, 0, 1]), "112");
+        assert_eq!(decode("0OIl").unwrap_err().code(), ErrorCode::InvalidEncoding);
+
+        let note = Note::new(11, 22).unwrap();
+        let checked = encode_check(&note.to_bytes());
+        assert_eq!(decode_check(&checked).unwrap(), note.to_bytes());
+        let mut corrupted = decode(&checked).unwrap();
+        corrupted[0] ^= 1;
+        assert_eq!(decode_check(&encode(&corrupted)).unwrap_err().code(), ErrorCode::InvalidChecksum);
+
+        let commitment = note.commitment();
+        assert_eq!(decode_field(&encode_field(commitment)).unwrap(), commitment);
+        assert_eq!(decode_field(&encode_field(u128::MAX)).unwrap_err().code(), ErrorCode::FieldOverflow);
+        assert_eq!(decode_field(&encode(&[1, 2])).unwrap_err().code(), ErrorCode::InvalidEncoding);
+    }
+}
@@ -0,0 +1,75 @@
+use base64::engine::general_purpose::{STANDARD, STANDARD_NO_PAD, URL_SAFE, URL_SAFE_NO_PAD};
+use base64::Engine;
+use borsh::BorshDeserialize;
+
+use crate::merkle_tree::MerkleProof;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Variant {
+    Standard,
+    StandardNoPad,
+    /// `-` and `_` instead of `+` and `/`, safe in URLs and file names.
+    UrlSafe,
+    UrlSafeNoPad
+}
+
+impl Variant {
+    fn engine(self) -> &'static base64::engine::GeneralPurpose {
+        match self {
+            Variant::Standard => &STANDARD,
+            Variant::StandardNoPad => &STANDARD_NO_PAD,
+            Variant::UrlSafe => &URL_SAFE,
+            Variant::UrlSafeNoPad => &URL_SAFE_NO_PAD
+        }
+    }
+}
+
+pub fn encode(data: &[u8], variant: Variant) -> String {
+    variant.engine().encode(data)
+}
+
+pub fn decode(value: &str, variant: Variant) -> Result<Vec<u8>, SolanaError> {
+    variant
+        .engine()
+        .decode(value)
+        .map_err(|e| utils::err_with_code(ErrorCode::InvalidEncoding, format!("Invalid base64: {}", e)))
+}
+
+/// Borsh bytes of the proof in unpadded URL-safe base64, short enough for a
+/// deep link query parameter at the usual depths.
+pub fn encode_proof(proof: &MerkleProof) -> String {
+    encode(&borsh::to_vec(proof).expect("Writing to a Vec cannot fail"), Variant::UrlSafeNoPad)
+}
+
+pub fn decode_proof(value: &str) -> Result<MerkleProof, SolanaError> {
+    let bytes = decode(value, Variant::UrlSafeNoPad)?;
+    MerkleProof::try_from_slice(&bytes)
+        .map_err(|e| utils::err_with_code(ErrorCode::InvalidEncoding, format!("Invalid proof bytes: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_base64() {
+        let data = [0xfb, 0xff, 0x01];
+        assert_eq!(encode(&data, Variant::Standard), "+/8B");
+        assert_eq!(encode(&data, Variant::UrlSafe), "-_8B");
+        assert_eq!(encode(b"ab", Variant::Standard), "YWI=");
+        assert_eq!(encode(b"ab", Variant::StandardNoPad), "YWI");
+        assert_eq!(decode("YWI=", Variant::Standard).unwrap(), b"ab");
+        assert_eq!(decode("-_8B", Variant::Standard).unwrap_err().code(), ErrorCode::InvalidEncoding);
+
+        let mut tree = MerkleTree::new(4);
+        tree.insert(11).unwrap();
+        tree.insert(22).unwrap();
+        let proof = tree.proof(1).unwrap();
+        let encoded = encode_proof(&proof);
+        assert!(!encoded.contains(['+', '/', '=']));
+        assert_eq!(decode_proof(&encoded).unwrap(), proof);
+        assert!(decode_proof(&encoded[..8]).is_err());
+    }
+}
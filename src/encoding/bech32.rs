@@ -0,0 +1,97 @@
+use bech32::{Bech32m, Hrp};
+
+use crate::curve::babyjubjub::Point;
+use crate::encoding::field_from_slice;
+use crate::stealth::MetaAddress;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+pub const COMMITMENT_HRP: &str = "stcm";
+pub const META_ADDRESS_HRP: &str = "stmeta";
+
+/// Bech32m string of `data` under the human-readable prefix `hrp`.
+pub fn encode(hrp: &str, data: &[u8]) -> Result<String, SolanaError> {
+    let hrp = Hrp::parse(hrp).map_err(|e| utils::err_with_code(ErrorCode::InvalidParameters, format!("Invalid bech32 prefix: {}", e)))?;
+    bech32::encode::<Bech32m>(hrp, data).map_err(|e| utils::err_with_code(ErrorCode::InvalidParameters, format!("Cannot encode as bech32: {}", e)))
+}
+
+/// Prefix and payload of a bech32 or bech32m string. The prefix is returned
+/// in lowercase.
+pub fn decode(value: &str) -> Result<(String, Vec<u8>), SolanaError> {
+    let (hrp, data) = bech32::decode(value).map_err(|e| {
+        let code = match e {
+            bech32::DecodeError::Checksum(_) => ErrorCode::InvalidChecksum,
+            _ => ErrorCode::InvalidEncoding
+        };
+        utils::err_with_code(code, format!("Invalid bech32: {}", e))
+    })?;
+    Ok((hrp.to_lowercase(), data))
+}
+
+fn decode_with_prefix(value: &str, expected: &'static str) -> Result<Vec<u8>, SolanaError> {
+    let (hrp, data) = decode(value)?;
+    if hrp != expected {
+        return Err(utils::err_with_code(ErrorCode::InvalidEncoding, format!("Expected prefix {} but found {}", expected, hrp)));
+    }
+    Ok(data)
+}
+
+pub fn encode_commitment(commitment: u128) -> String {
+    encode(COMMITMENT_HRP, &commitment.to_be_bytes()).expect("A 16-byte payload fits bech32m")
+}
+
+pub fn decode_commitment(value: &str) -> Result<u128, SolanaError> {
+    field_from_slice(&decode_with_prefix(value, COMMITMENT_HRP)?)
+}
+
+/// Compressed spending key followed by the compressed viewing key.
+pub fn encode_meta_address(meta_address: &MetaAddress) -> String {
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(&meta_address.spending_public_key.compress());
+    data[32..].copy_from_slice(&meta_address.viewing_public_key.compress());
+    encode(META_ADDRESS_HRP, &data).expect("A 64-byte payload fits bech32m")
+}
+
+pub fn decode_meta_address(value: &str) -> Result<MetaAddress, SolanaError> {
+    let data: [u8; 64] = decode_with_prefix(value, META_ADDRESS_HRP)?
+        .try_into()
+        .map_err(|_| utils::err_with_code(ErrorCode::InvalidEncoding, "Meta-address must be 64 bytes"))?;
+    Ok(MetaAddress {
+        spending_public_key: Point::decompress(data[..32].try_into().unwrap())?,
+        viewing_public_key: Point::decompress(data[32..].try_into().unwrap())?
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::stealth::ViewingKey;
+    use primitive_types::U256;
+
+    #[test]
+    fn test_bech32() {
+        let encoded = encode("abcdef", &[0xff, 0xff]).unwrap();
+        assert_eq!(decode(&encoded).unwrap(), ("abcdef".to_string(), vec![0xff, 0xff]));
+        assert_eq!(decode(&encoded.to_uppercase()).unwrap().0, "abcdef");
+        assert!(encode("", &[1]).is_err());
+
+        let commitment = 0x1234_5678_9abc_def0u128;
+        let encoded = encode_commitment(commitment);
+        assert!(encoded.starts_with("stcm1"));
+        assert_eq!(decode_commitment(&encoded).unwrap(), commitment);
+
+        let mut typo = encoded.into_bytes();
+        let last = typo.len() - 1;
+        typo[last] = if typo[last] == b'q' { b'p' } else { b'q' };
+        let typo = String::from_utf8(typo).unwrap();
+        assert_eq!(decode_commitment(&typo).unwrap_err().code(), ErrorCode::InvalidChecksum);
+
+        let meta_address = MetaAddress {
+            spending_public_key: ViewingKey::from_scalar(U256::from(7)).public_key(),
+            viewing_public_key: ViewingKey::from_scalar(U256::from(11)).public_key()
+        };
+        let encoded = encode_meta_address(&meta_address);
+        assert!(encoded.starts_with("stmeta1"));
+        assert_eq!(decode_meta_address(&encoded).unwrap(), meta_address);
+        assert_eq!(decode_commitment(&encoded).unwrap_err().code(), ErrorCode::InvalidEncoding);
+    }
+}
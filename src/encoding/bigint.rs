@@ -0,0 +1,81 @@
+use num_bigint::BigUint;
+use primitive_types::U256;
+
+use crate::constants::{in_field, FIELD_SIZE};
+use crate::curve::field::{Fr, MODULUS};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+fn u256_to_biguint(value: U256) -> BigUint {
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    BigUint::from_bytes_le(&bytes)
+}
+
+fn modulus() -> BigUint {
+    u256_to_biguint(MODULUS)
+}
+
+pub fn field_to_biguint(value: u128) -> BigUint {
+    BigUint::from(value)
+}
+
+/// Checked conversion to a leaf or root value: `FieldOverflow` unless the
+/// integer is already below `FIELD_SIZE`.
+pub fn field_from_biguint(value: &BigUint) -> Result<u128, SolanaError> {
+    u128::try_from(value)
+        .ok()
+        .filter(|value| in_field(*value))
+        .ok_or_else(|| utils::err_with_code(ErrorCode::FieldOverflow, "Integer is outside the field"))
+}
+
+/// Any integer reduced into `FIELD_SIZE`.
+pub fn reduce_to_field(value: &BigUint) -> u128 {
+    u128::try_from(value % BigUint::from(FIELD_SIZE)).expect("The remainder is below FIELD_SIZE")
+}
+
+/// Checked conversion to a BN254 scalar, rejecting values at or above the
+/// modulus.
+pub fn fr_from_biguint(value: &BigUint) -> Result<Fr, SolanaError> {
+    if value >= &modulus() {
+        return Err(utils::err_with_code(ErrorCode::FieldOverflow, "Integer is not below the BN254 modulus"));
+    }
+    Ok(Fr::from_u256(U256::from_little_endian(&value.to_bytes_le())))
+}
+
+impl From<Fr> for BigUint {
+    fn from(value: Fr) -> BigUint {
+        u256_to_biguint(value.to_u256())
+    }
+}
+
+/// Reduces modulo the BN254 modulus, like `Fr::from_u256`. Use
+/// `fr_from_biguint` to reject non-canonical input instead.
+impl From<BigUint> for Fr {
+    fn from(value: BigUint) -> Fr {
+        Fr::from_u256(U256::from_little_endian(&(value % modulus()).to_bytes_le()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_bigint() {
+        let value = BigUint::from(12345u32);
+        assert_eq!(field_from_biguint(&value).unwrap(), 12345);
+        assert_eq!(field_to_biguint(12345), value);
+        let large = BigUint::from(u128::MAX) * 3u32 + 7u32;
+        assert_eq!(field_from_biguint(&large).unwrap_err().code(), ErrorCode::FieldOverflow);
+        assert_eq!(field_from_biguint(&BigUint::from(u128::MAX)).unwrap_err().code(), ErrorCode::FieldOverflow);
+        assert_eq!(reduce_to_field(&large), 7);
+
+        let fr = Fr::from(98765u128);
+        assert_eq!(BigUint::from(fr), BigUint::from(98765u32));
+        assert_eq!(Fr::from(BigUint::from(fr)), fr);
+        assert_eq!(Fr::from(modulus() + 5u32), Fr::from(5u64));
+        assert_eq!(fr_from_biguint(&BigUint::from(5u32)).unwrap(), Fr::from(5u64));
+        assert!(fr_from_biguint(&modulus()).is_err());
+        assert_eq!(fr_from_biguint(&(modulus() - 1u32)).unwrap(), -Fr::one());
+    }
+}
@@ -0,0 +1,103 @@
+use primitive_types::U256;
+
+use crate::constants::in_field;
+use crate::curve::field::Fr;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// Zero-padded on the left, as Solidity stores a `uint128` in a `bytes32`.
+pub fn u128_to_bytes32_be(value: u128) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[16..].copy_from_slice(&value.to_be_bytes());
+    bytes
+}
+
+/// Zero-padded on the right.
+pub fn u128_to_bytes32_le(value: u128) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    bytes[..16].copy_from_slice(&value.to_le_bytes());
+    bytes
+}
+
+/// Inverse of `u128_to_bytes32_be`. Fails with `FieldOverflow` instead of
+/// truncating when any of the high 16 bytes is set or the value is outside
+/// the field.
+pub fn bytes32_to_u128(bytes: &[u8; 32]) -> Result<u128, SolanaError> {
+    field_from_halves(&bytes[..16], bytes[16..].try_into().unwrap(), u128::from_be_bytes)
+}
+
+/// Inverse of `u128_to_bytes32_le`, with the same checks.
+pub fn bytes32_le_to_u128(bytes: &[u8; 32]) -> Result<u128, SolanaError> {
+    field_from_halves(&bytes[16..], bytes[..16].try_into().unwrap(), u128::from_le_bytes)
+}
+
+fn field_from_halves(high: &[u8], low: [u8; 16], read: fn([u8; 16]) -> u128) -> Result<u128, SolanaError> {
+    if high.iter().any(|byte| *byte != 0) {
+        return Err(utils::err_with_code(ErrorCode::FieldOverflow, "bytes32 value does not fit in 128 bits"));
+    }
+    let value = read(low);
+    if !in_field(value) {
+        return Err(utils::err_with_code(ErrorCode::FieldOverflow, "bytes32 value is outside the field"));
+    }
+    Ok(value)
+}
+
+pub fn u256_to_bytes32_be(value: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    value.to_big_endian(&mut bytes);
+    bytes
+}
+
+pub fn u256_to_bytes32_le(value: U256) -> [u8; 32] {
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    bytes
+}
+
+pub fn fr_to_bytes32_be(value: Fr) -> [u8; 32] {
+    u256_to_bytes32_be(value.to_u256())
+}
+
+pub fn fr_to_bytes32_le(value: Fr) -> [u8; 32] {
+    value.to_le_bytes()
+}
+
+/// BN254 scalar from a big-endian `bytes32`, rejecting non-canonical values
+/// rather than reducing them.
+pub fn bytes32_to_fr(bytes: &[u8; 32]) -> Result<Fr, SolanaError> {
+    Fr::from_canonical(U256::from_big_endian(bytes)).ok_or_else(|| utils::err_with_code(ErrorCode::FieldOverflow, "bytes32 value is not below the BN254 modulus"))
+}
+
+pub fn bytes32_le_to_fr(bytes: &[u8; 32]) -> Result<Fr, SolanaError> {
+    Fr::from_le_bytes(bytes).ok_or_else(|| utils::err_with_code(ErrorCode::FieldOverflow, "bytes32 value is not below the BN254 modulus"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::field::MODULUS;
+
+    #[test]
+    fn test_bytes32() {
+        let value = 0x0102_0304_0506_0708_090a_0b0c_0d0e_0f10u128;
+        let be = u128_to_bytes32_be(value);
+        assert_eq!(be[15], 0);
+        assert_eq!(be[16], 0x01);
+        assert_eq!(be[31], 0x10);
+        assert_eq!(bytes32_to_u128(&be).unwrap(), value);
+        let le = u128_to_bytes32_le(value);
+        assert_eq!(le[0], 0x10);
+        assert_eq!(bytes32_le_to_u128(&le).unwrap(), value);
+
+        // Reading with the wrong endianness puts bytes in the high half.
+        assert_eq!(bytes32_to_u128(&le).unwrap_err().code(), ErrorCode::FieldOverflow);
+        assert_eq!(bytes32_le_to_u128(&be).unwrap_err().code(), ErrorCode::FieldOverflow);
+        assert!(bytes32_to_u128(&u128_to_bytes32_be(u128::MAX)).is_err());
+
+        let fr = Fr::from(value);
+        assert_eq!(fr_to_bytes32_be(fr), be);
+        assert_eq!(bytes32_to_fr(&be).unwrap(), fr);
+        assert_eq!(bytes32_le_to_fr(&fr_to_bytes32_le(fr)).unwrap(), fr);
+        assert_eq!(u256_to_bytes32_le(U256::from(value)), le);
+        assert!(bytes32_to_fr(&u256_to_bytes32_be(MODULUS)).is_err());
+    }
+}
@@ -0,0 +1,74 @@
+use crate::utils::{self, ErrorCode, SolanaError};
+
+const DIGITS: &[u8; 16] = b"0123456789abcdef";
+
+fn nibble(digit: u8) -> Option<u8> {
+    match digit {
+        b'0'..=b'9' => Some(digit - b'0'),
+        b'a'..=b'f' => Some(digit - b'a' + 10),
+        b'A'..=b'F' => Some(digit - b'A' + 10),
+        _ => None
+    }
+}
+
+/// Writes lowercase hex for `bytes` into the start of `out` and returns the
+/// number of bytes written. Nothing is written when `out` is too short.
+pub fn encode_hex_into(bytes: &[u8], out: &mut [u8]) -> Result<usize, SolanaError> {
+    let len = bytes.len() * 2;
+    let out = out.get_mut(..len).ok_or_else(|| utils::err_with_code(ErrorCode::InvalidParameters, "Output buffer is too short for the hex"))?;
+    for (byte, pair) in bytes.iter().zip(out.chunks_exact_mut(2)) {
+        pair[0] = DIGITS[(byte >> 4) as usize];
+        pair[1] = DIGITS[(byte & 0x0f) as usize];
+    }
+    Ok(len)
+}
+
+/// Decodes hex (either case, optional `0x`) into the start of `out` and
+/// returns the number of bytes written.
+pub fn decode_hex_into(hex: &[u8], out: &mut [u8]) -> Result<usize, SolanaError> {
+    let hex = hex.strip_prefix(b"0x").unwrap_or(hex);
+    if !hex.len().is_multiple_of(2) {
+        return Err(utils::err_with_code(ErrorCode::InvalidEncoding, "Hex has an odd number of digits"));
+    }
+    let len = hex.len() / 2;
+    let out = out.get_mut(..len).ok_or_else(|| utils::err_with_code(ErrorCode::InvalidParameters, "Output buffer is too short for the bytes"))?;
+    for (byte, pair) in out.iter_mut().zip(hex.chunks_exact(2)) {
+        match (nibble(pair[0]), nibble(pair[1])) {
+            (Some(high), Some(low)) => *byte = (high << 4) | low,
+            _ => return Err(utils::err_with_code(ErrorCode::InvalidEncoding, "Hex contains a non-hex digit"))
+        }
+    }
+    Ok(len)
+}
+
+/// A root or other field value as 32 hex digits, big-endian.
+pub fn encode_field_into(value: u128, out: &mut [u8]) -> Result<usize, SolanaError> {
+    encode_hex_into(&value.to_be_bytes(), out)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hex_into() {
+        let mut out = [0u8; 8];
+        assert_eq!(encode_hex_into(&[0x01, 0xab, 0xff], &mut out).unwrap(), 6);
+        assert_eq!(&out[..6], b"01abff");
+        assert_eq!(encode_hex_into(&[0; 5], &mut out).unwrap_err().code(), ErrorCode::InvalidParameters);
+
+        let mut bytes = [0u8; 4];
+        assert_eq!(decode_hex_into(b"0x01ABff", &mut bytes).unwrap(), 3);
+        assert_eq!(bytes[..3], [0x01, 0xab, 0xff]);
+        assert_eq!(decode_hex_into(b"abc", &mut bytes).unwrap_err().code(), ErrorCode::InvalidEncoding);
+        assert_eq!(decode_hex_into(b"zz", &mut bytes).unwrap_err().code(), ErrorCode::InvalidEncoding);
+        assert!(decode_hex_into(b"0011223344", &mut bytes).is_err());
+
+        let mut root = [0u8; 32];
+        encode_field_into(0x1234, &mut root).unwrap();
+        assert_eq!(&root, format!("{:032x}", 0x1234).as_bytes());
+        let mut decoded = [0u8; 16];
+        decode_hex_into(&root, &mut decoded).unwrap();
+        assert_eq!(u128::from_be_bytes(decoded), 0x1234);
+    }
+}
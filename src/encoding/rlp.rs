@@ -0,0 +1,45 @@
+use alloy_rlp::{Decodable, Encodable};
+
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// RLP bytes of a proof, deposit event or any other `Encodable` value. Proofs
+/// encode as `[leaf, leaf_index, [path_elements...], [path_indices...]]`
+/// with minimal big-endian integers, and an event's root is omitted when
+/// absent.
+pub fn encode<T: Encodable>(value: &T) -> Vec<u8> {
+    alloy_rlp::encode(value)
+}
+
+/// Decodes a single value and rejects trailing bytes.
+pub fn decode<T: Decodable>(bytes: &[u8]) -> Result<T, SolanaError> {
+    alloy_rlp::decode_exact(bytes).map_err(|e| utils::err_with_code(ErrorCode::InvalidEncoding, format!("Invalid RLP: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::replay::DepositEvent;
+    use crate::merkle_tree::{MerkleProof, MerkleTree};
+
+    #[test]
+    fn test_rlp() {
+        let proof = MerkleProof { leaf: 1, leaf_index: 0, path_elements: vec![0x0400], path_indices: vec![0] };
+        assert_eq!(encode(&proof), [0xc8, 0x01, 0x80, 0xc3, 0x82, 0x04, 0x00, 0xc1, 0x80]);
+
+        let mut tree = MerkleTree::new(4);
+        tree.insert(11).unwrap();
+        tree.insert(22).unwrap();
+        let proof = tree.proof(1).unwrap();
+        let bytes = encode(&proof);
+        assert_eq!(decode::<MerkleProof>(&bytes).unwrap(), proof);
+        assert!(decode::<MerkleProof>(&bytes[..bytes.len() - 1]).is_err());
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert_eq!(decode::<MerkleProof>(&trailing).unwrap_err().code(), ErrorCode::InvalidEncoding);
+
+        let event = DepositEvent { commitment: 22, leaf_index: 1, timestamp: 1_700_000_001, root: Some(tree.get_last_root()) };
+        assert_eq!(decode::<DepositEvent>(&encode(&event)).unwrap(), event);
+        let unrooted = DepositEvent { root: None, ..event };
+        assert_eq!(decode::<DepositEvent>(&encode(&unrooted)).unwrap(), unrooted);
+    }
+}
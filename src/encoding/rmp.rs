@@ -0,0 +1,45 @@
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// MessagePack with field names, so queued items stay readable by other
+/// MessagePack clients and survive fields being added. Field values are
+/// 16-byte big-endian binaries.
+pub fn encode<T: Serialize>(value: &T) -> Result<Vec<u8>, SolanaError> {
+    rmp_serde::to_vec_named(value).map_err(|e| utils::err_with_code(ErrorCode::InvalidEncoding, format!("Cannot encode as MessagePack: {}", e)))
+}
+
+pub fn decode<T: DeserializeOwned>(bytes: &[u8]) -> Result<T, SolanaError> {
+    rmp_serde::from_slice(bytes).map_err(|e| utils::err_with_code(ErrorCode::InvalidEncoding, format!("Invalid MessagePack: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleProof;
+    use crate::pool::{Pool, PoolState};
+
+    #[test]
+    fn test_rmp() {
+        let proof = MerkleProof { leaf: 1, leaf_index: 2, path_elements: vec![u128::MAX - 1], path_indices: vec![1] };
+        let bytes = encode(&proof).unwrap();
+        assert_eq!(bytes[0], 0x84);
+        assert!(bytes.windows(4).any(|window| window == b"leaf"));
+        assert_eq!(decode::<MerkleProof>(&bytes).unwrap(), proof);
+        assert_eq!(decode::<MerkleProof>(&bytes[..bytes.len() - 1]).unwrap_err().code(), ErrorCode::InvalidEncoding);
+
+        let mut pool = Pool::new(4);
+        pool.deposit(11).unwrap();
+        pool.deposit(22).unwrap();
+        let root = pool.tree().get_last_root();
+        pool.withdraw(33, root).unwrap();
+
+        let state: PoolState = decode(&encode(&pool.state()).unwrap()).unwrap();
+        assert_eq!(state, pool.state());
+        let mut restored = Pool::from_state(state).unwrap();
+        assert_eq!(restored.tree().get_last_root(), root);
+        assert!(restored.is_spent(33));
+        assert!(restored.deposit(22).is_err());
+    }
+}
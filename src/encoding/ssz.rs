@@ -0,0 +1,194 @@
+use sha2::{Digest, Sha256};
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+
+use crate::merkle_tree::{MerkleProof, MerkleTree};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// List limit for proof paths: a `u64` leaf index cannot address a deeper
+/// tree.
+pub const PROOF_DEPTH_LIMIT: usize = 64;
+/// List limit for snapshot leaves.
+pub const SNAPSHOT_LEAVES_LIMIT: u128 = 1 << 64;
+
+pub type Chunk = [u8; 32];
+
+pub fn encode<T: Encode>(value: &T) -> Vec<u8> {
+    value.as_ssz_bytes()
+}
+
+pub fn decode<T: Decode>(bytes: &[u8]) -> Result<T, SolanaError> {
+    T::from_ssz_bytes(bytes).map_err(|e| utils::err_with_code(ErrorCode::InvalidEncoding, format!("Invalid SSZ: {:?}", e)))
+}
+
+/// `hash_tree_root` as defined by the SSZ spec, with SHA-256. Fails when a
+/// list is longer than its SSZ limit.
+pub trait HashTreeRoot {
+    fn hash_tree_root(&self) -> Result<Chunk, SolanaError>;
+}
+
+/// Leaves of a tree with the root they produce, as an SSZ container
+/// `{levels: uint8, leaves: List[uint128, 2**64], root: uint128}`.
+#[derive(Debug, Clone, PartialEq, Eq, Encode, Decode)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TreeSnapshot {
+    pub levels: u8,
+    pub leaves: Vec<u128>,
+    pub root: u128
+}
+
+impl TreeSnapshot {
+    pub fn from_tree(tree: &MerkleTree) -> Result<TreeSnapshot, SolanaError> {
+        if tree.frontier_only() {
+            return Err(utils::err_with_code(ErrorCode::LeavesNotRetained, "A frontier-only tree has no leaves to snapshot"));
+        }
+        Ok(TreeSnapshot { levels: tree.levels(), leaves: tree.leaves().to_vec(), root: tree.get_last_root() })
+    }
+
+    /// Rebuilds the tree and checks it against the recorded root.
+    pub fn to_tree(&self) -> Result<MerkleTree, SolanaError> {
+        if self.levels == 0 {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Snapshot tree has no levels"));
+        }
+        let mut tree = MerkleTree::new(self.levels);
+        if self.leaves.len() as u128 > tree.capacity() {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Snapshot has more leaves than the tree can hold"));
+        }
+        tree.insert_many(self.leaves.iter().copied())?;
+        if tree.get_last_root() != self.root {
+            return Err(utils::err_with_code(ErrorCode::UnknownRoot, "Snapshot leaves do not produce the recorded root"));
+        }
+        Ok(tree)
+    }
+}
+
+fn hash(left: &Chunk, right: &Chunk) -> Chunk {
+    let mut hasher = Sha256::new();
+    hasher.update(left);
+    hasher.update(right);
+    hasher.finalize().into()
+}
+
+/// Little-endian values packed into 32-byte chunks, the last one zero-padded.
+pub fn pack(bytes: &[u8]) -> Vec<Chunk> {
+    bytes
+        .chunks(32)
+        .map(|part| {
+            let mut chunk = [0u8; 32];
+            chunk[..part.len()].copy_from_slice(part);
+            chunk
+        })
+        .collect()
+}
+
+/// Root of `chunks` padded with zero chunks to the next power of two of
+/// `limit`, without materialising the padding.
+pub fn merkleize(chunks: &[Chunk], limit: u128) -> Result<Chunk, SolanaError> {
+    if chunks.len() as u128 > limit {
+        return Err(utils::err_with_code(ErrorCode::InvalidParameters, "More chunks than the list limit"));
+    }
+    let depth = limit.max(1).next_power_of_two().trailing_zeros();
+    let mut zero = [0u8; 32];
+    let mut layer = chunks.to_vec();
+    for _ in 0..depth {
+        if layer.len() % 2 == 1 {
+            layer.push(zero);
+        }
+        layer = layer.chunks(2).map(|pair| hash(&pair[0], &pair[1])).collect();
+        zero = hash(&zero, &zero);
+    }
+    Ok(layer.first().copied().unwrap_or(zero))
+}
+
+pub fn mix_in_length(root: &Chunk, length: usize) -> Chunk {
+    let mut chunk = [0u8; 32];
+    chunk[..8].copy_from_slice(&(length as u64).to_le_bytes());
+    hash(root, &chunk)
+}
+
+fn list_root(bytes: &[u8], item_size: u128, length: usize, limit: u128) -> Result<Chunk, SolanaError> {
+    if length as u128 > limit {
+        return Err(utils::err_with_code(ErrorCode::InvalidParameters, "List is longer than its SSZ limit"));
+    }
+    let root = merkleize(&pack(bytes), (limit * item_size).div_ceil(32))?;
+    Ok(mix_in_length(&root, length))
+}
+
+fn basic_root(bytes: &[u8]) -> Chunk {
+    pack(bytes)[0]
+}
+
+impl HashTreeRoot for MerkleProof {
+    /// `{leaf: uint128, leaf_index: uint64, path_elements: List[uint128, 64],
+    /// path_indices: List[uint8, 64]}`. Paths deeper than the limit have no
+    /// root.
+    fn hash_tree_root(&self) -> Result<Chunk, SolanaError> {
+        let elements: Vec<u8> = self.path_elements.iter().flat_map(|value| value.to_le_bytes()).collect();
+        let fields = [
+            basic_root(&self.leaf.to_le_bytes()),
+            basic_root(&self.leaf_index.to_le_bytes()),
+            list_root(&elements, 16, self.path_elements.len(), PROOF_DEPTH_LIMIT as u128)?,
+            list_root(&self.path_indices, 1, self.path_indices.len(), PROOF_DEPTH_LIMIT as u128)?
+        ];
+        merkleize(&fields, fields.len() as u128)
+    }
+}
+
+impl HashTreeRoot for TreeSnapshot {
+    fn hash_tree_root(&self) -> Result<Chunk, SolanaError> {
+        let leaves: Vec<u8> = self.leaves.iter().flat_map(|value| value.to_le_bytes()).collect();
+        let fields = [
+            basic_root(&[self.levels]),
+            list_root(&leaves, 16, self.leaves.len(), SNAPSHOT_LEAVES_LIMIT)?,
+            basic_root(&self.root.to_le_bytes())
+        ];
+        merkleize(&fields, fields.len() as u128)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_ssz() {
+        let proof = MerkleProof { leaf: 1, leaf_index: 2, path_elements: vec![3], path_indices: vec![1] };
+        let bytes = encode(&proof);
+        assert_eq!(bytes.len(), 32 + 16 + 1);
+        assert_eq!(bytes[24..32], [32, 0, 0, 0, 48, 0, 0, 0]);
+        assert_eq!(decode::<MerkleProof>(&bytes).unwrap(), proof);
+        assert_eq!(decode::<MerkleProof>(&bytes[..40]).unwrap_err().code(), ErrorCode::InvalidEncoding);
+
+        let a = [1u8; 32];
+        let b = [2u8; 32];
+        assert_eq!(merkleize(&[a], 1).unwrap(), a);
+        assert_eq!(merkleize(&[a, b], 2).unwrap(), hash(&a, &b));
+        assert_eq!(merkleize(&[a], 4).unwrap(), hash(&hash(&a, &[0; 32]), &hash(&[0; 32], &[0; 32])));
+        assert_eq!(merkleize(&[], 2).unwrap(), hash(&[0; 32], &[0; 32]));
+        assert!(merkleize(&[a, b], 1).is_err());
+        assert_eq!(pack(&[7u8; 33]).len(), 2);
+
+        let mut tree = MerkleTree::new(4);
+        tree.insert_many([11, 22, 33]).unwrap();
+        let snapshot = TreeSnapshot::from_tree(&tree).unwrap();
+        let restored: TreeSnapshot = decode(&encode(&snapshot)).unwrap();
+        assert_eq!(restored.to_tree().unwrap().get_last_root(), tree.get_last_root());
+        assert_eq!(restored.hash_tree_root().unwrap(), snapshot.hash_tree_root().unwrap());
+
+        let mut forged = snapshot.clone();
+        forged.leaves[0] = 12;
+        assert_ne!(forged.hash_tree_root().unwrap(), snapshot.hash_tree_root().unwrap());
+        assert_eq!(forged.to_tree().unwrap_err().code(), ErrorCode::UnknownRoot);
+        let flat = TreeSnapshot { levels: 0, ..snapshot.clone() };
+        assert_eq!(flat.to_tree().unwrap_err().code(), ErrorCode::InvalidParameters);
+        let overfull = TreeSnapshot { levels: 1, ..snapshot };
+        assert_eq!(overfull.to_tree().unwrap_err().code(), ErrorCode::LeafIndexOutOfRange);
+
+        let proof = tree.proof(2).unwrap();
+        let mut moved = proof.clone();
+        moved.leaf_index = 3;
+        assert_ne!(proof.hash_tree_root().unwrap(), moved.hash_tree_root().unwrap());
+        let deep = MerkleProof { path_elements: vec![0; PROOF_DEPTH_LIMIT + 1], ..proof };
+        assert!(deep.hash_tree_root().is_err());
+    }
+}
@@ -0,0 +1,84 @@
+use primitive_types::{H256, U256};
+
+use crate::constants::in_field;
+use crate::curve::field::Fr;
+use crate::encoding::bytes32::{bytes32_to_fr, bytes32_to_u128, fr_to_bytes32_be, u128_to_bytes32_be};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// Leaf or root value as a `U256`.
+pub fn field_to_u256(value: u128) -> U256 {
+    U256::from(value)
+}
+
+/// Checked inverse of `field_to_u256`: `FieldOverflow` for values that do not
+/// fit in 128 bits or lie outside the field.
+pub fn field_from_u256(value: U256) -> Result<u128, SolanaError> {
+    if value.bits() > 128 || !in_field(value.low_u128()) {
+        return Err(utils::err_with_code(ErrorCode::FieldOverflow, "U256 value is outside the field"));
+    }
+    Ok(value.low_u128())
+}
+
+/// Leaf or root value as a big-endian `H256`, the `bytes32` layout.
+pub fn field_to_h256(value: u128) -> H256 {
+    H256(u128_to_bytes32_be(value))
+}
+
+pub fn field_from_h256(value: &H256) -> Result<u128, SolanaError> {
+    bytes32_to_u128(value.as_fixed_bytes())
+}
+
+impl From<Fr> for U256 {
+    fn from(value: Fr) -> U256 {
+        value.to_u256()
+    }
+}
+
+/// Rejects values at or above the BN254 modulus instead of reducing them.
+impl TryFrom<U256> for Fr {
+    type Error = SolanaError;
+
+    fn try_from(value: U256) -> Result<Fr, SolanaError> {
+        Fr::from_canonical(value).ok_or_else(|| utils::err_with_code(ErrorCode::FieldOverflow, "U256 value is not below the BN254 modulus"))
+    }
+}
+
+impl From<Fr> for H256 {
+    fn from(value: Fr) -> H256 {
+        H256(fr_to_bytes32_be(value))
+    }
+}
+
+impl TryFrom<H256> for Fr {
+    type Error = SolanaError;
+
+    fn try_from(value: H256) -> Result<Fr, SolanaError> {
+        bytes32_to_fr(value.as_fixed_bytes())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::curve::field::MODULUS;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_u256() {
+        let mut tree = MerkleTree::new(4);
+        tree.insert(field_from_u256(U256::from(11)).unwrap()).unwrap();
+        let root = tree.get_last_root();
+        assert_eq!(field_from_u256(field_to_u256(root)).unwrap(), root);
+        assert_eq!(field_from_h256(&field_to_h256(root)).unwrap(), root);
+        assert_eq!(field_to_h256(1).as_bytes()[31], 1);
+        assert_eq!(field_from_u256(U256::from(u128::MAX)).unwrap_err().code(), ErrorCode::FieldOverflow);
+        assert!(field_from_u256(U256::from(1) << 128).is_err());
+        assert!(field_from_h256(&H256::repeat_byte(1)).is_err());
+
+        let fr = Fr::from(root);
+        assert_eq!(U256::from(fr), U256::from(root));
+        assert_eq!(Fr::try_from(U256::from(fr)).unwrap(), fr);
+        assert_eq!(Fr::try_from(H256::from(fr)).unwrap(), fr);
+        assert!(Fr::try_from(MODULUS).is_err());
+    }
+}
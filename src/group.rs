@@ -0,0 +1,111 @@
+use crate::{
+    merkle_tree::{MerkleProof, MerkleTree},
+    utils::{self, ErrorCode, SolanaError}
+};
+
+/// Deepest group accepted, matching Semaphore's 32-level limit.
+pub const MAX_DEPTH: u8 = 32;
+
+/// Semaphore-style group of identity commitments.
+///
+/// Removing a member sets its leaf to 0, as the Semaphore reference
+/// implementation does, so member indices never shift.
+#[derive(Debug, Clone)]
+pub struct Group {
+    id: u128,
+    depth: u8,
+    tree: MerkleTree
+}
+
+impl Group {
+    pub fn new(id: u128, depth: u8) -> Result<Self, SolanaError> {
+        if depth == 0 || depth > MAX_DEPTH {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Group depth must be between 1 and 32")
+                .with_compared_values(MAX_DEPTH, depth));
+        }
+        Ok(Group {
+            id,
+            depth,
+            tree: MerkleTree::new(depth)
+        })
+    }
+
+    pub fn id(&self) -> u128 {
+        self.id
+    }
+
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    pub fn root(&self) -> u128 {
+        self.tree.get_last_root()
+    }
+
+    pub fn size(&self) -> usize {
+        self.tree.leaves().len()
+    }
+
+    pub fn members(&self) -> &[u128] {
+        self.tree.leaves()
+    }
+
+    pub fn index_of(&self, identity_commitment: u128) -> Option<u64> {
+        if identity_commitment == 0 {
+            return None;
+        }
+        self.tree.index_of(identity_commitment)
+    }
+
+    pub fn add_member(&mut self, identity_commitment: u128) -> Result<u64, SolanaError> {
+        if identity_commitment == 0 {
+            return Err(utils::err("Member value cannot be 0"));
+        }
+        if self.index_of(identity_commitment).is_some() {
+            return Err(utils::err_with_code(ErrorCode::MemberAlreadyExists, "Member already exists in the group"));
+        }
+        self.tree.insert(identity_commitment)
+    }
+
+    pub fn remove_member(&mut self, identity_commitment: u128) -> Result<(), SolanaError> {
+        let index = self.member_index(identity_commitment)?;
+        self.tree.update(index, 0)
+    }
+
+    pub fn generate_merkle_proof(&self, identity_commitment: u128) -> Result<MerkleProof, SolanaError> {
+        let index = self.member_index(identity_commitment)?;
+        self.tree.proof(index)
+    }
+
+    fn member_index(&self, identity_commitment: u128) -> Result<u64, SolanaError> {
+        self.index_of(identity_commitment)
+            .ok_or_else(|| utils::err_with_code(ErrorCode::MemberNotFound, "Member is not in the group"))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_members() {
+        let mut group = Group::new(42, 4).unwrap();
+        assert_eq!(group.add_member(11).unwrap(), 0);
+        assert_eq!(group.add_member(22).unwrap(), 1);
+        assert_eq!(group.add_member(11).unwrap_err().code(), ErrorCode::MemberAlreadyExists);
+        assert!(group.add_member(0).is_err());
+
+        let proof = group.generate_merkle_proof(22).unwrap();
+        assert_eq!(proof.leaf_index, 1);
+        assert!(proof.verify(group.root()));
+
+        group.remove_member(11).unwrap();
+        assert_eq!(group.members(), &[0, 22]);
+        assert_eq!(group.remove_member(11).unwrap_err().code(), ErrorCode::MemberNotFound);
+        assert!(group.generate_merkle_proof(22).unwrap().verify(group.root()));
+
+        assert_eq!(Group::new(42, 0).unwrap_err().code(), ErrorCode::InvalidParameters);
+        assert_eq!(Group::new(42, MAX_DEPTH + 1).unwrap_err().code(), ErrorCode::InvalidParameters);
+        assert!(Group::new(42, MAX_DEPTH).is_ok());
+    }
+}
@@ -1,74 +1,681 @@
-use std::str::FromStr;
+pub mod anemoi;
+#[cfg(feature = "blake3")]
+pub mod blake3;
+pub mod gmimc;
+pub mod keccak;
+pub mod mimc7;
+pub mod mimc_classic;
+pub mod mimc_sponge;
+pub mod neptune;
+pub mod pedersen;
+pub mod poseidon;
+pub mod poseidon2;
+pub mod presets;
+pub mod rescue;
+pub mod sha256;
 
+use primitive_types::{U256, U512};
+
+use crate::constants::{FIELD_SIZE, MIMC_ROUNDS, MIMC_ROUND_CONSTANTS};
+use crate::curve::field::{Fr, MODULUS};
+use crate::utils::{self, ErrorCode, SolanaError};
+use keccak::keccak256;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
 pub struct Hasher {
     p: u128,
     n_rounds: u8,
-    c: Vec<u128>
+    c: Vec<u128>,
+    exponent: u8,
+    domain: DomainTag
+}
+
+/// Personalization mixed into every Feistel round key, so hashers built for
+/// different purposes (leaves, nullifiers, nodes) never agree on an output.
+/// The tag for a label is `hash_to_field(label)`; `DomainTag::NONE` leaves the
+/// hasher unchanged.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct DomainTag(u128);
+
+impl DomainTag {
+    pub const NONE: DomainTag = DomainTag(0);
+
+    pub fn new(label: &str) -> Self {
+        DomainTag(hash_to_field(label.as_bytes()))
+    }
+
+    pub fn value(&self) -> u128 {
+        self.0
+    }
+}
+
+impl From<&str> for DomainTag {
+    fn from(label: &str) -> Self {
+        DomainTag::new(label)
+    }
+}
+
+pub const SUPPORTED_EXPONENTS: [u8; 3] = [3, 5, 7];
+
+/// Two-to-one node hash used by `MerkleTree`.
+pub trait MerkleHasher {
+    fn hash_pair(&self, left: u128, right: u128) -> u128;
+
+    /// Node hash for wider trees. Defaults to folding `hash_pair` over the
+    /// children from the left, and `zero(0)` for no children; hashers with a
+    /// wide native input override it.
+    fn hash_children(&self, children: &[u128]) -> u128 {
+        match children.split_first() {
+            Some((first, rest)) => rest.iter().fold(*first, |acc, child| self.hash_pair(acc, *child)),
+            None => self.zero(0)
+        }
+    }
+
+    /// Root of an empty subtree with `level` levels below it.
+    fn zero(&self, level: u8) -> u128 {
+        let mut result = 0;
+        for _ in 0..level {
+            result = self.hash_pair(result, result);
+        }
+        result
+    }
+}
+
+/// Two-to-one node hash over the full BN254 scalar field, for trees whose
+/// nodes do not fit the 128-bit `FIELD_SIZE` (see `merkle_tree::bn254`).
+pub trait FrHasher {
+    fn hash_pair(&self, left: Fr, right: Fr) -> Fr;
+}
+
+/// Canonical encoding of a leaf value as one field element, so structured
+/// leaves can be inserted without hashing them by hand first.
+pub trait IntoField {
+    fn to_field(&self) -> u128;
+}
+
+impl IntoField for u128 {
+    fn to_field(&self) -> u128 {
+        *self
+    }
+}
+
+/// Fields of a structured leaf (amount, owner, blinding, ...) absorbed in
+/// order with `Hasher::hash_many`.
+impl<const N: usize> IntoField for [u128; N] {
+    fn to_field(&self) -> u128 {
+        Hasher::default().hash_many(self)
+    }
 }
 
 impl Default for Hasher {
     fn default() -> Self {
         Hasher {
-            p: u128::from_str("340282366920938463463374607431768211455").expect("Failed conversion"),
-            n_rounds: 10,
-            c: vec![
-                0,
-                u128::from_str("25823191961023811529686723375255045").expect("Failed conversion"),
-                u128::from_str("48376936063113800887806988124358800").expect("Failed conversion"),
-                u128::from_str("75580405153655082660116863095114839").expect("Failed conversion"),
-                u128::from_str("66651710483985382365580181188706173").expect("Failed conversion"),
-                u128::from_str("45887003413921204775397977044284378").expect("Failed conversion"),
-                u128::from_str("14399999722617037892747232478295923").expect("Failed conversion"),
-                u128::from_str("29376176727758177809204424209125257").expect("Failed conversion"),
-                u128::from_str("13768859312518298840937540532277016").expect("Failed conversion"),
-                u128::from_str("54749662990362840569021981534456448").expect("Failed conversion"),
-                u128::from_str("25161436470718351277017231215227846").expect("Failed conversion"),
-                u128::from_str("90370030464179443930112165274275271").expect("Failed conversion"),
-                u128::from_str("92014788260850167582827910417652439").expect("Failed conversion"),
-                u128::from_str("40376490640073034398204558905403523").expect("Failed conversion"),
-                u128::from_str("90379224439153137712327643289289624").expect("Failed conversion"),
-                u128::from_str("11220341520269979188892857030918685").expect("Failed conversion"),
-                u128::from_str("11480168113674888067906254878279274").expect("Failed conversion"),
-                u128::from_str("11144081894867681653997893051446803").expect("Failed conversion"),
-                u128::from_str("64965960071752809090438003157362764").expect("Failed conversion"),
-                u128::from_str("98428510787134995495896453413714864").expect("Failed conversion")
-            ]    
+            p: FIELD_SIZE,
+            n_rounds: MIMC_ROUNDS,
+            c: MIMC_ROUND_CONSTANTS.to_vec(),
+            exponent: 5,
+            domain: DomainTag::NONE
         }
     }
 }
 
 impl Hasher {
-    fn mimc_feistel(il: u128, ir: u128, k: u128) -> (u128, u128) {
-        let hasher = Hasher::default();
-        let mut last_l = il.clone();
-        let mut last_r = ir.clone();
-
-        for i in 0..hasher.n_rounds {
-            let mask = last_r.wrapping_add(k).checked_rem(hasher.p).unwrap();
-            let mask = mask.wrapping_add(hasher.c[i as usize]).checked_rem(hasher.p).unwrap();
-            let mask2 = mask.wrapping_mul(mask).checked_rem(hasher.p).unwrap();
-            let mask4 = mask2.wrapping_mul(mask2).checked_rem(hasher.p).unwrap();
-            let mask = mask4.wrapping_mul(mask).checked_rem(hasher.p).unwrap();
-    
+    pub fn new(p: u128, n_rounds: u8, c: Vec<u128>) -> Result<Self, SolanaError> {
+        if p <= 1 {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Hasher field prime must be greater than one"));
+        }
+        if n_rounds == 0 {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Hasher must have at least one round"));
+        }
+        if c.len() < n_rounds as usize {
+            return Err(utils::err_with_code(
+                ErrorCode::InvalidParameters,
+                "Hasher needs one round constant per round"
+            ));
+        }
+        Ok(Hasher { p, n_rounds, c, exponent: 5, domain: DomainTag::NONE })
+    }
+
+    /// Switches the round function to `x^exponent`. The exponent must be one of
+    /// `SUPPORTED_EXPONENTS` and coprime to `p - 1`, otherwise the round
+    /// function is not a permutation.
+    pub fn with_exponent(mut self, exponent: u8) -> Result<Self, SolanaError> {
+        if !SUPPORTED_EXPONENTS.contains(&exponent) {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "MiMC exponent must be 3, 5 or 7"));
+        }
+        if gcd(exponent as u128, self.p - 1) != 1 {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "MiMC exponent must be coprime to p - 1"));
+        }
+        self.exponent = exponent;
+        Ok(self)
+    }
+
+    /// Separates this hasher from every other domain, e.g. `with_domain("commitment")`.
+    pub fn with_domain(mut self, domain: impl Into<DomainTag>) -> Self {
+        self.domain = domain.into();
+        self
+    }
+
+    /// circomlib `mimcsponge.getConstants`: `c[i]` is `keccak256` applied `i + 1`
+    /// times to the seed, reduced mod `p`, with the first and last constants zero.
+    /// circomlib's MiMCSponge uses the seed `"mimcsponge"`.
+    pub fn from_seed(seed: &str, n_rounds: u8, p: u128) -> Result<Self, SolanaError> {
+        if p <= 1 {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Hasher field prime must be greater than one"));
+        }
+
+        let mut c: Vec<u128> = keccak_chain(seed.as_bytes(), n_rounds as usize)
+            .into_iter()
+            .map(|link| (link % U256::from(p)).as_u128())
+            .collect();
+        if let Some(first) = c.first_mut() {
+            *first = 0;
+        }
+        if let Some(last) = c.last_mut() {
+            *last = 0;
+        }
+        Hasher::new(p, n_rounds, c)
+    }
+
+    pub fn p(&self) -> u128 {
+        self.p
+    }
+
+    pub fn n_rounds(&self) -> u8 {
+        self.n_rounds
+    }
+
+    pub fn constants(&self) -> &[u128] {
+        &self.c
+    }
+
+    pub fn exponent(&self) -> u8 {
+        self.exponent
+    }
+
+    pub fn domain(&self) -> DomainTag {
+        self.domain
+    }
+
+    pub(crate) fn sbox(&self, x: u128) -> u128 {
+        let x2 = self.reduce(x.wrapping_mul(x));
+        match self.exponent {
+            3 => self.reduce(x2.wrapping_mul(x)),
+            7 => {
+                let x4 = self.reduce(x2.wrapping_mul(x2));
+                let x6 = self.reduce(x4.wrapping_mul(x2));
+                self.reduce(x6.wrapping_mul(x))
+            }
+            _ => {
+                let x4 = self.reduce(x2.wrapping_mul(x2));
+                self.reduce(x4.wrapping_mul(x))
+            }
+        }
+    }
+
+    fn feistel(&self, il: u128, ir: u128, k: u128) -> (u128, u128) {
+        let mut last_l = il;
+        let mut last_r = ir;
+
+        for i in 0..self.n_rounds {
+            let mask = self.reduce(last_r.wrapping_add(k));
+            let mask = self.reduce(mask.wrapping_add(self.c[i as usize]));
+            let mask = self.reduce(mask.wrapping_add(self.domain.0));
+            let mask = self.sbox(mask);
+
             let temp = last_r;
-            last_r = last_l.wrapping_add(mask).checked_rem(hasher.p).unwrap();
+            last_r = self.reduce(last_l.wrapping_add(mask));
             last_l = temp;
         }
-    
+
         (last_l, last_r)
     }
 
-    pub fn mimc_sponge(left: u128, right: u128, k: u128) -> u128 {
-        let mut last_r = left.clone();
-        let mut last_l = right.clone();
-    
-        for _ in 0..Hasher::default().n_rounds {
-            let (new_last_r, new_last_l) = Hasher::mimc_feistel(last_r, last_l, k);
-    
-            last_r = new_last_r.wrapping_add(1).checked_rem(Hasher::default().p).unwrap();
-            last_l = new_last_l.clone();
-        }
-    
+    pub fn sponge(&self, left: u128, right: u128, k: u128) -> u128 {
+        let mut last_r = left;
+        let mut last_l = right;
+
+        for _ in 0..self.n_rounds {
+            let (new_last_r, new_last_l) = self.feistel(last_r, last_l, k);
+
+            last_r = self.reduce(new_last_r.wrapping_add(1));
+            last_l = new_last_l;
+        }
+
         last_r
     }
-}
\ No newline at end of file
+
+    /// Absorbs any number of inputs as circomlib's `MiMCSponge(nInputs, nRounds, 1)`
+    /// does: each input is added to the rate element `R` before one Feistel
+    /// permutation of `(R, C)`, and the result is the final `R`.
+    pub fn hash_many(&self, inputs: &[u128]) -> u128 {
+        self.squeeze(inputs, 1)[0]
+    }
+
+    /// `MiMCSponge(nInputs, nRounds, nOutputs)`: after absorbing, the first output
+    /// is `R` and every further output permutes the state once more.
+    pub fn squeeze(&self, inputs: &[u128], n_outputs: usize) -> Vec<u128> {
+        let mut state = MimcSpongeState::new(self.clone());
+        state.absorb(inputs);
+        state.squeeze(n_outputs)
+    }
+
+    /// Keyed sponge MAC over field elements: the message is absorbed with `key`
+    /// as the Feistel key and the tag is the final rate element.
+    ///
+    /// Security rests on the keyed MiMC permutation being indistinguishable from
+    /// random, so `key` must be a uniformly random field element kept secret.
+    /// Tags are a single element, so forgeries succeed with probability about
+    /// `1 / p` per attempt. The capacity element is never output, which rules
+    /// out length extension. Arithmetic is not constant time; compare tags with
+    /// `utils::secure_eq` where timing matters.
+    pub fn mac(&self, key: u128, message: &[u128]) -> u128 {
+        let mut state = MimcSpongeState::with_key(self.clone(), key);
+        state.absorb(message);
+        state.finalize()
+    }
+
+    /// `hash_pair` over every pair, spread across threads with the `parallel` feature.
+    pub fn hash_batch(&self, pairs: &[(u128, u128)]) -> Vec<u128> {
+        #[cfg(feature = "parallel")]
+        {
+            use rayon::prelude::*;
+            pairs.par_iter().map(|(left, right)| self.hash_pair(*left, *right)).collect()
+        }
+        #[cfg(not(feature = "parallel"))]
+        {
+            pairs.iter().map(|(left, right)| self.hash_pair(*left, *right)).collect()
+        }
+    }
+
+    pub fn mimc_sponge(left: u128, right: u128, k: u128) -> u128 {
+        Hasher::default().sponge(left, right, k)
+    }
+
+    // `x mod p`. With the default prime the divisor is a constant, which turns
+    // the 128-bit division into a few additions; it is most of the cost of a
+    // hash on targets without native u128 division, such as Solana's BPF.
+    fn reduce(&self, x: u128) -> u128 {
+        if self.p == FIELD_SIZE {
+            x % FIELD_SIZE
+        } else {
+            x % self.p
+        }
+    }
+
+    pub(crate) fn add_mod(&self, a: u128, b: u128) -> u128 {
+        let (a, b) = (a % self.p, b % self.p);
+        match a.checked_add(b) {
+            Some(sum) if sum < self.p => sum,
+            _ => a.wrapping_add(b).wrapping_sub(self.p)
+        }
+    }
+}
+
+impl MerkleHasher for Hasher {
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        let mut r = self.sponge(left, 0, self.p);
+        r = self.reduce(r.wrapping_add(right));
+        self.sponge(r, 0, self.p)
+    }
+
+    /// Absorbs every child into one sponge with `hash_many`.
+    fn hash_children(&self, children: &[u128]) -> u128 {
+        self.hash_many(children)
+    }
+
+    // Empty subtrees chain the sponge over the previous level alone.
+    fn zero(&self, level: u8) -> u128 {
+        let mut result = 0;
+        for _ in 0..level {
+            result = self.sponge(result, 0, self.p);
+        }
+        result
+    }
+}
+
+/// Incremental form of `Hasher::squeeze`: absorbing the inputs over several
+/// calls gives the same outputs as passing them all at once.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MimcSpongeState {
+    hasher: Hasher,
+    key: u128,
+    r: u128,
+    c: u128
+}
+
+impl Default for MimcSpongeState {
+    fn default() -> Self {
+        MimcSpongeState::new(Hasher::default())
+    }
+}
+
+impl MimcSpongeState {
+    pub fn new(hasher: Hasher) -> Self {
+        MimcSpongeState::with_key(hasher, 0)
+    }
+
+    /// The sponge with `key` as the Feistel key `k` of circomlib's `MiMCSponge`.
+    pub fn with_key(hasher: Hasher, key: u128) -> Self {
+        MimcSpongeState { hasher, key, r: 0, c: 0 }
+    }
+
+    pub fn absorb(&mut self, chunk: &[u128]) {
+        for input in chunk {
+            self.r = self.hasher.add_mod(self.r, *input);
+            (self.r, self.c) = self.hasher.feistel(self.r, self.c, self.key);
+        }
+    }
+
+    pub fn finalize(self) -> u128 {
+        self.r
+    }
+
+    pub fn squeeze(self, n_outputs: usize) -> Vec<u128> {
+        let (mut r, mut c) = (self.r, self.c);
+        let mut outputs = Vec::with_capacity(n_outputs);
+        for i in 0..n_outputs {
+            if i > 0 {
+                (r, c) = self.hasher.feistel(r, c, self.key);
+            }
+            outputs.push(r);
+        }
+        outputs
+    }
+}
+
+/// Hashes arbitrary bytes into the `Hasher` field.
+///
+/// The input is padded with a `0x01` byte and then zeros to a multiple of 15
+/// bytes, each 15-byte chunk is read big-endian as a field element (always
+/// below `FIELD_SIZE`), and the elements are absorbed with `Hasher::hash_many`.
+pub fn hash_to_field(bytes: &[u8]) -> u128 {
+    let mut state = HashToField::default();
+    state.update(bytes);
+    state.finalize()
+}
+
+/// Incremental `hash_to_field`, holding back at most one partial chunk.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct HashToField {
+    sponge: MimcSpongeState,
+    pending: Vec<u8>
+}
+
+impl HashToField {
+    const CHUNK_SIZE: usize = 15;
+
+    /// The same padding and chunking absorbed by another `Hasher`, e.g. one
+    /// built with `with_domain`.
+    pub fn with_hasher(hasher: Hasher) -> Self {
+        HashToField { sponge: MimcSpongeState::new(hasher), pending: Vec::new() }
+    }
+
+    pub fn update(&mut self, bytes: &[u8]) {
+        self.pending.extend_from_slice(bytes);
+        let full = self.pending.len() / Self::CHUNK_SIZE * Self::CHUNK_SIZE;
+        for chunk in self.pending[..full].chunks(Self::CHUNK_SIZE) {
+            self.sponge.absorb(&[be_chunk_to_u128(chunk)]);
+        }
+        self.pending.drain(..full);
+    }
+
+    pub fn finalize(mut self) -> u128 {
+        for chunk in pad_chunks(&self.pending, Self::CHUNK_SIZE) {
+            self.sponge.absorb(&[be_chunk_to_u128(&chunk)]);
+        }
+        self.sponge.finalize()
+    }
+}
+
+/// RustCrypto adapter: the 16-byte output is the big-endian field element.
+#[cfg(feature = "digest")]
+mod digest_impl {
+    use digest::consts::U16;
+    use digest::{FixedOutput, HashMarker, Output, OutputSizeUser, Reset, Update};
+
+    use super::HashToField;
+
+    impl HashMarker for HashToField {}
+
+    impl OutputSizeUser for HashToField {
+        type OutputSize = U16;
+    }
+
+    impl Update for HashToField {
+        fn update(&mut self, data: &[u8]) {
+            HashToField::update(self, data);
+        }
+    }
+
+    impl FixedOutput for HashToField {
+        fn finalize_into(self, out: &mut Output<Self>) {
+            out.copy_from_slice(&HashToField::finalize(self).to_be_bytes());
+        }
+    }
+
+    impl Reset for HashToField {
+        fn reset(&mut self) {
+            *self = HashToField::default();
+        }
+    }
+}
+
+fn be_chunk_to_u128(chunk: &[u8]) -> u128 {
+    chunk.iter().fold(0u128, |acc, byte| (acc << 8) | *byte as u128)
+}
+
+/// The same padding with 31-byte chunks into the BN254 scalar field, absorbed
+/// with circomlib's `MultiMiMC7` under key zero.
+pub fn hash_to_field_bn254(bytes: &[u8]) -> Fr {
+    let elements: Vec<Fr> = pad_chunks(bytes, 31)
+        .iter()
+        .map(|chunk| Fr::from_u256(U256::from_big_endian(chunk)))
+        .collect();
+    mimc7::multi_hash(&elements, Fr::zero())
+}
+
+fn pad_chunks(bytes: &[u8], chunk_size: usize) -> Vec<Vec<u8>> {
+    let mut padded = bytes.to_vec();
+    padded.push(0x01);
+    padded.resize(padded.len().div_ceil(chunk_size) * chunk_size, 0);
+    padded.chunks(chunk_size).map(|chunk| chunk.to_vec()).collect()
+}
+
+// Maps a BN254 hash output onto the 128-bit tree field.
+pub(crate) fn fr_to_field(value: Fr) -> u128 {
+    (value.to_u256() % U256::from(FIELD_SIZE)).as_u128()
+}
+
+// alpha^-1 mod (p - 1) for the BN254 scalar field, found as the multiple
+// `(p - 1) * t + 1` divisible by alpha.
+pub(crate) fn inverse_exponent(alpha: u64) -> U256 {
+    let p_minus_one = U512::from(MODULUS - 1);
+    (1..alpha)
+        .map(|t| p_minus_one * U512::from(t) + 1)
+        .find(|n| (*n % U512::from(alpha)).is_zero())
+        .map(|n| U256::try_from(n / U512::from(alpha)).expect("Inverse exponent is below p"))
+        .expect("alpha is coprime to p - 1")
+}
+
+pub(crate) fn gcd(mut a: u128, mut b: u128) -> u128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a
+}
+
+/// `len` links of circomlib's constant chain; link `i` is `keccak256` applied
+/// `i + 1` times to `seed`.
+pub(crate) fn keccak_chain(seed: &[u8], len: usize) -> Vec<U256> {
+    let mut links = Vec::with_capacity(len);
+    let mut c = keccak256(seed);
+    for _ in 0..len {
+        links.push(U256::from_big_endian(&c));
+        c = keccak256(&c);
+    }
+    links
+}
+
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_new() {
+        let hasher = Hasher::new(FIELD_SIZE, MIMC_ROUNDS, MIMC_ROUND_CONSTANTS.to_vec()).unwrap();
+        assert_eq!(hasher.sponge(1, 2, 0), 66603705049924598216870927428500306880);
+        assert_eq!(Hasher::mimc_sponge(3, 4, FIELD_SIZE), 257519298621215689485032891712352369940);
+
+        for (p, n_rounds, c) in [(0, 10, vec![0; 10]), (1, 10, vec![0; 10]), (FIELD_SIZE, 0, vec![]), (FIELD_SIZE, 10, vec![0; 9])] {
+            let err = Hasher::new(p, n_rounds, c).err().unwrap();
+            assert_eq!(err.code(), ErrorCode::InvalidParameters);
+        }
+    }
+
+    #[test]
+    fn test_exponent() {
+        let quintic = Hasher::default().with_exponent(5).unwrap();
+        assert_eq!(quintic, Hasher::default());
+        let cube = Hasher::default().with_exponent(3).unwrap();
+        let septic = Hasher::default().with_exponent(7).unwrap();
+        assert_ne!(cube.sponge(1, 2, 0), quintic.sponge(1, 2, 0));
+        assert_ne!(septic.sponge(1, 2, 0), quintic.sponge(1, 2, 0));
+        assert_eq!(septic.sbox(2), 128);
+
+        assert!(Hasher::default().with_exponent(4).is_err());
+        // 3 divides 7 - 1, so x^3 is not a permutation mod 7.
+        let small = Hasher::new(7, 1, vec![0]).unwrap();
+        assert!(small.clone().with_exponent(3).is_err());
+        assert!(small.with_exponent(5).is_ok());
+    }
+
+    #[test]
+    fn test_domain() {
+        let plain = Hasher::default();
+        assert_eq!(plain.clone().with_domain(DomainTag::NONE), plain);
+
+        let commitment = Hasher::default().with_domain("commitment");
+        let nullifier = Hasher::default().with_domain("nullifier");
+        assert_eq!(commitment.domain(), DomainTag::new("commitment"));
+        assert_ne!(commitment.sponge(1, 2, 0), plain.sponge(1, 2, 0));
+        assert_ne!(commitment.sponge(1, 2, 0), nullifier.sponge(1, 2, 0));
+        assert_ne!(commitment.hash_pair(1, 2), plain.hash_pair(1, 2));
+    }
+
+    #[test]
+    fn test_mac() {
+        let hasher = Hasher::default();
+        let tag = hasher.mac(7, &[1, 2, 3]);
+        assert_eq!(tag, hasher.mac(7, &[1, 2, 3]));
+        assert_ne!(tag, hasher.mac(8, &[1, 2, 3]));
+        assert_ne!(tag, hasher.mac(7, &[1, 2, 4]));
+        assert_ne!(tag, hasher.hash_many(&[1, 2, 3]));
+        assert_eq!(hasher.mac(0, &[1, 2, 3]), hasher.hash_many(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_hash_batch() {
+        let hasher = Hasher::default();
+        let pairs: Vec<(u128, u128)> = (0..64).map(|i| (i, i + 1)).collect();
+        let expected: Vec<u128> = pairs.iter().map(|(l, r)| hasher.hash_pair(*l, *r)).collect();
+        assert_eq!(hasher.hash_batch(&pairs), expected);
+        assert!(hasher.hash_batch(&[]).is_empty());
+    }
+
+    #[test]
+    fn test_to_field() {
+        assert_eq!(7u128.to_field(), 7);
+        assert_eq!([1u128, 2, 3].to_field(), Hasher::default().hash_many(&[1, 2, 3]));
+    }
+
+    #[test]
+    fn test_hash_many() {
+        let hasher = Hasher::default();
+        let h = hasher.hash_many(&[1, 2, 3]);
+        assert_eq!(h, hasher.hash_many(&[1, 2, 3]));
+        assert_ne!(h, hasher.hash_many(&[3, 2, 1]));
+        assert_ne!(h, hasher.hash_many(&[1, 2]));
+        assert_ne!(hasher.hash_many(&[0]), hasher.hash_many(&[0, 0]));
+        assert_eq!(hasher.hash_many(&[FIELD_SIZE]), hasher.hash_many(&[0]));
+        assert_eq!(hasher.add_mod(FIELD_SIZE - 1, 2), 1);
+    }
+
+    #[test]
+    fn test_squeeze() {
+        let hasher = Hasher::default();
+        let outputs = hasher.squeeze(&[1, 2, 3], 3);
+        assert_eq!(outputs.len(), 3);
+        assert_eq!(outputs[0], hasher.hash_many(&[1, 2, 3]));
+        assert_eq!(hasher.squeeze(&[1, 2, 3], 2), outputs[..2]);
+        assert_ne!(outputs[1], outputs[2]);
+        assert!(hasher.squeeze(&[1], 0).is_empty());
+    }
+
+    #[test]
+    fn test_streaming() {
+        let hasher = Hasher::default();
+        let mut state = MimcSpongeState::new(hasher.clone());
+        state.absorb(&[1]);
+        state.absorb(&[]);
+        state.absorb(&[2, 3]);
+        assert_eq!(state.clone().finalize(), hasher.hash_many(&[1, 2, 3]));
+        assert_eq!(state.squeeze(3), hasher.squeeze(&[1, 2, 3], 3));
+
+        let message: Vec<u8> = (0..100).collect();
+        let mut bytes = HashToField::default();
+        for piece in message.chunks(7) {
+            bytes.update(piece);
+        }
+        assert_eq!(bytes.finalize(), hash_to_field(&message));
+    }
+
+    #[cfg(feature = "digest")]
+    #[test]
+    fn test_digest() {
+        use digest::Digest;
+
+        let state = <HashToField as Digest>::new().chain_update(b"ab").chain_update(b"c");
+        let output = Digest::finalize(state);
+        assert_eq!(output.as_slice(), hash_to_field(b"abc").to_be_bytes());
+    }
+
+    #[test]
+    fn test_hash_to_field() {
+        assert_eq!(pad_chunks(&[], 15), vec![[vec![1], vec![0; 14]].concat()]);
+        assert_eq!(pad_chunks(&[7; 15], 15).len(), 2);
+
+        let hasher = Hasher::default();
+        assert_eq!(hash_to_field(b"abc"), hasher.hash_many(&[0x61626301 << 88]));
+        assert_ne!(hash_to_field(b""), hash_to_field(&[0]));
+        assert_ne!(hash_to_field(&[0; 15]), hash_to_field(&[0; 16]));
+        assert_ne!(hash_to_field_bn254(b""), hash_to_field_bn254(&[0]));
+        assert_eq!(
+            hash_to_field_bn254(b"abc"),
+            mimc7::multi_hash(&[Fr::from(0x61626301u64) * Fr::from(2u64).pow(U256::from(27 * 8))], Fr::zero())
+        );
+    }
+
+    #[test]
+    fn test_from_seed() {
+        let hasher = Hasher::from_seed("mimcsponge", 220, FIELD_SIZE).unwrap();
+        let c = hasher.constants();
+        assert_eq!(c.len(), 220);
+        assert_eq!(c[0], 0);
+        assert_eq!(c[1], 14705651573373799429158838557142940974);
+        assert_eq!(c[2], 323338933597179277258282711534689773152);
+        assert_eq!(c[218], 38099370601852827700033063742175424346);
+        assert_eq!(c[219], 0);
+
+        // Over BN254 the same chain gives circomlib's MiMCSponge constant c[1].
+        let bn254 = U256::from_dec_str("21888242871839275222246405745257275088548364400416034343698204186575808495617").unwrap();
+        assert_eq!(
+            keccak_chain(b"mimcsponge", 2)[1] % bn254,
+            U256::from_dec_str("7120861356467848435263064379192047478074060781135320967663101236819528304084").unwrap()
+        );
+
+        assert!(Hasher::from_seed("mimcsponge", 0, FIELD_SIZE).is_err());
+        assert!(Hasher::from_seed("mimcsponge", 220, 1).is_err());
+    }
+}
@@ -0,0 +1,134 @@
+use primitive_types::U256;
+
+use crate::curve::field::Fr;
+use crate::hasher::{fr_to_field, inverse_exponent, MerkleHasher};
+
+pub const ALPHA: u64 = 5;
+pub const ROUNDS: usize = 21;
+
+// Smallest generator of the multiplicative group of the BN254 scalar field,
+// used as the Flystel `beta`; `delta` is its inverse.
+const GENERATOR: u64 = 5;
+
+// The first hundred decimals of pi. The reference also uses the next hundred
+// as `pi_1`, but only `pi_1^0 = 1` appears with a single column.
+const PI_0: &str = "1415926535897932384626433832795028841971693993751058209749445923078164062862089986280348253421170679";
+
+/// Anemoi over the BN254 scalar field with one column (state `(x, y)`), x^5
+/// and its inverse in the open Flystel, and 21 rounds for 128-bit security.
+///
+/// Round constants follow the reference: `C_r = g*pi_0^(2r) + (pi_0^r + 1)^alpha`
+/// and `D_r = g + (pi_0^r + 1)^alpha + g^-1`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Anemoi {
+    c: Vec<Fr>,
+    d: Vec<Fr>,
+    alpha_inv: U256,
+    delta: Fr
+}
+
+impl Default for Anemoi {
+    fn default() -> Self {
+        Anemoi::bn254()
+    }
+}
+
+impl Anemoi {
+    pub fn bn254() -> Self {
+        let g = Fr::from(GENERATOR);
+        let delta = g.inverse().expect("Generator is non-zero");
+        let pi_0 = decimal_to_fr(PI_0);
+        let pi_1 = Fr::one();
+
+        let mut c = Vec::with_capacity(ROUNDS);
+        let mut d = Vec::with_capacity(ROUNDS);
+        let mut pi_0_r = Fr::one();
+        for _ in 0..ROUNDS {
+            let pow_alpha = (pi_0_r + pi_1).pow(U256::from(ALPHA));
+            c.push(g * pi_0_r.square() + pow_alpha);
+            d.push(g * pi_1.square() + pow_alpha + delta);
+            pi_0_r = pi_0_r * pi_0;
+        }
+
+        Anemoi {
+            c,
+            d,
+            alpha_inv: inverse_exponent(ALPHA),
+            delta
+        }
+    }
+
+    pub fn permute(&self, x: Fr, y: Fr) -> (Fr, Fr) {
+        let g = Fr::from(GENERATOR);
+        let (mut x, mut y) = (x, y);
+        for (c, d) in self.c.iter().zip(&self.d) {
+            x = x + *c;
+            y = y + *d;
+            (x, y) = linear_layer(x, y);
+
+            let t = x - g * y.square();
+            y = y - t.pow(self.alpha_inv);
+            x = t + g * y.square() + self.delta;
+        }
+        linear_layer(x, y)
+    }
+
+    /// Jive two-to-one mode: the sum of the inputs and the permuted outputs.
+    pub fn jive(&self, left: Fr, right: Fr) -> Fr {
+        let (x, y) = self.permute(left, right);
+        left + right + x + y
+    }
+}
+
+impl MerkleHasher for Anemoi {
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        fr_to_field(self.jive(Fr::from(left), Fr::from(right)))
+    }
+}
+
+// With one column the MDS matrix is the identity, leaving the pseudo-Hadamard transform.
+fn linear_layer(x: Fr, y: Fr) -> (Fr, Fr) {
+    let y = y + x;
+    (x + y, y)
+}
+
+fn decimal_to_fr(digits: &str) -> Fr {
+    digits
+        .bytes()
+        .fold(Fr::zero(), |acc, digit| acc * Fr::from(10u64) + Fr::from((digit - b'0') as u64))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    fn fr(value: &str) -> Fr {
+        Fr::from_u256(U256::from_dec_str(value).unwrap())
+    }
+
+    #[test]
+    fn test_constants() {
+        let anemoi = Anemoi::bn254();
+        assert_eq!(anemoi.c[0], Fr::from(37u64));
+        assert_eq!(anemoi.d[0], fr("8755297148735710088898562298102910035419345760166413737479281674630323398284"));
+        assert_eq!(anemoi.c[1], fr("13352247125433170118601974521234241686699252132838635793584252509352796067497"));
+    }
+
+    #[test]
+    fn test_permutation_and_jive() {
+        let anemoi = Anemoi::bn254();
+        assert_eq!(anemoi.permute(Fr::zero(), Fr::zero()), (
+            fr("12781024641116938412396488495458428946694972335198159367692885078458658344185"),
+            fr("9211078655966643530904274854247924841832576083874876104173103080297910456399")
+        ));
+        assert_eq!(
+            anemoi.jive(Fr::from(1u64), Fr::from(2u64)),
+            fr("11012753884551777699786047765908401937140394503730594635496653648393745728624")
+        );
+
+        let mut tree = MerkleTree::with_hasher(3, anemoi);
+        tree.insert(5).unwrap();
+        assert!(tree.proof(0).unwrap().verify_with(tree.get_last_root(), tree.hasher()));
+    }
+}
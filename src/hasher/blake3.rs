@@ -0,0 +1,46 @@
+use primitive_types::U256;
+
+use crate::constants::FIELD_SIZE;
+use crate::hasher::MerkleHasher;
+
+/// BLAKE3 node hasher for off-chain mirrors of a leaf set, e.g. to cross-check
+/// an indexer's leaves quickly. Not meant for circuits. Both children are
+/// hashed as 32-byte big-endian words and the digest is reduced into
+/// `FIELD_SIZE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Blake3;
+
+impl MerkleHasher for Blake3 {
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        let mut data = [0u8; 64];
+        data[16..32].copy_from_slice(&left.to_be_bytes());
+        data[48..].copy_from_slice(&right.to_be_bytes());
+        let digest = blake3::hash(&data);
+        (U256::from_big_endian(digest.as_bytes()) % U256::from(FIELD_SIZE)).as_u128()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_blake3() {
+        assert_eq!(
+            blake3::hash(b"").to_hex().as_str(),
+            "af1349b9f5f9a1a6a0404dea36dcc9499bcb25c9adc112b7cc9a93cae41f3262"
+        );
+
+        let leaves = [11, 22, 33];
+        let mut mirror = MerkleTree::with_hasher(4, Blake3);
+        let mut tree = MerkleTree::new(4);
+        for leaf in leaves {
+            mirror.insert(leaf).unwrap();
+            tree.insert(leaf).unwrap();
+        }
+        assert_eq!(mirror.leaves(), tree.leaves());
+        assert_ne!(mirror.get_last_root(), tree.get_last_root());
+        assert!(mirror.proof(2).unwrap().verify_with(mirror.get_last_root(), &Blake3));
+    }
+}
@@ -0,0 +1,103 @@
+use crate::constants::FIELD_SIZE;
+use crate::hasher::{Hasher, MerkleHasher};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+pub const SEED: &str = "gmimc";
+pub const DEFAULT_BRANCHES: usize = 3;
+pub const DEFAULT_ROUNDS: u8 = 101;
+
+/// GMiMC with the expanding round function (GMiMC-erf): each round computes
+/// `f = (x_0 + c_i)^e`, adds `f` to every other branch and rotates the branches
+/// left by one. The prime, round constants and exponent come from `hasher`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Gmimc {
+    hasher: Hasher,
+    branches: usize
+}
+
+impl Default for Gmimc {
+    fn default() -> Self {
+        let hasher = Hasher::from_seed(SEED, DEFAULT_ROUNDS, FIELD_SIZE)
+            .and_then(|hasher| hasher.with_exponent(3))
+            .expect("Default GMiMC parameters are valid");
+        Gmimc {
+            hasher,
+            branches: DEFAULT_BRANCHES
+        }
+    }
+}
+
+impl Gmimc {
+    pub fn new(hasher: Hasher, branches: usize) -> Result<Self, SolanaError> {
+        if branches < 2 {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "GMiMC needs at least two branches"));
+        }
+        Ok(Gmimc { hasher, branches })
+    }
+
+    pub fn branches(&self) -> usize {
+        self.branches
+    }
+
+    pub fn permute(&self, state: &mut [u128]) -> Result<(), SolanaError> {
+        if state.len() != self.branches {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "GMiMC state must have one element per branch")
+                .with_compared_values(self.branches, state.len()));
+        }
+        for c in &self.hasher.c[..self.hasher.n_rounds as usize] {
+            let f = self.hasher.sbox(self.hasher.add_mod(state[0], *c));
+            for x in state.iter_mut().skip(1) {
+                *x = self.hasher.add_mod(*x, f);
+            }
+            state.rotate_left(1);
+        }
+        Ok(())
+    }
+
+    /// Sponge with the last branch as capacity: inputs are absorbed
+    /// `branches - 1` at a time and the first branch is the output.
+    pub fn hash(&self, inputs: &[u128]) -> u128 {
+        let mut state = vec![0; self.branches];
+        for chunk in inputs.chunks(self.branches - 1) {
+            for (x, input) in state.iter_mut().zip(chunk) {
+                *x = self.hasher.add_mod(*x, *input);
+            }
+            self.permute(&mut state).expect("State has one element per branch");
+        }
+        state[0]
+    }
+}
+
+impl MerkleHasher for Gmimc {
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        self.hash(&[left, right])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_hash() {
+        let gmimc = Gmimc::default();
+        let h = gmimc.hash(&[1, 2]);
+        assert_ne!(h, gmimc.hash(&[2, 1]));
+        assert_ne!(h, gmimc.hash(&[1, 2, 0]));
+        assert_eq!(h, gmimc.hash_pair(1, 2));
+        assert!(Gmimc::new(Hasher::default(), 1).is_err());
+        assert_eq!(gmimc.permute(&mut [1]).unwrap_err().code(), ErrorCode::InvalidParameters);
+    }
+
+    #[test]
+    fn test_tree() {
+        let mut tree = MerkleTree::with_hasher(4, Gmimc::default());
+        for leaf in [11, 22, 33] {
+            tree.insert(leaf).unwrap();
+        }
+        let proof = tree.proof(2).unwrap();
+        assert!(proof.verify_with(tree.get_last_root(), tree.hasher()));
+        assert!(!proof.verify(tree.get_last_root()));
+    }
+}
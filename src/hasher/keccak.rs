@@ -0,0 +1,61 @@
+use primitive_types::U256;
+use tiny_keccak::{Hasher as _, Keccak};
+
+use crate::constants::FIELD_SIZE;
+use crate::hasher::MerkleHasher;
+
+pub fn keccak256(data: &[u8]) -> [u8; 32] {
+    #[cfg(feature = "precompile")]
+    if let Some(accelerated) = crate::precompile::keccak256() {
+        return accelerated(data);
+    }
+    let mut keccak = Keccak::v256();
+    let mut output = [0u8; 32];
+    keccak.update(data);
+    keccak.finalize(&mut output);
+    output
+}
+
+/// `keccak256(data)` read as a big-endian integer and reduced into `FIELD_SIZE`.
+pub fn hash_to_field(data: &[u8]) -> u128 {
+    (U256::from_big_endian(&keccak256(data)) % U256::from(FIELD_SIZE)).as_u128()
+}
+
+/// Keccak-256 node hasher matching
+/// `uint256(keccak256(abi.encode(left, right))) % type(uint128).max` in
+/// Solidity, with both children encoded as `uint256`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Keccak256;
+
+impl MerkleHasher for Keccak256 {
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        let mut data = [0u8; 64];
+        data[16..32].copy_from_slice(&left.to_be_bytes());
+        data[48..].copy_from_slice(&right.to_be_bytes());
+        hash_to_field(&data)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_keccak() {
+        assert_eq!(
+            hex::encode(keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        let digest = U256::from_big_endian(&keccak256(b"abc"));
+        assert_eq!(hash_to_field(b"abc"), (digest % U256::from(FIELD_SIZE)).as_u128());
+        assert_ne!(Keccak256.hash_pair(1, 2), Keccak256.hash_pair(2, 1));
+
+        let mut tree = MerkleTree::with_hasher(4, Keccak256);
+        tree.insert(11).unwrap();
+        tree.insert(22).unwrap();
+        let proof = tree.proof(1).unwrap();
+        assert!(proof.verify_with(tree.get_last_root(), tree.hasher()));
+        assert!(!proof.verify(tree.get_last_root()));
+    }
+}
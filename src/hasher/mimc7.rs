@@ -0,0 +1,53 @@
+use std::sync::OnceLock;
+
+use crate::curve::field::Fr;
+use crate::hasher::keccak_chain;
+
+pub const SEED: &str = "mimc";
+pub const NROUNDS: usize = 91;
+
+/// circomlib MiMC-7 round constants: `c[0] = 0` and `c[i]` is the i-th link of a
+/// keccak256 chain starting at `keccak256(SEED)`, reduced into the field.
+pub fn constants() -> &'static [Fr] {
+    static CONSTANTS: OnceLock<Vec<Fr>> = OnceLock::new();
+    CONSTANTS.get_or_init(|| {
+        let mut constants: Vec<Fr> = keccak_chain(SEED.as_bytes(), NROUNDS).into_iter().map(Fr::from_u256).collect();
+        constants[0] = Fr::zero();
+        constants
+    })
+}
+
+/// circomlib `mimc7.hash(x, k)`.
+pub fn hash(x: Fr, k: Fr) -> Fr {
+    let constants = constants();
+    let mut r = Fr::zero();
+    for (i, c) in constants.iter().enumerate() {
+        let t = if i == 0 { x + k } else { r + k + *c };
+        let t2 = t.square();
+        let t4 = t2.square();
+        r = t4 * t2 * t;
+    }
+    r + k
+}
+
+/// circomlib `mimc7.multiHash(arr, key)`.
+pub fn multi_hash(inputs: &[Fr], key: Fr) -> Fr {
+    let mut r = key;
+    for input in inputs {
+        r = r + *input + hash(*input, r);
+    }
+    r
+}
+
+#[cfg(test)]
+mod tests {
+    use primitive_types::U256;
+
+    use super::*;
+
+    #[test]
+    fn test_circomlib_vector() {
+        let expected = U256::from_str_radix("176c6eefc3fdf8d6136002d8e6f7a885bbd1c4e3957b93ddc1ec3ae7859f1a08", 16).unwrap();
+        assert_eq!(hash(Fr::from(1u64), Fr::from(2u64)).to_u256(), expected);
+    }
+}
@@ -0,0 +1,54 @@
+use crate::hasher::Hasher;
+
+/// MiMC-n/n block cipher without the Feistel structure:
+/// `x_{i+1} = (x_i + k + c_i)^e` for every round, then `+ k`.
+///
+/// Uses the prime, constants and exponent of `hasher`; a classic instance needs
+/// about `log_e(p)` rounds, more than the Feistel sponge.
+pub fn encrypt(hasher: &Hasher, x: u128, k: u128) -> u128 {
+    let mut r = x;
+    for c in &hasher.c[..hasher.n_rounds as usize] {
+        r = hasher.sbox(hasher.add_mod(hasher.add_mod(r, k), *c));
+    }
+    hasher.add_mod(r, k)
+}
+
+/// Miyaguchi-Preneel over `encrypt`, as gnark's MiMC: starting from `h = 0`,
+/// each input `m` sets `h = E_h(m) + h + m`.
+pub fn hash(hasher: &Hasher, inputs: &[u128]) -> u128 {
+    inputs.iter().fold(0, |h, m| {
+        let e = encrypt(hasher, *m, h);
+        hasher.add_mod(hasher.add_mod(e, h), *m)
+    })
+}
+
+/// Two-to-one compression for Merkle nodes.
+pub fn compress(hasher: &Hasher, left: u128, right: u128) -> u128 {
+    hash(hasher, &[left, right])
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+
+    use super::*;
+
+    #[test]
+    fn test_encrypt_is_permutation() {
+        let hasher = Hasher::new(7, 3, vec![0, 3, 5]).unwrap();
+        let outputs: HashSet<u128> = (0..7).map(|x| encrypt(&hasher, x, 4)).collect();
+        assert_eq!(outputs.len(), 7);
+        assert!(outputs.iter().all(|y| *y < 7));
+    }
+
+    #[test]
+    fn test_compress() {
+        let hasher = Hasher::default();
+        let node = compress(&hasher, 1, 2);
+        assert_eq!(node, hash(&hasher, &[1, 2]));
+        assert_ne!(node, compress(&hasher, 2, 1));
+
+        let h1 = encrypt(&hasher, 1, 0) + 1;
+        assert_eq!(hash(&hasher, &[1]), h1);
+    }
+}
@@ -0,0 +1,73 @@
+use std::sync::OnceLock;
+
+use crate::curve::field::Fr;
+use crate::hasher::{fr_to_field, keccak_chain, FrHasher, MerkleHasher};
+
+pub const SEED: &str = "mimcsponge";
+pub const NROUNDS: usize = 220;
+
+/// circomlib MiMCSponge round constants: the keccak256 chain from `SEED`
+/// reduced into the field, with the first and last constant set to zero.
+pub fn constants() -> &'static [Fr] {
+    static CONSTANTS: OnceLock<Vec<Fr>> = OnceLock::new();
+    CONSTANTS.get_or_init(|| {
+        let mut constants: Vec<Fr> = keccak_chain(SEED.as_bytes(), NROUNDS).into_iter().map(Fr::from_u256).collect();
+        constants[0] = Fr::zero();
+        constants[NROUNDS - 1] = Fr::zero();
+        constants
+    })
+}
+
+/// circomlib `mimcsponge.hash(xL, xR, k)`, the Feistel permutation the
+/// `MiMCSponge` contract exposes.
+pub fn feistel(mut xl: Fr, mut xr: Fr, k: Fr) -> (Fr, Fr) {
+    for (i, c) in constants().iter().enumerate() {
+        let t = xl + k + *c;
+        let t2 = t.square();
+        let t5 = t2.square() * t;
+        if i < NROUNDS - 1 {
+            (xl, xr) = (xr + t5, xl);
+        } else {
+            xr = xr + t5;
+        }
+    }
+    (xl, xr)
+}
+
+/// `MerkleTreeWithHistory.hashLeftRight` from Tornado Cash: both children are
+/// absorbed into one sponge with a zero key.
+pub fn hash_left_right(left: Fr, right: Fr) -> Fr {
+    let (r, c) = feistel(left, Fr::zero(), Fr::zero());
+    feistel(r + right, c, Fr::zero()).0
+}
+
+/// `hash_left_right` as a BN254 tree hasher.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct MimcSponge;
+
+impl FrHasher for MimcSponge {
+    fn hash_pair(&self, left: Fr, right: Fr) -> Fr {
+        hash_left_right(left, right)
+    }
+}
+
+impl MerkleHasher for MimcSponge {
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        fr_to_field(hash_left_right(Fr::from(left), Fr::from(right)))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use primitive_types::U256;
+
+    use super::*;
+
+    #[test]
+    fn test_tornado_zero_level() {
+        let zero = U256::from_str_radix("2fe54c60d3acabf3343a35b6eba15db4821b340f76e741e2249685ed4899af6c", 16).unwrap();
+        let one = U256::from_str_radix("256a6135777eee2fd26f54b8b7037a25439d5235caee224154186d2b8a52e31d", 16).unwrap();
+        let zero = Fr::from_canonical(zero).unwrap();
+        assert_eq!(hash_left_right(zero, zero).to_u256(), one);
+    }
+}
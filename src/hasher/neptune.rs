@@ -0,0 +1,126 @@
+use primitive_types::U256;
+
+use crate::curve::field::Fr;
+use crate::hasher::poseidon::{cauchy_matrix, Poseidon, FULL_ROUNDS};
+use crate::hasher::FrHasher;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// Field size the round numbers are computed for, fixed in Neptune whatever
+/// the field.
+const ROUND_NUMBERS_FIELD_BITS: f32 = 255.0;
+const SECURITY_LEVEL: f32 = 128.0;
+/// Largest arity whose Merkle domain tag `2^arity - 1` fits the field.
+pub const MAX_ARITY: usize = 253;
+
+/// Neptune's `Strength`: `Strengthened` adds 25% to the partial rounds.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum Strength {
+    #[default]
+    Standard,
+    Strengthened
+}
+
+/// Partial rounds Neptune uses for `arity` inputs (width `arity + 1`): the
+/// cheapest secure round numbers with a 7.5% margin, times 1.25 when
+/// strengthened.
+pub fn partial_rounds(arity: usize, strength: Strength) -> usize {
+    let width = arity + 1;
+    let mut best = (usize::MAX, 0, 0);
+    for full_rounds in (2..=1000).step_by(2) {
+        for partial_rounds in 4..200 {
+            if round_numbers_are_secure(width, full_rounds, partial_rounds) {
+                let full_rounds = full_rounds + 2;
+                let partial_rounds = (1.075 * partial_rounds as f32).ceil() as usize;
+                let sboxes = full_rounds * width + partial_rounds;
+                if sboxes < best.0 || (sboxes == best.0 && full_rounds < best.1) {
+                    best = (sboxes, full_rounds, partial_rounds);
+                }
+            }
+        }
+    }
+    match strength {
+        Strength::Standard => best.2,
+        Strength::Strengthened => (best.2 as f32 * 1.25).ceil() as usize
+    }
+}
+
+fn round_numbers_are_secure(width: usize, full_rounds: usize, partial_rounds: usize) -> bool {
+    let (t, rp, n, m) = (width as f32, partial_rounds as f32, ROUND_NUMBERS_FIELD_BITS, SECURITY_LEVEL);
+    let rf_stat = if m <= (n - 3.0) * (t + 1.0) { 6.0 } else { 10.0 };
+    let rf_interp = 0.43 * m + t.log2() - rp;
+    let rf_grob_1 = 0.21 * n - rp;
+    let rf_grob_2 = (0.14 * n - 1.0 - rp) / (t - 1.0);
+    let rf_max = [rf_stat, rf_interp, rf_grob_1, rf_grob_2].iter().map(|rf| rf.ceil() as i64).max().unwrap_or(0);
+    full_rounds as i64 >= rf_max
+}
+
+/// Poseidon with Neptune's parameter choices, over the BN254 scalar field:
+/// width `arity + 1`, 8 full rounds, `partial_rounds(arity, strength)`, Grain
+/// constants and the Cauchy MDS matrix over `xs = 0..t`, `ys = t..2t`.
+///
+/// Neptune (and Filecoin) run over BLS12-381, so hashes only match a Neptune
+/// instance over BN254, not Filecoin's trees.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Neptune {
+    poseidon: Poseidon,
+    arity: usize
+}
+
+impl Neptune {
+    pub fn new(arity: usize, strength: Strength) -> Result<Self, SolanaError> {
+        if arity == 0 || arity > MAX_ARITY {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Neptune arity must be between 1 and 253"));
+        }
+        let width = arity + 1;
+        let points: Vec<Fr> = (0..2 * width as u64).map(Fr::from).collect();
+        let mds = cauchy_matrix(&points[..width], &points[width..]).expect("Points are distinct");
+        let poseidon = Poseidon::with_mds(width, FULL_ROUNDS, partial_rounds(arity, strength), mds)?;
+        Ok(Neptune { poseidon, arity })
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn poseidon(&self) -> &Poseidon {
+        &self.poseidon
+    }
+
+    /// Merkle tree node: domain tag `2^arity - 1` in the first element,
+    /// children after it, output taken from the second element.
+    pub fn hash_children(&self, children: &[Fr]) -> Result<Fr, SolanaError> {
+        let tag = Fr::from_u256((U256::one() << self.arity) - 1);
+        let mut state = vec![tag];
+        state.extend_from_slice(children);
+        self.poseidon.permute(&mut state)?;
+        Ok(state[1])
+    }
+}
+
+impl FrHasher for Neptune {
+    fn hash_pair(&self, left: Fr, right: Fr) -> Fr {
+        self.hash_children(&[left, right]).expect("Tree hashers take two inputs")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_numbers() {
+        // Neptune's partial rounds for its common arities.
+        for (arity, standard, strengthened) in [(2, 55, 69), (4, 56, 70), (8, 57, 72), (11, 57, 72), (16, 59, 74), (24, 59, 74), (36, 60, 75)] {
+            assert_eq!(partial_rounds(arity, Strength::Standard), standard, "{}", arity);
+            assert_eq!(partial_rounds(arity, Strength::Strengthened), strengthened, "{}", arity);
+        }
+
+        let neptune = Neptune::new(2, Strength::Standard).unwrap();
+        assert_eq!(neptune.poseidon().width(), 3);
+        assert_eq!(neptune.poseidon().mds()[1][2], Fr::from(6u64).inverse().unwrap());
+        assert!(neptune.hash_children(&[Fr::one()]).is_err());
+        assert_ne!(neptune.hash_pair(Fr::one(), Fr::zero()), Neptune::new(2, Strength::Strengthened).unwrap().hash_pair(Fr::one(), Fr::zero()));
+        assert!(Neptune::new(0, Strength::Standard).is_err());
+        assert!(Neptune::new(MAX_ARITY + 1, Strength::Standard).is_err());
+    }
+}
@@ -0,0 +1,143 @@
+use std::sync::Mutex;
+
+use blake_hash::{Blake256, Digest};
+use primitive_types::U256;
+
+use crate::curve::babyjubjub::{Point, COFACTOR, SUBGROUP_ORDER};
+
+pub const GENERATOR_PREFIX: &str = "PedersenGenerator";
+pub const WINDOW_SIZE: usize = 4;
+pub const WINDOWS_PER_SEGMENT: usize = 50;
+
+const BITS_PER_SEGMENT: usize = WINDOW_SIZE * WINDOWS_PER_SEGMENT;
+
+/// circomlib `getBasePoint`: the first BLAKE-256 hash of
+/// `PedersenGenerator_<index>_<try>` that decodes to a curve point, times 8.
+pub fn base_point(index: usize) -> Point {
+    static BASES: Mutex<Vec<Point>> = Mutex::new(Vec::new());
+
+    let mut bases = BASES.lock().expect("Pedersen base cache is poisoned");
+    while bases.len() <= index {
+        let next = derive_generator(GENERATOR_PREFIX, bases.len());
+        bases.push(next);
+    }
+    bases[index]
+}
+
+/// The `getBasePoint` derivation with a caller-chosen prefix, for generators
+/// that must be independent of the Pedersen hash bases.
+pub fn derive_generator(prefix: &str, index: usize) -> Point {
+    let mut try_index = 0;
+    loop {
+        let seed = format!("{}_{:0>32}_{:0>32}", prefix, index, try_index);
+        let mut hash = [0u8; 32];
+        hash.copy_from_slice(&Blake256::digest(seed.as_bytes()));
+        hash[31] &= 0xbf;
+
+        if let Ok(point) = Point::decompress(&hash) {
+            let base = point.mul_scalar(U256::from(COFACTOR));
+            assert!(base.in_subgroup(), "Pedersen base point is not in the subgroup");
+            return base;
+        }
+        try_index += 1;
+    }
+}
+
+/// circomlib `pedersenHash`, returning the packed point.
+pub fn hash(message: &[u8]) -> [u8; 32] {
+    hash_point(message).compress()
+}
+
+/// circomlib `pedersenHash` before packing. Tornado Cash commitments and
+/// nullifier hashes are the `x` coordinate of this point.
+pub fn hash_point(message: &[u8]) -> Point {
+    let bits: Vec<bool> = message
+        .iter()
+        .flat_map(|byte| (0..8).map(move |i| (byte >> i) & 1 == 1))
+        .collect();
+    if bits.is_empty() {
+        return Point::identity();
+    }
+
+    let segments = (bits.len() - 1) / BITS_PER_SEGMENT + 1;
+    let mut accumulator = Point::identity();
+
+    for segment in 0..segments {
+        let windows = if segment == segments - 1 {
+            (bits.len() - (segments - 1) * BITS_PER_SEGMENT - 1) / WINDOW_SIZE + 1
+        } else {
+            WINDOWS_PER_SEGMENT
+        };
+
+        // Window values are signed, so positive and negative parts are summed separately.
+        let mut positive = U256::zero();
+        let mut negative = U256::zero();
+        for window in 0..windows {
+            let mut offset = segment * BITS_PER_SEGMENT + window * WINDOW_SIZE;
+            let mut value = 1u64;
+            for bit in 0..WINDOW_SIZE - 1 {
+                if offset >= bits.len() {
+                    break;
+                }
+                if bits[offset] {
+                    value += 1 << bit;
+                }
+                offset += 1;
+            }
+
+            let shifted = U256::from(value) << (window * (WINDOW_SIZE + 1));
+            if offset < bits.len() && bits[offset] {
+                negative += shifted;
+            } else {
+                positive += shifted;
+            }
+        }
+
+        let scalar = if positive >= negative {
+            positive - negative
+        } else {
+            SUBGROUP_ORDER - (negative - positive)
+        };
+        accumulator = accumulator.add(&base_point(segment).mul_scalar(scalar));
+    }
+
+    accumulator
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_circomlib_base_points() {
+        let expected = [
+            (
+                "10457101036533406547632367118273992217979173478358440826365724437999023779287",
+                "19824078218392094440610104313265183977899662750282163392862422243483260492317"
+            ),
+            (
+                "2671756056509184035029146175565761955751135805354291559563293617232983272177",
+                "2663205510731142763556352975002641716101654201788071096152948830924149045094"
+            )
+        ];
+        for (index, (x, y)) in expected.iter().enumerate() {
+            let base = base_point(index);
+            assert_eq!(base.x.to_u256(), U256::from_dec_str(x).unwrap());
+            assert_eq!(base.y.to_u256(), U256::from_dec_str(y).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_hash() {
+        // One byte is two 4-bit windows; a window with no bits set contributes 1.
+        assert_eq!(hash_point(&[0]), base_point(0).mul_scalar(U256::from(1 + 32)));
+        assert_eq!(hash_point(&[0x01]), base_point(0).mul_scalar(U256::from(2 + 32)));
+        assert_eq!(hash_point(&[0x08]), base_point(0).mul_scalar(SUBGROUP_ORDER - U256::from(1) + U256::from(32)));
+        assert_eq!(Point::decompress(&hash(&[1, 2, 3])).unwrap(), hash_point(&[1, 2, 3]));
+
+        // 31 + 31 bytes, the Tornado Cash note preimage, spans three segments.
+        let preimage = [7u8; 62];
+        assert!(hash_point(&preimage).in_subgroup());
+        assert_ne!(hash_point(&preimage), hash_point(&preimage[..61]));
+    }
+}
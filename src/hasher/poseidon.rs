@@ -0,0 +1,177 @@
+use primitive_types::U256;
+
+use crate::curve::field::{Fr, MODULUS};
+use crate::hasher::poseidon2::Grain;
+use crate::hasher::{fr_to_field, FrHasher, MerkleHasher};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+pub const FULL_ROUNDS: usize = 8;
+pub const SBOX_DEGREE: u64 = 5;
+
+/// circomlib's partial round counts for t = 2..=17 (`N_ROUNDS_P`).
+pub const CIRCOM_PARTIAL_ROUNDS: [usize; 16] = [56, 57, 56, 60, 60, 63, 64, 63, 60, 66, 60, 65, 70, 60, 64, 68];
+
+/// Poseidon over the BN254 scalar field as circomlib implements it: the state
+/// is `[0, inputs..]`, x^5 S-box, full rounds split around the partial ones and
+/// the output is the first element after the permutation.
+///
+/// Round constants and the Cauchy MDS matrix come from the Grain LFSR of the
+/// reference parameter script, in that order.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Poseidon {
+    width: usize,
+    full_rounds: usize,
+    partial_rounds: usize,
+    round_constants: Vec<Fr>,
+    mds: Vec<Vec<Fr>>
+}
+
+impl Default for Poseidon {
+    fn default() -> Self {
+        Poseidon::circom(2).expect("Two inputs are supported")
+    }
+}
+
+impl Poseidon {
+    /// circomlib `poseidon` for `inputs` inputs (1 to 16).
+    pub fn circom(inputs: usize) -> Result<Self, SolanaError> {
+        let partial_rounds = *CIRCOM_PARTIAL_ROUNDS
+            .get(inputs.wrapping_sub(1))
+            .ok_or_else(|| utils::err_with_code(ErrorCode::InvalidParameters, "circomlib Poseidon takes 1 to 16 inputs"))?;
+        Self::new(inputs + 1, FULL_ROUNDS, partial_rounds)
+    }
+
+    /// Instance with Grain-generated constants for any width and round count.
+    pub fn new(width: usize, full_rounds: usize, partial_rounds: usize) -> Result<Self, SolanaError> {
+        let (mut grain, round_constants) = Self::grain_constants(width, full_rounds, partial_rounds)?;
+        let mds = loop {
+            let points: Vec<Fr> = (0..2 * width).map(|_| grain.next_reduced_element()).collect();
+            if let Some(mds) = cauchy_matrix(&points[..width], &points[width..]) {
+                break mds;
+            }
+        };
+        Ok(Poseidon { width, full_rounds, partial_rounds, round_constants, mds })
+    }
+
+    /// Grain round constants with a caller-chosen `width` x `width` MDS matrix.
+    pub fn with_mds(width: usize, full_rounds: usize, partial_rounds: usize, mds: Vec<Vec<Fr>>) -> Result<Self, SolanaError> {
+        if mds.len() != width || mds.iter().any(|row| row.len() != width) {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "MDS matrix must be width x width"));
+        }
+        let (_, round_constants) = Self::grain_constants(width, full_rounds, partial_rounds)?;
+        Ok(Poseidon { width, full_rounds, partial_rounds, round_constants, mds })
+    }
+
+    fn grain_constants(width: usize, full_rounds: usize, partial_rounds: usize) -> Result<(Grain, Vec<Fr>), SolanaError> {
+        if width < 2 || !full_rounds.is_multiple_of(2) {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Poseidon needs a width of at least 2 and an even number of full rounds"));
+        }
+        let mut grain = Grain::new(MODULUS.bits(), width, full_rounds, partial_rounds);
+        let round_constants = (0..(full_rounds + partial_rounds) * width).map(|_| grain.next_field_element()).collect();
+        Ok((grain, round_constants))
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn partial_rounds(&self) -> usize {
+        self.partial_rounds
+    }
+
+    pub fn round_constants(&self) -> &[Fr] {
+        &self.round_constants
+    }
+
+    pub fn mds(&self) -> &[Vec<Fr>] {
+        &self.mds
+    }
+
+    pub fn permute(&self, state: &mut [Fr]) -> Result<(), SolanaError> {
+        if state.len() != self.width {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "State length does not match the Poseidon width")
+                .with_compared_values(self.width, state.len()));
+        }
+        let half = self.full_rounds / 2;
+        for (round, constants) in self.round_constants.chunks(self.width).enumerate() {
+            for (x, c) in state.iter_mut().zip(constants) {
+                *x = *x + *c;
+            }
+            if (half..half + self.partial_rounds).contains(&round) {
+                state[0] = sbox(state[0]);
+            } else {
+                for x in state.iter_mut() {
+                    *x = sbox(*x);
+                }
+            }
+            let mixed: Vec<Fr> = self
+                .mds
+                .iter()
+                .map(|row| row.iter().zip(state.iter()).fold(Fr::zero(), |acc, (m, x)| acc + *m * *x))
+                .collect();
+            state.copy_from_slice(&mixed);
+        }
+        Ok(())
+    }
+
+    /// circomlib `poseidon(inputs)`; takes exactly `width - 1` inputs.
+    pub fn hash(&self, inputs: &[Fr]) -> Result<Fr, SolanaError> {
+        let mut state = vec![Fr::zero(); 1];
+        state.extend_from_slice(inputs);
+        self.permute(&mut state)?;
+        Ok(state[0])
+    }
+}
+
+impl FrHasher for Poseidon {
+    fn hash_pair(&self, left: Fr, right: Fr) -> Fr {
+        self.hash(&[left, right]).expect("Tree hashers take two inputs")
+    }
+}
+
+impl MerkleHasher for Poseidon {
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        fr_to_field(FrHasher::hash_pair(self, Fr::from(left), Fr::from(right)))
+    }
+}
+
+/// `M[i][j] = 1 / (xs[i] + ys[j])`, `None` if the points are not distinct or a
+/// sum is zero.
+pub(crate) fn cauchy_matrix(xs: &[Fr], ys: &[Fr]) -> Option<Vec<Vec<Fr>>> {
+    let points: Vec<Fr> = xs.iter().chain(ys).copied().collect();
+    if points.iter().enumerate().any(|(i, p)| points[..i].contains(p)) {
+        return None;
+    }
+    xs.iter().map(|x| ys.iter().map(|y| (*x + *y).inverse()).collect()).collect()
+}
+
+fn sbox(x: Fr) -> Fr {
+    x.pow(U256::from(SBOX_DEGREE))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fr(hex: &str) -> Fr {
+        Fr::from_canonical(U256::from_str_radix(hex, 16).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_circomlib_vectors() {
+        let poseidon = Poseidon::default();
+        assert_eq!(poseidon.round_constants()[0], fr("0ee9a592ba9a9518d05986d656f40c2114c4993c11bb29938d21d47304cd8e6e"));
+        assert_eq!(poseidon.mds()[0][0], fr("109b7f411ba0e4c9b2b70caf5c36a7b194be7c11ad24378bfedb68592ba8118b"));
+        assert_eq!(
+            poseidon.hash(&[Fr::from(1u64), Fr::from(2u64)]).unwrap(),
+            fr("115cc0f5e7d690413df64c6b9662e9cf2a3617f2743245519e19607a4417189a")
+        );
+        assert_eq!(
+            poseidon.hash(&[Fr::zero(), Fr::zero()]).unwrap(),
+            fr("2098f5fb9e239eab3ceac3f27b81e481dc3124d55ffed523a839ee8446b64864")
+        );
+        assert!(poseidon.hash(&[Fr::one()]).is_err());
+        assert!(Poseidon::circom(0).is_err());
+        assert!(Poseidon::circom(17).is_err());
+    }
+}
@@ -0,0 +1,202 @@
+use primitive_types::U256;
+
+use crate::curve::field::{Fr, MODULUS};
+use crate::hasher::{fr_to_field, MerkleHasher};
+
+pub const WIDTH: usize = 3;
+pub const FULL_ROUNDS: usize = 8;
+pub const PARTIAL_ROUNDS: usize = 56;
+pub const SBOX_DEGREE: u64 = 5;
+
+// Diagonal of `M_I - J` for t = 3 from the reference parameters.
+const INTERNAL_DIAGONAL: [u64; WIDTH] = [1, 1, 2];
+
+/// Poseidon2 over the BN254 scalar field with the published t = 3 instance
+/// (HorizenLabs `poseidon2_instance_bn256`): x^5 S-box, 8 full and 56 partial
+/// rounds, `circ(2, 1, 1)` external layer and `J + diag(1, 1, 2)` internal layer.
+///
+/// Round constants are generated with the Grain LFSR of the reference scripts;
+/// partial rounds only take a constant for the first element.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Poseidon2 {
+    round_constants: Vec<[Fr; WIDTH]>
+}
+
+impl Default for Poseidon2 {
+    fn default() -> Self {
+        Poseidon2::bn254()
+    }
+}
+
+impl Poseidon2 {
+    pub fn bn254() -> Self {
+        let mut grain = Grain::new(MODULUS.bits(), WIDTH, FULL_ROUNDS, PARTIAL_ROUNDS);
+        let round_constants = (0..FULL_ROUNDS + PARTIAL_ROUNDS)
+            .map(|round| {
+                if Self::is_partial_round(round) {
+                    [grain.next_field_element(), Fr::zero(), Fr::zero()]
+                } else {
+                    [grain.next_field_element(), grain.next_field_element(), grain.next_field_element()]
+                }
+            })
+            .collect();
+        Poseidon2 { round_constants }
+    }
+
+    pub fn round_constants(&self) -> &[[Fr; WIDTH]] {
+        &self.round_constants
+    }
+
+    pub fn permute(&self, state: &mut [Fr; WIDTH]) {
+        Self::external_layer(state);
+        for (round, constants) in self.round_constants.iter().enumerate() {
+            if Self::is_partial_round(round) {
+                state[0] = sbox(state[0] + constants[0]);
+                Self::internal_layer(state);
+            } else {
+                for (x, c) in state.iter_mut().zip(constants) {
+                    *x = sbox(*x + *c);
+                }
+                Self::external_layer(state);
+            }
+        }
+    }
+
+    /// Two-to-one compression: `permute([left, right, 0])[0]`.
+    pub fn compress(&self, left: Fr, right: Fr) -> Fr {
+        let mut state = [left, right, Fr::zero()];
+        self.permute(&mut state);
+        state[0]
+    }
+
+    fn is_partial_round(round: usize) -> bool {
+        (FULL_ROUNDS / 2..FULL_ROUNDS / 2 + PARTIAL_ROUNDS).contains(&round)
+    }
+
+    fn external_layer(state: &mut [Fr; WIDTH]) {
+        let sum = state[0] + state[1] + state[2];
+        for x in state.iter_mut() {
+            *x = *x + sum;
+        }
+    }
+
+    fn internal_layer(state: &mut [Fr; WIDTH]) {
+        let sum = state[0] + state[1] + state[2];
+        for (x, d) in state.iter_mut().zip(INTERNAL_DIAGONAL) {
+            *x = *x * Fr::from(d) + sum;
+        }
+    }
+}
+
+impl MerkleHasher for Poseidon2 {
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        fr_to_field(self.compress(Fr::from(left), Fr::from(right)))
+    }
+}
+
+fn sbox(x: Fr) -> Fr {
+    x.pow(U256::from(SBOX_DEGREE))
+}
+
+/// Self-shrinking Grain LFSR from the Poseidon reference parameter scripts,
+/// initialised for a prime field and an x^alpha S-box.
+pub(crate) struct Grain {
+    bits: [bool; 80],
+    field_bits: usize
+}
+
+impl Grain {
+    pub(crate) fn new(field_bits: usize, width: usize, full_rounds: usize, partial_rounds: usize) -> Self {
+        let fields = [(1, 2), (0, 4), (field_bits, 12), (width, 12), (full_rounds, 10), (partial_rounds, 10)];
+        let mut bits = [true; 80];
+        let mut i = 0;
+        for (value, length) in fields {
+            for bit in (0..length).rev() {
+                bits[i] = (value >> bit) & 1 == 1;
+                i += 1;
+            }
+        }
+
+        let mut grain = Grain { bits, field_bits };
+        for _ in 0..160 {
+            grain.step();
+        }
+        grain
+    }
+
+    fn step(&mut self) -> bool {
+        let b = &self.bits;
+        let new_bit = b[62] ^ b[51] ^ b[38] ^ b[23] ^ b[13] ^ b[0];
+        self.bits.rotate_left(1);
+        self.bits[79] = new_bit;
+        new_bit
+    }
+
+    fn next_bit(&mut self) -> bool {
+        loop {
+            let keep = self.step();
+            let bit = self.step();
+            if keep {
+                return bit;
+            }
+        }
+    }
+
+    fn next_integer(&mut self) -> U256 {
+        let mut value = U256::zero();
+        for _ in 0..self.field_bits {
+            value = (value << 1) | U256::from(self.next_bit() as u8);
+        }
+        value
+    }
+
+    /// Next `field_bits`-bit big-endian integer below the modulus.
+    pub(crate) fn next_field_element(&mut self) -> Fr {
+        loop {
+            if let Some(element) = Fr::from_canonical(self.next_integer()) {
+                return element;
+            }
+        }
+    }
+
+    /// Next `field_bits`-bit integer reduced into the field, as the reference
+    /// scripts sample the MDS matrix.
+    pub(crate) fn next_reduced_element(&mut self) -> Fr {
+        Fr::from_u256(self.next_integer())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    fn fr(hex: &str) -> Fr {
+        Fr::from_canonical(U256::from_str_radix(hex, 16).unwrap()).unwrap()
+    }
+
+    #[test]
+    fn test_reference_vector() {
+        let poseidon2 = Poseidon2::bn254();
+        assert_eq!(
+            poseidon2.round_constants()[0][0],
+            fr("1d066a255517b7fd8bddd3a93f7804ef7f8fcde48bb4c37a59a09a1a97052816")
+        );
+
+        let mut state = [Fr::from(0u64), Fr::from(1u64), Fr::from(2u64)];
+        poseidon2.permute(&mut state);
+        assert_eq!(state, [
+            fr("0bb61d24daca55eebcb1929a82650f328134334da98ea4f847f760054f4a3033"),
+            fr("303b6f7c86d043bfcbcc80214f26a30277a15d3f74ca654992defe7ff8d03570"),
+            fr("1ed25194542b12eef8617361c3ba7c52e660b145994427cc86296242cf766ec8")
+        ]);
+    }
+
+    #[test]
+    fn test_tree() {
+        let mut tree = MerkleTree::with_hasher(3, Poseidon2::default());
+        tree.insert(1).unwrap();
+        tree.insert(2).unwrap();
+        assert!(tree.proof(0).unwrap().verify_with(tree.get_last_root(), tree.hasher()));
+    }
+}
@@ -0,0 +1,235 @@
+use std::sync::OnceLock;
+
+use primitive_types::U256;
+
+use crate::constants::FIELD_SIZE;
+use crate::curve::field::Fr;
+use crate::hasher::keccak::keccak256;
+use crate::hasher::poseidon2::Poseidon2;
+use crate::hasher::mimc_sponge::{self, MimcSponge};
+use crate::hasher::poseidon::Poseidon;
+use crate::hasher::{Hasher, MerkleHasher};
+use crate::merkle_tree::bn254::Bn254MerkleTree;
+use crate::merkle_tree::MerkleTree;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+pub const STEALTH_MIMC_10: &str = "stealth_mimc_10";
+pub const MIMC_SPONGE_220: &str = "mimc_sponge_220";
+pub const MIMC_SPONGE_BN254: &str = "mimc_sponge_bn254";
+pub const POSEIDON_T3_BN254: &str = "poseidon_t3_bn254";
+pub const POSEIDON2_T3_BN254: &str = "poseidon2_t3_bn254";
+
+/// Every preset accepted by `by_name`. The BN254 hashes (Tornado's
+/// MiMCSponge, circomlib Poseidon, Poseidon2) have their output reduced into
+/// `FIELD_SIZE` to fit a `MerkleTree` node; `tornado_tree` and `semaphore_tree`
+/// give the full-width roots.
+pub const NAMES: [&str; 5] = [STEALTH_MIMC_10, MIMC_SPONGE_220, MIMC_SPONGE_BN254, POSEIDON_T3_BN254, POSEIDON2_T3_BN254];
+
+/// Node hash behind a preset name, so anything that stores the name
+/// (`SealedProof`, `ProofEnvelope`, the tree builder) can rebuild any preset.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PresetHasher {
+    Mimc(Hasher),
+    MimcSponge(MimcSponge),
+    Poseidon(Poseidon),
+    Poseidon2(Poseidon2)
+}
+
+impl MerkleHasher for PresetHasher {
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        match self {
+            PresetHasher::Mimc(hasher) => hasher.hash_pair(left, right),
+            PresetHasher::MimcSponge(hasher) => MerkleHasher::hash_pair(hasher, left, right),
+            PresetHasher::Poseidon(hasher) => MerkleHasher::hash_pair(hasher, left, right),
+            PresetHasher::Poseidon2(hasher) => hasher.hash_pair(left, right)
+        }
+    }
+
+    fn hash_children(&self, children: &[u128]) -> u128 {
+        match self {
+            PresetHasher::Mimc(hasher) => hasher.hash_children(children),
+            PresetHasher::MimcSponge(hasher) => hasher.hash_children(children),
+            PresetHasher::Poseidon(hasher) => hasher.hash_children(children),
+            PresetHasher::Poseidon2(hasher) => hasher.hash_children(children)
+        }
+    }
+
+    fn zero(&self, level: u8) -> u128 {
+        match self {
+            PresetHasher::Mimc(hasher) => hasher.zero(level),
+            PresetHasher::MimcSponge(hasher) => hasher.zero(level),
+            PresetHasher::Poseidon(hasher) => hasher.zero(level),
+            PresetHasher::Poseidon2(hasher) => hasher.zero(level)
+        }
+    }
+}
+
+/// Depth of the deployed Tornado Cash pools.
+pub const TORNADO_LEVELS: u8 = 20;
+/// Levels covered by `MerkleTreeWithHistory.zeros`.
+pub const TORNADO_ZEROS_LEN: usize = 32;
+/// Depths accepted by the Semaphore v3 group contracts.
+pub const SEMAPHORE_MIN_DEPTH: u8 = 16;
+pub const SEMAPHORE_MAX_DEPTH: u8 = 32;
+
+/// The hasher behind `MerkleTree`: 10 rounds with the embedded constant table.
+pub fn stealth_mimc_10() -> Hasher {
+    Hasher::default()
+}
+
+/// circomlib's MiMCSponge round count and `"mimcsponge"` constants, reduced
+/// into `FIELD_SIZE`.
+pub fn mimc_sponge_220() -> Hasher {
+    Hasher::from_seed("mimcsponge", 220, FIELD_SIZE).expect("Preset parameters are valid")
+}
+
+/// Tree over the published Poseidon2 BN254 t = 3 instance.
+pub fn poseidon2_bn254_tree(levels: u8) -> MerkleTree<Poseidon2> {
+    MerkleTree::with_hasher(levels, Poseidon2::bn254())
+}
+
+/// `MerkleTreeWithHistory.zeros(level)`: `keccak256("tornado") mod p` at level
+/// 0 and `hashLeftRight` of the level below above it, as BN254 field elements.
+/// `None` past the 32 levels the contract defines. These do not fit a
+/// `MerkleTree` node, which is 128 bits wide; use `tornado_tree` instead.
+pub fn tornado_zeros(level: u8) -> Option<U256> {
+    static ZEROS: OnceLock<Vec<U256>> = OnceLock::new();
+    let zeros = ZEROS.get_or_init(|| {
+        let mut zero = Fr::from_u256(U256::from_big_endian(&keccak256(b"tornado")));
+        let mut zeros = Vec::with_capacity(TORNADO_ZEROS_LEN);
+        for _ in 0..TORNADO_ZEROS_LEN {
+            zeros.push(zero.to_u256());
+            zero = mimc_sponge::hash_left_right(zero, zero);
+        }
+        zeros
+    });
+    zeros.get(level as usize).copied()
+}
+
+/// Empty Tornado Cash pool: `TORNADO_LEVELS` levels of `hashLeftRight` seeded
+/// from `tornado_zeros`, so roots compare directly with `MerkleTreeWithHistory`.
+pub fn tornado_tree() -> Bn254MerkleTree<MimcSponge> {
+    let zeros = (0..=TORNADO_LEVELS)
+        .map(|level| Fr::from_u256(tornado_zeros(level).expect("Level is within the zeros table")))
+        .collect();
+    Bn254MerkleTree::from_zeros(MimcSponge, zeros).expect("Preset parameters are valid")
+}
+
+/// Semaphore tree with the off-chain default zero leaf 0: circomlib Poseidon
+/// over two inputs, depth 16 to 32. Semaphore v2 groups and v3 groups built
+/// from a zero value of 0 share these roots; on-chain v3 groups seed the zeros
+/// from their id instead, see `semaphore_group_tree`. Semaphore v4 groups are
+/// LeanIMTs, which have no zeros (the empty root is 0 and the tree grows with
+/// its members), so they are not covered by a fixed-depth tree.
+pub fn semaphore_tree(depth: u8) -> Result<Bn254MerkleTree<Poseidon>, SolanaError> {
+    semaphore_with_zero(depth, Fr::zero())
+}
+
+/// `SemaphoreGroups._createGroup`: the zero leaf is
+/// `uint256(keccak256(abi.encodePacked(groupId))) >> 8`.
+pub fn semaphore_group_tree(depth: u8, group_id: U256) -> Result<Bn254MerkleTree<Poseidon>, SolanaError> {
+    semaphore_with_zero(depth, semaphore_zero_value(group_id))
+}
+
+pub fn semaphore_zero_value(group_id: U256) -> Fr {
+    let mut bytes = [0u8; 32];
+    group_id.to_big_endian(&mut bytes);
+    Fr::from_u256(U256::from_big_endian(&keccak256(&bytes)) >> 8)
+}
+
+fn semaphore_with_zero(depth: u8, zero_leaf: Fr) -> Result<Bn254MerkleTree<Poseidon>, SolanaError> {
+    if !(SEMAPHORE_MIN_DEPTH..=SEMAPHORE_MAX_DEPTH).contains(&depth) {
+        return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Semaphore trees have a depth between 16 and 32"));
+    }
+    Bn254MerkleTree::with_zero_leaf(Poseidon::default(), depth, zero_leaf)
+}
+
+pub fn by_name(name: &str) -> Option<PresetHasher> {
+    match name {
+        STEALTH_MIMC_10 => Some(PresetHasher::Mimc(stealth_mimc_10())),
+        MIMC_SPONGE_220 => Some(PresetHasher::Mimc(mimc_sponge_220())),
+        MIMC_SPONGE_BN254 => Some(PresetHasher::MimcSponge(MimcSponge)),
+        POSEIDON_T3_BN254 => Some(PresetHasher::Poseidon(Poseidon::default())),
+        POSEIDON2_T3_BN254 => Some(PresetHasher::Poseidon2(Poseidon2::bn254())),
+        _ => None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_by_name() {
+        for name in NAMES {
+            assert!(by_name(name).is_some(), "{}", name);
+        }
+        assert_eq!(by_name(STEALTH_MIMC_10).unwrap(), PresetHasher::Mimc(Hasher::default()));
+        assert_eq!(by_name(MIMC_SPONGE_220).unwrap(), PresetHasher::Mimc(mimc_sponge_220()));
+
+        let poseidon = by_name(POSEIDON_T3_BN254).unwrap();
+        let expected = Poseidon::default().hash(&[Fr::from(1u64), Fr::from(2u64)]).unwrap();
+        assert_eq!(poseidon.hash_pair(1, 2), crate::hasher::fr_to_field(expected));
+        assert_eq!(by_name(POSEIDON2_T3_BN254).unwrap().hash_pair(1, 2), Poseidon2::bn254().hash_pair(1, 2));
+        assert!(by_name("poseidon").is_none());
+    }
+
+    #[test]
+    fn test_tornado_zeros() {
+        // From the deployed MerkleTreeWithHistory contract.
+        for (level, expected) in [
+            (0, "2fe54c60d3acabf3343a35b6eba15db4821b340f76e741e2249685ed4899af6c"),
+            (2, "1151949895e82ab19924de92c40a3d6f7bcb60d92b00504b8199613683f0c200"),
+            (19, "198622acbd783d1b0d9064105b1fc8e4d8889de95c4c519b3f635809fe6afc05"),
+            (20, "29d7ed391256ccc3ea596c86e933b89ff339d25ea8ddced975ae2fe30b5296d4"),
+            (21, "19be59f2f0413ce78c0c3703a3a5451b1d7f39629fa33abd11548a76065b2967")
+        ] {
+            assert_eq!(tornado_zeros(level), Some(U256::from_str_radix(expected, 16).unwrap()), "{}", level);
+        }
+        assert!(tornado_zeros(TORNADO_LEVELS).is_some());
+        assert!(tornado_zeros(TORNADO_ZEROS_LEN as u8).is_none());
+    }
+
+    #[test]
+    fn test_tornado_tree() {
+        let zero = |level| Fr::from_u256(tornado_zeros(level).unwrap());
+        let mut tree = tornado_tree();
+        assert_eq!(tree.levels(), TORNADO_LEVELS);
+        assert_eq!(tree.get_last_root(), zero(TORNADO_LEVELS));
+
+        // The contract's ZERO_VALUE as the first deposit leaves the empty root.
+        tree.insert(zero(0)).unwrap();
+        assert_eq!(tree.get_last_root(), zero(TORNADO_LEVELS));
+
+        let mut tree = tornado_tree();
+        let commitment = Fr::from(1u64);
+        tree.insert(commitment).unwrap();
+        let root = (0..TORNADO_LEVELS).fold(commitment, |node, level| mimc_sponge::hash_left_right(node, zero(level)));
+        assert_eq!(tree.get_last_root(), root);
+        assert!(tree.is_known_root(zero(TORNADO_LEVELS)));
+    }
+
+    #[test]
+    fn test_semaphore_tree() {
+        let tree = semaphore_tree(20).unwrap();
+        // Poseidon zero-subtree table shared by the circomlib-based trees.
+        for (level, expected) in [
+            (2, "1069673dcdb12263df301a6ff584a7ec261a44cb9dc68df067a4774460b1f1e1"),
+            (3, "18f43331537ee2af2e3d758d50f72106467c6eea50371dd528d57eb2b856d238"),
+            (8, "2fa5e5f18f6027a6501bec864564472a616b2e274a41211a444cbe3a99f3cc61")
+        ] {
+            assert_eq!(tree.zero(level).unwrap().to_u256(), U256::from_str_radix(expected, 16).unwrap(), "{}", level);
+        }
+        // Depth-20 root computed from the same chain, kept as a regression value.
+        assert_eq!(
+            tree.get_last_root().to_u256(),
+            U256::from_str_radix("2134e76ac5d21aab186c2be1dd8f84ee880a1e46eaf712f9d371b6df22191f3e", 16).unwrap()
+        );
+        assert!(semaphore_tree(SEMAPHORE_MIN_DEPTH - 1).is_err());
+        assert!(semaphore_tree(SEMAPHORE_MAX_DEPTH + 1).is_err());
+
+        let group = semaphore_group_tree(SEMAPHORE_MIN_DEPTH, U256::one()).unwrap();
+        assert_eq!(group.zero(0), Some(semaphore_zero_value(U256::one())));
+        assert!(semaphore_zero_value(U256::one()).to_u256().bits() <= 248);
+    }
+}
@@ -0,0 +1,274 @@
+use primitive_types::{U256, U512};
+use tiny_keccak::{Hasher as _, Shake, Xof};
+
+use crate::curve::field::{Fr, MODULUS};
+use crate::hasher::{fr_to_field, gcd, inverse_exponent, MerkleHasher};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+pub const DEFAULT_STATE_WIDTH: usize = 3;
+pub const DEFAULT_CAPACITY: usize = 1;
+pub const DEFAULT_SECURITY_LEVEL: usize = 128;
+
+// Smallest generator of the multiplicative group of the BN254 scalar field.
+const PRIMITIVE_ELEMENT: u64 = 5;
+
+/// Rescue-Prime over the BN254 scalar field, with every parameter derived as in
+/// the reference implementation (Szepieniec, Ashur, Dhooghe 2020): the S-box
+/// exponent, the round count from the Groebner-basis bound, the MDS matrix
+/// from a Vandermonde matrix and the round constants from SHAKE256 seeded with
+/// `Rescue-XLIX(p,m,capacity,security_level)`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RescuePrime {
+    width: usize,
+    capacity: usize,
+    alpha: u64,
+    alpha_inv: U256,
+    rounds: usize,
+    mds: Vec<Vec<Fr>>,
+    round_constants: Vec<Fr>
+}
+
+impl Default for RescuePrime {
+    fn default() -> Self {
+        RescuePrime::new(DEFAULT_STATE_WIDTH, DEFAULT_CAPACITY, DEFAULT_SECURITY_LEVEL)
+            .expect("Default Rescue-Prime parameters are valid")
+    }
+}
+
+impl RescuePrime {
+    pub fn new(width: usize, capacity: usize, security_level: usize) -> Result<Self, SolanaError> {
+        if capacity == 0 || capacity >= width {
+            return Err(utils::err_with_code(
+                ErrorCode::InvalidParameters,
+                "Rescue-Prime capacity must be between one and the state width"
+            ));
+        }
+        if security_level == 0 || security_level > 256 {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Rescue-Prime security level must be 1 to 256 bits"));
+        }
+
+        let p_minus_one = MODULUS - 1;
+        let mut alpha = 3u64;
+        while gcd(alpha as u128, (p_minus_one % alpha).as_u128()) != 1 {
+            alpha += 1;
+        }
+
+        let rounds = Self::number_of_rounds(width, capacity, security_level, alpha);
+        Ok(RescuePrime {
+            width,
+            capacity,
+            alpha,
+            alpha_inv: inverse_exponent(alpha),
+            rounds,
+            mds: Self::mds_matrix(width),
+            round_constants: Self::round_constants(width, capacity, security_level, rounds)
+        })
+    }
+
+    pub fn width(&self) -> usize {
+        self.width
+    }
+
+    pub fn rate(&self) -> usize {
+        self.width - self.capacity
+    }
+
+    pub fn rounds(&self) -> usize {
+        self.rounds
+    }
+
+    pub fn alpha(&self) -> u64 {
+        self.alpha
+    }
+
+    pub fn permute(&self, state: &mut [Fr]) -> Result<(), SolanaError> {
+        if state.len() != self.width {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Rescue-Prime state has the wrong width")
+                .with_compared_values(self.width, state.len()));
+        }
+        let m = self.width;
+        for round in 0..self.rounds {
+            for x in state.iter_mut() {
+                *x = x.pow(U256::from(self.alpha));
+            }
+            self.apply_mds(state);
+            for (x, c) in state.iter_mut().zip(&self.round_constants[round * 2 * m..]) {
+                *x = *x + *c;
+            }
+
+            for x in state.iter_mut() {
+                *x = x.pow(self.alpha_inv);
+            }
+            self.apply_mds(state);
+            for (x, c) in state.iter_mut().zip(&self.round_constants[round * 2 * m + m..]) {
+                *x = *x + *c;
+            }
+        }
+        Ok(())
+    }
+
+    /// Reference `rescue_prime_hash`: the input is padded with a one and then
+    /// zeros to a multiple of the rate, absorbed, and the rate part is returned.
+    pub fn hash(&self, inputs: &[Fr]) -> Vec<Fr> {
+        let rate = self.rate();
+        let mut padded = inputs.to_vec();
+        padded.push(Fr::one());
+        while !padded.len().is_multiple_of(rate) {
+            padded.push(Fr::zero());
+        }
+
+        let mut state = vec![Fr::zero(); self.width];
+        for chunk in padded.chunks(rate) {
+            for (x, input) in state.iter_mut().zip(chunk) {
+                *x = *x + *input;
+            }
+            self.permute(&mut state).expect("State has the permutation width");
+        }
+        state.truncate(rate);
+        state
+    }
+
+    fn apply_mds(&self, state: &mut [Fr]) {
+        let result: Vec<Fr> = self
+            .mds
+            .iter()
+            .map(|row| row.iter().zip(state.iter()).fold(Fr::zero(), |acc, (a, b)| acc + *a * *b))
+            .collect();
+        state.copy_from_slice(&result);
+    }
+
+    fn number_of_rounds(width: usize, capacity: usize, security_level: usize, alpha: u64) -> usize {
+        let rate = width - capacity;
+        let dcon = |n: usize| (alpha as usize - 1) * width * (n - 1) / 2 + 2;
+        let v = |n: usize| width * (n - 1) + rate;
+
+        let target = U512::one() << security_level;
+        let mut l1 = 1;
+        while l1 < 25 {
+            if binomial_squared_exceeds(v(l1) + dcon(l1), v(l1), target) {
+                break;
+            }
+            l1 += 1;
+        }
+        (3 * l1.max(5)).div_ceil(2)
+    }
+
+    fn round_constants(width: usize, capacity: usize, security_level: usize, rounds: usize) -> Vec<Fr> {
+        let bytes_per_int = MODULUS.bits().div_ceil(8) + 1;
+        let count = 2 * width * rounds;
+        let seed = format!("Rescue-XLIX({},{},{},{})", MODULUS, width, capacity, security_level);
+
+        let mut shake = Shake::v256();
+        shake.update(seed.as_bytes());
+        let mut bytes = vec![0u8; bytes_per_int * count];
+        shake.squeeze(&mut bytes);
+
+        bytes
+            .chunks(bytes_per_int)
+            .map(|chunk| {
+                let reduced = U512::from_little_endian(chunk) % U512::from(MODULUS);
+                Fr::from_u256(U256::try_from(reduced).expect("Reduced value is below p"))
+            })
+            .collect()
+    }
+
+    // Transpose of the right half of the reduced echelon form of
+    // `V[i][j] = g^(i * j)`, `0 <= i < m`, `0 <= j < 2m`.
+    fn mds_matrix(width: usize) -> Vec<Vec<Fr>> {
+        let g = Fr::from(PRIMITIVE_ELEMENT);
+        let mut v: Vec<Vec<Fr>> = (0..width)
+            .map(|i| (0..2 * width).map(|j| g.pow(U256::from(i * j))).collect())
+            .collect();
+
+        for column in 0..width {
+            let pivot = (column..width)
+                .find(|row| !v[*row][column].is_zero())
+                .expect("Vandermonde matrix has full rank");
+            v.swap(column, pivot);
+
+            let inverse = v[column][column].inverse().expect("Pivot is non-zero");
+            for x in v[column].iter_mut() {
+                *x = *x * inverse;
+            }
+            for row in 0..width {
+                if row != column && !v[row][column].is_zero() {
+                    let factor = v[row][column];
+                    let pivot_row = v[column].clone();
+                    for (x, y) in v[row].iter_mut().zip(pivot_row) {
+                        *x = *x - factor * y;
+                    }
+                }
+            }
+        }
+
+        (0..width).map(|i| (0..width).map(|j| v[j][width + i]).collect()).collect()
+    }
+}
+
+// Whether `binomial(n, k)^2 > target`, stopping early once the binomial is
+// large enough that its square is known to exceed any 512-bit target.
+fn binomial_squared_exceeds(n: usize, k: usize, target: U512) -> bool {
+    let mut binomial = U512::one();
+    for i in 0..k {
+        binomial = binomial * U512::from(n - i) / U512::from(i + 1);
+        if binomial.bits() > 256 {
+            return true;
+        }
+    }
+    binomial * binomial > target
+}
+
+impl MerkleHasher for RescuePrime {
+    /// The first rate element of `hash([left, right])`, reduced into `FIELD_SIZE`.
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        fr_to_field(self.hash(&[Fr::from(left), Fr::from(right)])[0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    fn fr(value: &str) -> Fr {
+        Fr::from_u256(U256::from_dec_str(value).unwrap())
+    }
+
+    #[test]
+    fn test_parameters() {
+        let rescue = RescuePrime::default();
+        assert_eq!(rescue.alpha(), 5);
+        assert_eq!(rescue.rounds(), 14);
+        assert_eq!(
+            rescue.round_constants[0],
+            fr("16315208746038078395621556119853320273013100435293928429550050637277758017174")
+        );
+        assert_eq!(rescue.mds[0][0], Fr::from(125u64));
+        assert_eq!(rescue.mds[0][1], -Fr::from(155u64));
+        assert_eq!(Fr::from(7u64).pow(U256::from(5)).pow(rescue.alpha_inv), Fr::from(7u64));
+        assert!(RescuePrime::new(3, 3, 128).is_err());
+    }
+
+    #[test]
+    fn test_hash() {
+        let rescue = RescuePrime::default();
+        let mut state = [Fr::zero(); 3];
+        rescue.permute(&mut state).unwrap();
+        assert_eq!(rescue.permute(&mut [Fr::zero(); 2]).unwrap_err().code(), ErrorCode::InvalidParameters);
+        assert_eq!(state[0], fr("15670477443787400659893356741836277574726642173860732653469352282629692244860"));
+
+        let output = rescue.hash(&[Fr::from(1u64), Fr::from(2u64)]);
+        assert_eq!(output, vec![
+            fr("19955277490808493510831169602631407111104744046414437667271324145367080531545"),
+            fr("649740822031455595330432760014348331074228589165010691290466708483664201035")
+        ]);
+    }
+
+    #[test]
+    fn test_tree() {
+        let mut tree = MerkleTree::with_hasher(3, RescuePrime::default());
+        tree.insert(1).unwrap();
+        tree.insert(2).unwrap();
+        assert!(tree.proof(1).unwrap().verify_with(tree.get_last_root(), tree.hasher()));
+    }
+}
@@ -0,0 +1,43 @@
+use primitive_types::U256;
+use sha2::{Digest, Sha256 as Sha256Digest};
+
+use crate::constants::FIELD_SIZE;
+use crate::hasher::MerkleHasher;
+
+/// SHA-256 node hasher for trees checked by SHA-256 circuit gadgets: the
+/// digest of both children as 32-byte big-endian words, reduced into
+/// `FIELD_SIZE`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Sha256;
+
+impl MerkleHasher for Sha256 {
+    fn hash_pair(&self, left: u128, right: u128) -> u128 {
+        let mut data = [0u8; 64];
+        data[16..32].copy_from_slice(&left.to_be_bytes());
+        data[48..].copy_from_slice(&right.to_be_bytes());
+        let digest = Sha256Digest::digest(data);
+        (U256::from_big_endian(&digest) % U256::from(FIELD_SIZE)).as_u128()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_sha256() {
+        let digest = Sha256Digest::digest([0u8; 64]);
+        assert_eq!(
+            hex::encode(digest),
+            "f5a5fd42d16a20302798ef6ed309979b43003d2320d9f0e8ea9831a92759fb4b"
+        );
+        assert_eq!(Sha256.hash_pair(0, 0), (U256::from_big_endian(&digest) % U256::from(FIELD_SIZE)).as_u128());
+
+        let mut tree = MerkleTree::with_hasher(4, Sha256);
+        tree.insert(11).unwrap();
+        let proof = tree.proof(0).unwrap();
+        assert!(proof.verify_with(tree.get_last_root(), tree.hasher()));
+        assert_eq!(tree.zero_hashes()[1], Sha256.hash_pair(0, 0));
+    }
+}
@@ -1,3 +1,22 @@
+pub mod commitment;
+pub mod constants;
+pub mod curve;
+pub mod encoding;
+pub mod group;
 pub mod hasher;
 pub mod merkle_tree;
-pub mod utils;
\ No newline at end of file
+#[cfg(feature = "napi")]
+pub mod node;
+pub mod note;
+pub mod nullifier_set;
+pub mod pool;
+#[cfg(feature = "precompile")]
+pub mod precompile;
+#[cfg(feature = "rng")]
+pub mod rng;
+pub mod root_policy;
+pub mod signature;
+pub mod stealth;
+pub mod utils;
+#[cfg(feature = "wasm")]
+pub mod wasm;
@@ -1,213 +1,887 @@
-use std::collections::HashMap;
+#[cfg(feature = "solana")]
+pub mod account;
+pub mod bn254;
+pub mod builder;
+mod compact;
+pub mod envelope;
+#[cfg(feature = "heapless")]
+pub mod bounded;
+#[cfg(feature = "evm")]
+pub mod evm;
+pub mod fixed;
+#[cfg(feature = "json")]
+pub mod json;
+pub mod multi;
+pub mod oz;
+pub mod replay;
+pub mod sealed;
+mod snapshot;
+mod stats;
+mod sharded;
+pub mod wide;
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+pub use builder::{MerkleTreeBuilder, TreeStorage};
+pub use compact::MerkleProofRef;
+pub use snapshot::SNAPSHOT_VERSION;
+pub use stats::{ProofFormat, ProofStats};
+use std::fmt::Display;
 use std::str::FromStr;
 
-use crate::{hasher::Hasher, utils::{self, SolanaError}};
+use crate::{constants::in_field, hasher::{HashToField, Hasher, IntoField, MerkleHasher}, utils::{self, Context, ErrorCode, Map, SolanaError}};
 
 pub const ROOT_HISTORY_SIZE: u8 = 30;
 
+/// Domain of the hasher behind `leaf_from_bytes`.
+pub const LEAF_DOMAIN: &str = "stealth-lib/leaf";
+
+/// Canonical leaf for raw data: `hash_to_field`'s padding (a `0x01` byte, zeros
+/// to a multiple of 15 bytes, big-endian chunks) absorbed by the default
+/// `Hasher` with domain `LEAF_DOMAIN`, so byte leaves never collide with
+/// plain `hash_to_field` outputs or internal nodes.
+pub fn leaf_from_bytes(bytes: &[u8]) -> u128 {
+    let mut state = HashToField::with_hasher(Hasher::default().with_domain(LEAF_DOMAIN));
+    state.update(bytes);
+    state.finalize()
+}
+
+/// Incremental Merkle tree. The node hash is chosen by `H`; `MerkleTree::new`
+/// uses the MiMC `Hasher`, any other `MerkleHasher` goes through `with_hasher`.
 #[derive(Debug, Clone)]
-pub struct MerkleTree {
+pub struct MerkleTree<H: MerkleHasher = Hasher> {
+    hasher: H,
     levels: u8,
-    filled_subtrees: HashMap<u8, u128>,
-    roots: HashMap<u8, u128>,
+    filled_subtrees: Vec<u128>,
+    roots: Vec<u128>,
+    root_counts: Map<u128, usize>,
     current_root_index: u8,
-    next_index: u8
+    root_history_size: u8,
+    next_index: u64,
+    leaves: Vec<u128>,
+    leaf_indices: Map<u128, u64>,
+    strict_leaves: bool,
+    frontier_only: bool,
+    root_pending: bool,
+    zeros: Vec<u128>
+}
+
+/// Inclusion proof for a single leaf. `path_indices[i]` is 0 when the node at
+/// level `i` is a left child and 1 when it is a right child.
+///
+/// With the `serde` feature, bincode 1 with its default options writes the
+/// fields in order: `leaf` as 16 little-endian bytes, `leaf_index` as 8, then
+/// each list as a `u64` length followed by its items. This layout is stable.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode, parity_scale_codec::Decode))]
+#[cfg_attr(feature = "rlp", derive(alloy_rlp::RlpEncodable, alloy_rlp::RlpDecodable))]
+#[cfg_attr(feature = "ssz", derive(ssz_derive::Encode, ssz_derive::Decode))]
+#[cfg_attr(all(feature = "defmt", target_os = "none"), derive(defmt::Format))]
+pub struct MerkleProof {
+    pub leaf: u128,
+    pub leaf_index: u64,
+    pub path_elements: Vec<u128>,
+    pub path_indices: Vec<u8>
+}
+
+impl MerkleProof {
+    /// Root under the default `Hasher`, see `root_with` for other hashers.
+    pub fn root(&self) -> u128 {
+        self.root_with(&Hasher::default())
+    }
+
+    pub fn root_with<H: MerkleHasher>(&self, hasher: &H) -> u128 {
+        let mut current_level_hash = self.leaf;
+        for (sibling, index) in self.path_elements.iter().zip(&self.path_indices) {
+            let (left, right) = utils::order_pair(current_level_hash, *sibling, *index != 0);
+            current_level_hash = hasher.hash_pair(left, right);
+        }
+        current_level_hash
+    }
+
+    /// Verifies against the default MiMC `Hasher` only. A proof from a tree
+    /// built `with_hasher` always fails here; use `verify_with` and the same
+    /// hasher instead.
+    pub fn verify(&self, root: u128) -> bool {
+        self.verify_with(root, &Hasher::default())
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(leaf_index = self.leaf_index, depth = self.path_elements.len()), ret))]
+    pub fn verify_with<H: MerkleHasher>(&self, root: u128, hasher: &H) -> bool {
+        self.path_elements.len() == self.path_indices.len()
+            && utils::secure_eq(&self.root_with(hasher).to_le_bytes(), &root.to_le_bytes())
+    }
+
+    /// Checks that the leaf is `leaf_from_bytes(data)` before verifying the path.
+    pub fn verify_bytes(&self, data: &[u8], root: u128) -> bool {
+        self.leaf == leaf_from_bytes(data) && self.verify(root)
+    }
+
+    /// `verify_with` that explains a rejection: `InvalidProof` for mismatched
+    /// path lengths, path indices that do not spell out `leaf_index`, or a
+    /// different root (expected and computed roots in `compared_values`), and
+    /// `FieldOverflow` for a leaf or sibling outside the field.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(leaf_index = self.leaf_index, depth = self.path_elements.len()), err))]
+    pub fn verify_detailed<H: MerkleHasher>(&self, root: u128, hasher: &H) -> Result<(), SolanaError> {
+        if self.path_elements.len() != self.path_indices.len() {
+            return Err(utils::err_with_code(ErrorCode::InvalidProof, "Proof path lengths differ")
+                .with_compared_values(self.path_elements.len(), self.path_indices.len()));
+        }
+        if let Some(value) = std::iter::once(&self.leaf).chain(&self.path_elements).find(|value| !in_field(**value)) {
+            return Err(utils::err_with_code(ErrorCode::FieldOverflow, format!("Proof value {} is not a canonical field element", value)));
+        }
+
+        let mut index = self.leaf_index;
+        for (level, side) in self.path_indices.iter().enumerate() {
+            if *side as u64 != index % 2 {
+                return Err(utils::err_with_code(
+                    ErrorCode::InvalidProof,
+                    format!("Path index at level {} does not match leaf index {}", level, self.leaf_index)
+                )
+                .with_compared_values(index % 2, side));
+            }
+            index /= 2;
+        }
+        if index != 0 {
+            return Err(utils::err_with_code(ErrorCode::InvalidProof, "Leaf index does not fit in the proof depth"));
+        }
+
+        let computed = self.root_with(hasher);
+        if !utils::secure_eq(&computed.to_le_bytes(), &root.to_le_bytes()) {
+            return Err(utils::err_with_code(ErrorCode::InvalidProof, "Proof does not lead to the root").with_compared_values(root, computed));
+        }
+        Ok(())
+    }
+
+    /// Verification without a `MerkleProof` value, for targets with no heap:
+    /// bit `i` of `indices_bitmap` is `path_indices[i]`. Paths deeper than 32
+    /// levels, or bits set past the path, are rejected.
+    pub fn verify_slices<H: MerkleHasher>(leaf: u128, path: &[u128], indices_bitmap: u32, root: u128, hasher: &H) -> bool {
+        if path.len() > 32 || (path.len() < 32 && indices_bitmap >> path.len() != 0) {
+            return false;
+        }
+        let mut node = leaf;
+        for (level, sibling) in path.iter().enumerate() {
+            let (left, right) = utils::order_pair(node, *sibling, (indices_bitmap >> level) & 1 == 1);
+            node = hasher.hash_pair(left, right);
+        }
+        utils::secure_eq(&node.to_le_bytes(), &root.to_le_bytes())
+    }
+
+    /// Like `verify`, but fails with `FieldOverflow` when the leaf or a sibling
+    /// is not a canonical field element.
+    pub fn verify_strict(&self, root: u128) -> Result<bool, SolanaError> {
+        if !in_field(self.leaf) || !self.path_elements.iter().all(|sibling| in_field(*sibling)) {
+            return Err(utils::err_with_code(ErrorCode::FieldOverflow, "Proof value is not a canonical field element"));
+        }
+        Ok(self.verify(root))
+    }
+}
+
+/// Allocation-free inclusion check for zkVM guests and other constrained
+/// targets: the path is borrowed and the sides are the bits of `leaf_index`,
+/// so nothing is copied or decoded before hashing.
+pub fn verify_inclusion<H: MerkleHasher>(leaf: u128, leaf_index: u64, path: &[u128], root: u128, hasher: &H) -> bool {
+    if path.len() < 64 && leaf_index >> path.len() != 0 {
+        return false;
+    }
+    let mut node = leaf;
+    let mut index = leaf_index;
+    for sibling in path {
+        let (left, right) = utils::order_pair(node, *sibling, index & 1 == 1);
+        node = hasher.hash_pair(left, right);
+        index >>= 1;
+    }
+    utils::secure_eq(&node.to_le_bytes(), &root.to_le_bytes())
+}
+
+/// Append-only proof between two sizes of the same tree: the path of the
+/// last old leaf in the new tree. Its left siblings are complete subtrees
+/// shared by both trees, and replacing every right sibling by the empty
+/// subtree root turns the new root into the old one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode, parity_scale_codec::Decode))]
+pub struct ConsistencyProof {
+    pub old_size: u64,
+    pub new_size: u64,
+    pub proof: MerkleProof
+}
+
+impl ConsistencyProof {
+    pub fn verify(&self, old_root: u128, new_root: u128) -> bool {
+        self.verify_with(old_root, new_root, &Hasher::default())
+    }
+
+    pub fn verify_with<H: MerkleHasher>(&self, old_root: u128, new_root: u128, hasher: &H) -> bool {
+        if self.old_size == 0 || self.old_size > self.new_size || self.proof.leaf_index != self.old_size - 1 {
+            return false;
+        }
+        // Positions come from the size, never from the proof itself.
+        // A right sibling past `new_size` must be empty.
+        let mut index = self.proof.leaf_index as usize;
+        for (i, (sibling, side)) in self.proof.path_elements.iter().zip(&self.proof.path_indices).enumerate() {
+            if *side as usize != index % 2 {
+                return false;
+            }
+            let right_starts_after = (index + 1).checked_shl(i as u32).is_none_or(|start| start >= self.new_size as usize);
+            if index.is_multiple_of(2) && right_starts_after && *sibling != hasher.zero(i as u8) {
+                return false;
+            }
+            index /= 2;
+        }
+
+        let mut old_proof = self.proof.clone();
+        for (i, (sibling, side)) in old_proof.path_elements.iter_mut().zip(&self.proof.path_indices).enumerate() {
+            if *side == 0 {
+                *sibling = hasher.zero(i as u8);
+            }
+        }
+        self.proof.verify_with(new_root, hasher) && old_proof.verify_with(old_root, hasher)
+    }
+}
+
+/// Leaves appended after `from_size`, with the root they must produce, for
+/// syncing a mirror without a full snapshot.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode, parity_scale_codec::Decode))]
+#[cfg_attr(all(feature = "defmt", target_os = "none"), derive(defmt::Format))]
+pub struct TreeDelta {
+    pub from_size: u64,
+    pub leaves: Vec<u128>,
+    pub expected_root: u128
 }
 
 impl MerkleTree {
     pub fn new(levels: u8) -> Self {
+        Self::with_hasher(levels, Hasher::default())
+    }
+
+    pub fn zeros(i: u8) -> u128 {
+        Hasher::default().zero(i)
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    pub fn with_hasher(levels: u8, hasher: H) -> Self {
         let mut instance = MerkleTree {
+            hasher,
             levels,
-            filled_subtrees: HashMap::new(),
-            roots: HashMap::new(),
+            filled_subtrees: Vec::new(),
+            roots: Vec::with_capacity(ROOT_HISTORY_SIZE as usize),
+            root_counts: Map::new(),
             current_root_index: 0,
-            next_index: 0
+            root_history_size: ROOT_HISTORY_SIZE,
+            next_index: 0,
+            leaves: Vec::new(),
+            leaf_indices: Map::new(),
+            strict_leaves: false,
+            frontier_only: false,
+            root_pending: false,
+            zeros: Vec::new()
         };
+        instance.zeros = (0..levels).map(|i| instance.hasher.zero(i)).collect();
 
-        for i in 0..levels {
-            instance.filled_subtrees.insert(i, Self::zeros(i));
-        }
+        instance.filled_subtrees = instance.zeros.clone();
 
-        instance.roots.insert(0, Self::zeros(levels - 1));
+        let initial_root = instance.zeros[levels as usize - 1];
+        instance.set_root(0, initial_root);
         instance
     }
 
-    pub fn root_hash(&self) -> Option<&u128> {
-        self.roots.get(&self.current_root_index)
+    /// Empty subtree roots by level, computed once at construction.
+    pub fn zero_hashes(&self) -> &[u128] {
+        &self.zeros
     }
 
-    pub fn hash_left_right(&self, left: u128, right: u128) -> u128 {
-        let field_size: u128 = u128::from_str("340282366920938463463374607431768211455").expect("Failed to parse field size");
+    pub fn levels(&self) -> u8 {
+        self.levels
+    }
+
+    /// Number of leaves inserted so far.
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    /// Number of recent roots `is_known_root` accepts.
+    pub fn root_history_size(&self) -> u8 {
+        self.root_history_size
+    }
+
+    /// Rejects leaves outside the field on `insert` and `update` instead of
+    /// letting them wrap.
+    pub fn with_strict_leaves(mut self) -> Self {
+        self.strict_leaves = true;
+        self
+    }
+
+    pub fn strict_leaves(&self) -> bool {
+        self.strict_leaves
+    }
+
+    /// Keeps only the filled subtrees and root history, not the leaves, so
+    /// memory stays O(levels). `proof` and `update` then fail with
+    /// `LeavesNotRetained`. No root index map is kept either; `is_known_root`
+    /// scans the history instead.
+    pub fn with_frontier_only(mut self) -> Self {
+        self.frontier_only = true;
+        self.leaves = Vec::new();
+        self.leaf_indices = Map::new();
+        self.root_counts = Map::new();
+        self
+    }
+
+    pub fn frontier_only(&self) -> bool {
+        self.frontier_only
+    }
+
+    fn check_leaves_retained(&self) -> Result<(), SolanaError> {
+        if self.frontier_only {
+            return Err(utils::err_with_code(ErrorCode::LeavesNotRetained, "Tree keeps only its frontier"));
+        }
+        Ok(())
+    }
+
+    /// Number of leaves the tree can hold, `2^levels`, saturating at `u128::MAX`.
+    pub fn capacity(&self) -> u128 {
+        1u128.checked_shl(self.levels as u32).unwrap_or(u128::MAX)
+    }
+
+    fn check_capacity(&self) -> Result<(), SolanaError> {
+        if self.next_index as u128 >= self.capacity() || self.next_index == u64::MAX {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Merkle tree is full, no more leaves can be added"));
+        }
+        Ok(())
+    }
 
-        let mut r = left;
-        let c = 0_u128;
+    fn check_leaf(&self, leaf: u128) -> Result<(), SolanaError> {
+        if self.strict_leaves && !in_field(leaf) {
+            return Err(utils::err_with_code(ErrorCode::FieldOverflow, "Leaf is not a canonical field element"));
+        }
+        Ok(())
+    }
 
-        r = Hasher::mimc_sponge(r, c, field_size);        
-        r = r.wrapping_add(right) % field_size;
-        r = Hasher::mimc_sponge(r, c, field_size);
+    pub fn root_hash(&self) -> Option<&u128> {
+        self.roots.get(self.current_root_index as usize)
+    }
 
-        r
+    pub fn hash_left_right(&self, left: u128, right: u128) -> u128 {
+        self.hasher.hash_pair(left, right)
     }
 
-    pub fn insert(&mut self, leaf: u128) -> Result<u8, SolanaError> {
-        // if (self.next_index as usize) < 2_usize.pow(self.levels.into()) {
-        //     return Err(utils::err("Merkle tree is full, no more leaves can be added").into());
-        // }
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(depth = self.levels), ret, err))]
+    pub fn insert(&mut self, leaf: u128) -> Result<u64, SolanaError> {
+        self.check_capacity()?;
+        self.check_leaf(leaf)?;
 
         let _next_index = self.next_index;
         let mut current_index = self.next_index;
-        let mut current_level_hash = leaf.clone();
+        let mut current_level_hash = leaf;
         let mut left: u128;
         let mut right: u128;
 
         for i in 0..self.levels {
-            if current_index % 2 == 0 {
-                left = current_level_hash.clone();
-                right = Self::zeros(i);
-                self.filled_subtrees.insert(i, current_level_hash.clone());
+            if current_index.is_multiple_of(2) {
+                left = current_level_hash;
+                right = self.zeros[i as usize];
+                self.filled_subtrees[i as usize] = current_level_hash;
             } else {
-                left = self.filled_subtrees.get(&i).unwrap().clone();
-                right = current_level_hash.clone();
+                left = self.filled_subtrees[i as usize];
+                right = current_level_hash;
             }
             current_level_hash = self.hash_left_right(left, right);
             current_index /= 2;
         }
 
-        let new_root_index: u8 = (self.current_root_index + 1) % ROOT_HISTORY_SIZE;
-        self.current_root_index = new_root_index;
-        self.roots.insert(new_root_index, current_level_hash.clone());
+        self.push_root(current_level_hash);
+        self.root_pending = false;
         self.next_index = _next_index + 1;
+        if !self.frontier_only {
+            self.push_leaf(leaf);
+        }
 
         Ok(_next_index)
     }
 
+    /// `insert` for on-chain use, on a tree built `with_frontier_only`. The
+    /// path is hashed straight out of the preallocated frontier and zero
+    /// tables; nothing is allocated and no map is touched.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(depth = self.levels), ret, err))]
+    pub fn insert_frontier_only(&mut self, leaf: u128) -> Result<u64, SolanaError> {
+        if !self.frontier_only {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree keeps its leaves, use insert"));
+        }
+        self.check_capacity()?;
+        self.check_leaf(leaf)?;
+
+        let index = self.next_index;
+        let mut current_index = index;
+        let mut current_level_hash = leaf;
+        for (filled, zero) in self.filled_subtrees.iter_mut().zip(&self.zeros) {
+            current_level_hash = if current_index.is_multiple_of(2) {
+                *filled = current_level_hash;
+                self.hasher.hash_pair(current_level_hash, *zero)
+            } else {
+                self.hasher.hash_pair(*filled, current_level_hash)
+            };
+            current_index /= 2;
+        }
+
+        self.push_root(current_level_hash);
+        self.root_pending = false;
+        self.next_index = index + 1;
+        Ok(index)
+    }
+
+    /// Inserts every leaf or none: capacity and leaf checks all run before the
+    /// first insert.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(depth = self.levels, from = self.next_index), err))]
+    pub fn insert_many(&mut self, leaves: impl IntoIterator<Item = u128>) -> Result<Vec<u64>, SolanaError> {
+        let leaves: Vec<u128> = leaves.into_iter().collect();
+        if self.next_index as u128 + leaves.len() as u128 > self.capacity() {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Merkle tree has no room for every leaf"));
+        }
+        for leaf in &leaves {
+            self.check_leaf(*leaf)?;
+        }
+        leaves.into_iter().map(|leaf| self.insert(leaf)).collect()
+    }
+
+    /// Inserts any `IntoField` value by its canonical field encoding.
+    pub fn insert_leaf<L: IntoField>(&mut self, leaf: &L) -> Result<u64, SolanaError> {
+        self.insert(leaf.to_field())
+    }
+
+    /// Inserts `leaf_from_bytes(bytes)`.
+    pub fn insert_bytes(&mut self, bytes: &[u8]) -> Result<u64, SolanaError> {
+        self.insert(leaf_from_bytes(bytes))
+    }
+
+    /// Inserts a leaf for a bulk load: only the frontier is updated, hashing
+    /// until the first left child instead of up to the root, and no root is
+    /// added to the history. Call `commit` once the batch is in; until then the
+    /// root accessors and `is_known_root` do not reflect these leaves.
+    pub fn insert_deferred(&mut self, leaf: u128) -> Result<u64, SolanaError> {
+        self.check_capacity()?;
+        self.check_leaf(leaf)?;
+
+        let index = self.next_index;
+        let mut current_index = index;
+        let mut current_level_hash = leaf;
+        let mut stopped = false;
+        for i in 0..self.levels {
+            if current_index.is_multiple_of(2) {
+                self.filled_subtrees[i as usize] = current_level_hash;
+                stopped = true;
+                break;
+            }
+            current_level_hash = self.hash_left_right(self.filled_subtrees[i as usize], current_level_hash);
+            current_index /= 2;
+        }
+
+        if stopped {
+            self.root_pending = true;
+        } else {
+            // The leaf completed the tree, so the walk already reached the root.
+            self.push_root(current_level_hash);
+            self.root_pending = false;
+        }
+        self.next_index = index + 1;
+        if !self.frontier_only {
+            self.push_leaf(leaf);
+        }
+        Ok(index)
+    }
+
+    /// Finishes a run of `insert_deferred`: completes the frontier along the
+    /// last leaf's path and records the resulting root once.
+    pub fn commit(&mut self) -> u128 {
+        if !self.root_pending {
+            return self.get_last_root();
+        }
+
+        let last_index = self.next_index - 1;
+        let start = last_index.trailing_ones() as u8;
+        let mut current_index = last_index >> start;
+        let mut current_level_hash = self.filled_subtrees[start as usize];
+        for i in start..self.levels {
+            if current_index.is_multiple_of(2) {
+                self.filled_subtrees[i as usize] = current_level_hash;
+                current_level_hash = self.hash_left_right(current_level_hash, self.zeros[i as usize]);
+            } else {
+                current_level_hash = self.hash_left_right(self.filled_subtrees[i as usize], current_level_hash);
+            }
+            current_index /= 2;
+        }
+
+        self.push_root(current_level_hash);
+        self.root_pending = false;
+        current_level_hash
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(depth = self.levels), err))]
+    pub fn update(&mut self, index: u64, leaf: u128) -> Result<(), SolanaError> {
+        self.check_leaves_retained()?;
+        if index >= self.next_index {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Leaf index is out of range"));
+        }
+        self.check_leaf(leaf)?;
+        let replaced = std::mem::replace(&mut self.leaves[index as usize], leaf);
+        if self.leaf_indices.get(&replaced) == Some(&index) {
+            self.leaf_indices.remove(&replaced);
+            if let Some(position) = self.leaves.iter().position(|other| *other == replaced) {
+                self.leaf_indices.insert(replaced, position as u64);
+            }
+        }
+        let first = self.leaf_indices.entry(leaf).or_insert(index);
+        *first = (*first).min(index);
+
+        let nodes = self.level_nodes();
+        for i in 0..self.levels {
+            let level = &nodes[i as usize];
+            let last_left = (level.len() - 1) & !1;
+            self.filled_subtrees[i as usize] = level[last_left];
+        }
+
+        self.push_root(nodes[self.levels as usize][0]);
+        self.root_pending = false;
+
+        Ok(())
+    }
+
+    fn push_root(&mut self, root: u128) {
+        let new_root_index: u8 = (self.current_root_index + 1) % self.root_history_size;
+        self.current_root_index = new_root_index;
+        self.set_root(new_root_index, root);
+    }
+
+    // Writes a history slot and keeps `root_counts` in step with the ring. Slots
+    // fill in order, so `roots` grows until the history wraps around.
+    fn set_root(&mut self, index: u8, root: u128) {
+        let index = index as usize;
+        if index == self.roots.len() {
+            self.roots.push(root);
+        } else {
+            let evicted = std::mem::replace(&mut self.roots[index], root);
+            if self.frontier_only {
+                return;
+            }
+            if let Some(count) = self.root_counts.get_mut(&evicted) {
+                *count -= 1;
+                if *count == 0 {
+                    self.root_counts.remove(&evicted);
+                }
+            }
+        }
+        if !self.frontier_only {
+            *self.root_counts.entry(root).or_insert(0) += 1;
+        }
+    }
+
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(depth = self.levels), err))]
+    pub fn proof(&self, index: u64) -> Result<MerkleProof, SolanaError> {
+        self.check_leaves_retained()?;
+        if index >= self.next_index {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Leaf index is out of range"));
+        }
+
+        Ok(self.path(&self.level_nodes(), index))
+    }
+
+    /// Proves that the tree with the first `new_size` leaves is an append-only
+    /// extension of the tree with the first `old_size`.
+    pub fn consistency_proof(&self, old_size: u64, new_size: u64) -> Result<ConsistencyProof, SolanaError> {
+        self.check_leaves_retained()?;
+        if old_size == 0 || old_size > new_size {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Consistency proof needs 0 < old_size <= new_size"));
+        }
+        if new_size > self.next_index {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Leaf index is out of range"));
+        }
+
+        let nodes = self.level_nodes_of(&self.leaves[..new_size as usize]);
+        Ok(ConsistencyProof { old_size, new_size, proof: self.path(&nodes, old_size - 1) })
+    }
+
+    fn path(&self, nodes: &[Vec<u128>], index: u64) -> MerkleProof {
+        let mut path_elements = Vec::with_capacity(self.levels as usize);
+        let mut path_indices = Vec::with_capacity(self.levels as usize);
+        let mut current_index = index as usize;
+
+        for i in 0..self.levels {
+            let sibling_index = current_index ^ 1;
+            let sibling = nodes[i as usize].get(sibling_index).copied().unwrap_or(self.zeros[i as usize]);
+            path_elements.push(sibling);
+            path_indices.push((current_index % 2) as u8);
+            current_index /= 2;
+        }
+
+        MerkleProof {
+            leaf: nodes[0][index as usize],
+            leaf_index: index,
+            path_elements,
+            path_indices
+        }
+    }
+
+    pub fn leaves(&self) -> &[u128] {
+        &self.leaves
+    }
+
+    pub fn get_leaf(&self, index: u64) -> Option<u128> {
+        self.leaves.get(usize::try_from(index).ok()?).copied()
+    }
+
+    /// Every non-empty node as `(level, index, hash)`, leaves first and the
+    /// root last. Empty subtrees are left out; their hashes are `zero_hashes`.
+    pub fn nodes(&self) -> impl Iterator<Item = (u8, u64, u128)> {
+        self.level_nodes().into_iter().enumerate().flat_map(|(level, hashes)| {
+            hashes.into_iter().enumerate().map(move |(index, hash)| (level as u8, index as u64, hash))
+        })
+    }
+
+    /// Every leaf appended since the tree held `other_size` leaves.
+    pub fn diff(&self, other_size: u64) -> Result<TreeDelta, SolanaError> {
+        self.check_leaves_retained()?;
+        if other_size > self.next_index {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Leaf index is out of range"));
+        }
+        Ok(TreeDelta {
+            from_size: other_size,
+            leaves: self.leaves[other_size as usize..].to_vec(),
+            expected_root: self.get_last_root()
+        })
+    }
+
+    /// Appends the delta's leaves if this tree is at `from_size` and they
+    /// lead to `expected_root`; otherwise the tree is left unchanged.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(from_size = delta.from_size, leaves = delta.leaves.len()), err))]
+    pub fn apply_delta(&mut self, delta: &TreeDelta) -> Result<(), SolanaError>
+    where
+        H: Clone
+    {
+        if delta.from_size != self.next_index {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Delta does not start at this tree's size"));
+        }
+
+        let mut updated = self.clone();
+        updated.insert_many(delta.leaves.iter().copied())?;
+        if updated.get_last_root() != delta.expected_root {
+            return Err(utils::err_with_code(ErrorCode::UnknownRoot, "Delta does not produce the expected root"));
+        }
+        *self = updated;
+        Ok(())
+    }
+
+    /// Index of the first occurrence of `leaf`, from a reverse index kept in
+    /// step with inserts and updates. Always `None` in frontier-only mode.
+    pub fn index_of(&self, leaf: u128) -> Option<u64> {
+        self.leaf_indices.get(&leaf).copied()
+    }
+
+    pub fn contains(&self, leaf: u128) -> bool {
+        self.leaf_indices.contains_key(&leaf)
+    }
+
+    fn push_leaf(&mut self, leaf: u128) {
+        self.leaf_indices.entry(leaf).or_insert(self.leaves.len() as u64);
+        self.leaves.push(leaf);
+    }
+
+    // Every non-empty node, level by level from the leaves up to the root.
+    fn level_nodes(&self) -> Vec<Vec<u128>> {
+        self.level_nodes_of(&self.leaves)
+    }
+
+    fn level_nodes_of(&self, leaves: &[u128]) -> Vec<Vec<u128>> {
+        let mut nodes = vec![leaves.to_vec()];
+        for i in 0..self.levels {
+            let level = &nodes[i as usize];
+            let parents = level
+                .chunks(2)
+                .map(|pair| self.hasher.hash_pair(pair[0], pair.get(1).copied().unwrap_or(self.zeros[i as usize])))
+                .collect();
+            nodes.push(parents);
+        }
+        nodes
+    }
+
+    /// O(1): looks the root up in a count of the roots currently in history.
+    /// Frontier-only trees scan the history instead.
     pub fn is_known_root(&self, root: u128) -> bool {
+        if self.frontier_only {
+            return root != 0 && self.roots.contains(&root);
+        }
+        root != 0 && self.root_counts.contains_key(&root)
+    }
+
+    pub fn root_age(&self, root: u128) -> Option<u8> {
         if root == 0 {
-            return false;
+            return None;
         }
-    
+
         let current_root_index = self.current_root_index;
         let mut i = current_root_index;
-        
+        let mut age = 0;
+
         loop {
-            if self.roots.get(&i).is_some() && *self.roots.get(&i).unwrap() == root {
-                return true;
+            if self.roots.get(i as usize) == Some(&root) {
+                return Some(age);
             }
             if i == 0 {
-                i = ROOT_HISTORY_SIZE - 1;
+                i = self.root_history_size - 1;
             } else {
                 i -= 1;
             }
             if i == current_root_index {
                 break;
             }
+            age += 1;
         }
-        false
+        None
     }
 
     pub fn get_last_root(&self) -> u128 {
-        return self.roots.get(&self.current_root_index).unwrap().clone();
-    }
-
-    pub fn zeros(i: u8) -> u128 {
-        let mut result = 0;
-        for _ in 0..i {
-            result = Hasher::mimc_sponge(
-                result, 
-                0,
-                u128::from_str("340282366920938463463374607431768211455").expect("Failed to parse field size")
-            );
-        }
-        result
+        *self.roots.get(self.current_root_index as usize).unwrap()
     }
 }
 
-impl ToString for MerkleTree {
-    fn to_string(&self) -> String {
+impl<H: MerkleHasher> Display for MerkleTree<H> {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
         let mut string_representation = String::new();
         
         string_representation.push_str(&format!("levels: {}\n", self.levels));
         
         string_representation.push_str("filled_subtrees:\n");
-        for (level, value) in &self.filled_subtrees {
-            string_representation.push_str(&format!("  {}: {}\n", level, value.to_string()));
+        for (level, value) in self.filled_subtrees.iter().enumerate() {
+            string_representation.push_str(&format!("  {}: {}\n", level, value));
         }
         
         string_representation.push_str("roots:\n");
-        for (level, value) in &self.roots {
-            string_representation.push_str(&format!("  {}: {}\n", level, value.to_string()));
+        for (level, value) in self.roots.iter().enumerate() {
+            string_representation.push_str(&format!("  {}: {}\n", level, value));
         }
         
         string_representation.push_str(&format!("current_root_index: {}\n", self.current_root_index));
         string_representation.push_str(&format!("next_index: {}\n", self.next_index));
         
-        string_representation
+        write!(f, "{}", string_representation)
     }
 }
 
 impl FromStr for MerkleTree {
     type Err = SolanaError;
 
+    /// Parses the `Display` form. List entries can follow a `filled_subtrees:`
+    /// or `roots:` header on indented `index: value` lines, or sit inline as
+    /// `roots: index: value`.
     fn from_str(s: &str) -> std::result::Result<Self, SolanaError> {
+        let invalid = |message: &'static str| utils::err_with_code(ErrorCode::InvalidEncoding, message);
         let mut levels: Option<u8> = None;
-        let mut filled_subtrees: HashMap<u8, u128> = HashMap::new();
-        let mut roots: HashMap<u8, u128> = HashMap::new();
+        let mut filled_subtrees: Map<u8, u128> = Map::new();
+        let mut roots: Map<u8, u128> = Map::new();
         let mut current_root_index: Option<u8> = None;
-        let mut next_index: Option<u8> = None;
+        let mut next_index: Option<u64> = None;
+        let mut section: Option<&str> = None;
 
-        for line in s.lines() {
-            let parts: Vec<&str> = line.trim().splitn(2, ":").collect();
-            if parts.len() != 2 {
-                return Err(utils::err("Error").into()
-                );
-            }
-            let key = parts[0].trim();
-            let value = parts[1].trim();
-
-            match key {
-                "levels" => {
-                    levels = Some(value.parse().map_err(|e| format!("Parsing levels failed: {}", e)).unwrap());
-                }
-                "filled_subtrees" => {
-                    let level_value: Vec<&str> = value.splitn(2, ":").collect();
-                    if level_value.len() != 2 {
-                        return Err(utils::err("Error occured in filled subtrees").into());
+        for line in s.lines().filter(|line| !line.trim().is_empty()) {
+            let (key, value) = line.trim().split_once(':').ok_or_else(|| invalid("Expected a `key: value` line"))?;
+            let (key, value) = (key.trim(), value.trim());
+            let (list, entry) = if line.starts_with(char::is_whitespace) {
+                (section.ok_or_else(|| invalid("Indented entry outside filled_subtrees or roots"))?, Some((key, value)))
+            } else {
+                section = None;
+                match key {
+                    "filled_subtrees" | "roots" if value.is_empty() => {
+                        section = Some(key);
+                        continue;
+                    }
+                    "filled_subtrees" | "roots" => {
+                        let entry = value.split_once(':').ok_or_else(|| invalid("Expected an `index: value` entry"))?;
+                        (key, Some((entry.0.trim(), entry.1.trim())))
                     }
-                    let level: u8 = level_value[0].trim().parse().map_err(|e| format!("Parsing filled_subtrees level failed: {}", e)).unwrap();
-                    let value: u128 = level_value[1].trim().parse().map_err(|e| format!("Parsing filled_subtrees value failed: {}", e)).unwrap();
+                    _ => (key, None)
+                }
+            };
+
+            match (list, entry) {
+                ("filled_subtrees", Some((level, value))) => {
+                    let level: u8 = level.parse().context("Parsing filled_subtrees level")?;
+                    let value: u128 = value.parse().context("Parsing filled_subtrees value")?;
                     filled_subtrees.insert(level, value);
                 }
-                "roots" => {
-                    let level_value: Vec<&str> = value.splitn(2, ":").collect();
-                    if level_value.len() != 2 {
-                        return Err(utils::err("Error in roots").into());
-                    }
-                    let level: u8 = level_value[0].trim().parse().map_err(|e| format!("Parsing roots level failed: {}", e)).unwrap();
-                    let value: u128 = level_value[1].trim().parse().map_err(|e| format!("Parsing roots value failed: {}", e)).unwrap();
-                    roots.insert(level, value);
+                ("roots", Some((index, value))) => {
+                    let index: u8 = index.parse().context("Parsing roots index")?;
+                    let value: u128 = value.parse().context("Parsing roots value")?;
+                    roots.insert(index, value);
+                }
+                ("levels", None) => {
+                    levels = Some(value.parse().context("Parsing levels")?);
                 }
-                "current_root_index" => {
-                    current_root_index = Some(value.parse().map_err(|e| format!("Parsing current_root_index failed: {}", e)).unwrap());
+                ("current_root_index", None) => {
+                    current_root_index = Some(value.parse().context("Parsing current_root_index")?);
                 }
-                "next_index" => {
-                    next_index = Some(value.parse().map_err(|e| format!("Parsing next_index failed: {}", e)).unwrap());
+                ("next_index", None) => {
+                    next_index = Some(value.parse().context("Parsing next_index")?);
                 }
                 _ => {
-                    return Err(utils::err("Unexpected error").into());
+                    return Err(invalid("Unexpected key").with_compared_values("levels, filled_subtrees, roots, current_root_index or next_index", key));
                 }
             }
         }
 
-        let levels = levels.ok_or("Missing levels").unwrap();
-        let current_root_index = current_root_index.ok_or("Missing current_root_index").unwrap();
-        let next_index = next_index.ok_or("Missing next_index").unwrap();
+        let levels = levels.ok_or_else(|| invalid("Missing levels"))?;
+        let current_root_index = current_root_index.ok_or_else(|| invalid("Missing current_root_index"))?;
+        let next_index = next_index.ok_or_else(|| invalid("Missing next_index"))?;
+        if levels == 0 {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree must have at least one level"));
+        }
+        if filled_subtrees.keys().any(|level| *level >= levels) {
+            return Err(invalid("filled_subtrees entry above the tree height"));
+        }
+
+        let filled_subtrees = (0..levels)
+            .map(|level| filled_subtrees.get(&level).copied().unwrap_or_else(|| MerkleTree::zeros(level)))
+            .collect();
+        let history_len = roots.keys().max().map_or(0, |max| *max as usize + 1);
+        let roots: Vec<u128> = (0..history_len).map(|i| roots.get(&(i as u8)).copied().unwrap_or(0)).collect();
+        if roots.len() > ROOT_HISTORY_SIZE as usize {
+            return Err(invalid("More roots than the root history holds").with_compared_values(ROOT_HISTORY_SIZE, roots.len()));
+        }
+        if current_root_index as usize >= roots.len() {
+            return Err(invalid("current_root_index points past the stored roots").with_compared_values(roots.len(), current_root_index));
+        }
+        if next_index as u128 > 1u128.checked_shl(levels as u32).unwrap_or(u128::MAX) {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "next_index exceeds the tree capacity"));
+        }
+        let mut root_counts = Map::new();
+        for root in &roots {
+            *root_counts.entry(*root).or_insert(0) += 1;
+        }
 
         Ok(MerkleTree {
+            hasher: Hasher::default(),
             levels,
             filled_subtrees,
             roots,
+            root_counts,
             current_root_index,
-            next_index
+            root_history_size: ROOT_HISTORY_SIZE,
+            next_index,
+            leaves: Vec::new(),
+            leaf_indices: Map::new(),
+            strict_leaves: false,
+            // The text form carries no leaves, so a non-empty tree only has
+            // its frontier.
+            frontier_only: next_index != 0,
+            root_pending: false,
+            zeros: (0..levels).map(MerkleTree::zeros).collect()
         })
     }
 }
@@ -215,6 +889,7 @@ impl FromStr for MerkleTree {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::constants::FIELD_SIZE;
     const MERKLE_TREE_HEIGHT: u8 = 20;
 
     #[test]
@@ -226,10 +901,467 @@ mod tests {
     }
 
     #[test]
+    fn test_proof() {
+        let mut merkle_tree = MerkleTree::new(4);
+        for leaf in [11, 22, 33] {
+            merkle_tree.insert(leaf).unwrap();
+        }
+        let root = merkle_tree.get_last_root();
+
+        for index in 0..3 {
+            let proof = merkle_tree.proof(index).unwrap();
+            assert_eq!(proof.path_elements.len(), 4);
+            assert!(proof.verify(root));
+        }
+        assert!(merkle_tree.proof(3).is_err());
+    }
+
+    #[test]
+    fn test_update() {
+        let mut merkle_tree = MerkleTree::new(4);
+        let mut expected = MerkleTree::new(4);
+        for leaf in [11, 22, 33] {
+            merkle_tree.insert(leaf).unwrap();
+        }
+        for leaf in [11, 0, 33] {
+            expected.insert(leaf).unwrap();
+        }
+
+        merkle_tree.update(1, 0).unwrap();
+        assert_eq!(merkle_tree.get_last_root(), expected.get_last_root());
+
+        merkle_tree.insert(44).unwrap();
+        expected.insert(44).unwrap();
+        assert_eq!(merkle_tree.get_last_root(), expected.get_last_root());
+        assert!(merkle_tree.proof(1).unwrap().verify(expected.get_last_root()));
+    }
+
+    #[test]
+    fn test_strict_leaves() {
+        let mut lenient = MerkleTree::new(4);
+        assert!(lenient.insert(FIELD_SIZE).is_ok());
+
+        let mut strict = MerkleTree::new(4).with_strict_leaves();
+        let err = strict.insert(FIELD_SIZE).err().unwrap();
+        assert_eq!(err.code(), ErrorCode::FieldOverflow);
+        strict.insert(11).unwrap();
+        assert_eq!(strict.update(0, FIELD_SIZE).err().unwrap().code(), ErrorCode::FieldOverflow);
+
+        let root = strict.get_last_root();
+        let mut proof = strict.proof(0).unwrap();
+        assert!(proof.verify_strict(root).unwrap());
+        proof.path_elements[0] = FIELD_SIZE;
+        assert_eq!(proof.verify_strict(root).err().unwrap().code(), ErrorCode::FieldOverflow);
+    }
+
+    #[test]
+    fn test_zero_hashes() {
+        let merkle_tree = MerkleTree::new(4);
+        let expected: Vec<u128> = (0..4).map(MerkleTree::zeros).collect();
+        assert_eq!(merkle_tree.zero_hashes(), expected);
+        assert_eq!(merkle_tree.get_last_root(), MerkleTree::zeros(3));
+    }
+
+    #[test]
+    fn test_frontier_only() {
+        let mut full = MerkleTree::new(4);
+        let mut frontier = MerkleTree::new(4).with_frontier_only();
+        for leaf in [11, 22, 33] {
+            full.insert(leaf).unwrap();
+            frontier.insert(leaf).unwrap();
+        }
+        assert_eq!(frontier.get_last_root(), full.get_last_root());
+        assert!(frontier.is_known_root(full.get_last_root()));
+        assert!(frontier.leaves().is_empty());
+        assert_eq!(frontier.proof(0).err().unwrap().code(), ErrorCode::LeavesNotRetained);
+        assert_eq!(frontier.update(0, 1).err().unwrap().code(), ErrorCode::LeavesNotRetained);
+    }
+
+    #[cfg(feature = "scale")]
+    #[test]
+    fn test_scale() {
+        use parity_scale_codec::{Decode, Encode};
+
+        let mut tree = MerkleTree::new(4);
+        tree.insert(11).unwrap();
+        let proof = tree.proof(0).unwrap();
+        let encoded = proof.encode();
+        assert_eq!(encoded.len(), 16 + 8 + 1 + 4 * 16 + 1 + 4);
+        assert_eq!(MerkleProof::decode(&mut &encoded[..]).unwrap(), proof);
+
+        let delta = tree.diff(0).unwrap();
+        assert_eq!(TreeDelta::decode(&mut &delta.encode()[..]).unwrap(), delta);
+    }
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_bincode() {
+        let proof = MerkleProof { leaf: 1, leaf_index: 2, path_elements: vec![3], path_indices: vec![1] };
+        let bytes = bincode::serialize(&proof).unwrap();
+        assert_eq!(
+            hex::encode(&bytes),
+            "01000000000000000000000000000000\
+             0200000000000000\
+             0100000000000000\
+             03000000000000000000000000000000\
+             0100000000000000\
+             01"
+        );
+        assert_eq!(bincode::deserialize::<MerkleProof>(&bytes).unwrap(), proof);
+
+        let delta = TreeDelta { from_size: 1, leaves: vec![5], expected_root: 6 };
+        assert_eq!(
+            hex::encode(bincode::serialize(&delta).unwrap()),
+            "0100000000000000\
+             0100000000000000\
+             05000000000000000000000000000000\
+             06000000000000000000000000000000"
+        );
+
+        let consistency = ConsistencyProof { old_size: 1, new_size: 2, proof };
+        assert_eq!(bincode::serialize(&consistency).unwrap()[16..], bytes);
+    }
+
+    #[test]
+    fn test_verify_inclusion() {
+        let mut tree = MerkleTree::new(4);
+        for leaf in [11, 22, 33] {
+            tree.insert(leaf).unwrap();
+        }
+        let root = tree.get_last_root();
+        let proof = tree.proof(2).unwrap();
+        assert!(verify_inclusion(33, 2, &proof.path_elements, root, tree.hasher()));
+        assert!(!verify_inclusion(33, 3, &proof.path_elements, root, tree.hasher()));
+        assert!(!verify_inclusion(33, 18, &proof.path_elements, root, tree.hasher()));
+    }
+
+    #[test]
+    fn test_verify_slices() {
+        let mut tree = MerkleTree::new(4);
+        for leaf in [11, 22, 33] {
+            tree.insert(leaf).unwrap();
+        }
+        let root = tree.get_last_root();
+        let proof = tree.proof(1).unwrap();
+        assert!(MerkleProof::verify_slices(22, &proof.path_elements, 0b0001, root, tree.hasher()));
+        assert!(!MerkleProof::verify_slices(22, &proof.path_elements, 0b0000, root, tree.hasher()));
+        assert!(!MerkleProof::verify_slices(22, &proof.path_elements, 0b10001, root, tree.hasher()));
+    }
+
+    #[test]
+    fn test_insert_frontier_only() {
+        let mut full = MerkleTree::new(4);
+        let mut frontier = MerkleTree::new(4).with_frontier_only();
+        let first_root = frontier.get_last_root();
+        for leaf in 1..=16 {
+            assert_eq!(frontier.insert_frontier_only(leaf).unwrap(), full.insert(leaf).unwrap());
+            assert_eq!(frontier.get_last_root(), full.get_last_root());
+        }
+        assert!(frontier.is_known_root(full.get_last_root()));
+        assert!(frontier.is_known_root(first_root));
+        assert!(!frontier.is_known_root(12345));
+        assert_eq!(frontier.insert_frontier_only(17).unwrap_err().code(), ErrorCode::LeafIndexOutOfRange);
+        assert_eq!(full.insert_frontier_only(17).unwrap_err().code(), ErrorCode::InvalidParameters);
+    }
+
+    #[test]
+    fn test_display() {
+        let mut merkle_tree = MerkleTree::new(2);
+        merkle_tree.insert(11).unwrap();
+        let expected = format!(
+            "levels: 2\nfilled_subtrees:\n  0: 11\n  1: {}\nroots:\n  0: {}\n  1: {}\ncurrent_root_index: 1\nnext_index: 1\n",
+            merkle_tree.hash_left_right(11, MerkleTree::zeros(0)),
+            MerkleTree::zeros(1),
+            merkle_tree.get_last_root()
+        );
+        assert_eq!(merkle_tree.to_string(), expected);
+        let err = "levels: x".parse::<MerkleTree>().err().unwrap();
+        assert_eq!(err.code(), ErrorCode::InvalidEncoding);
+        assert!(err.to_string().contains("Parsing levels: Invalid integer"));
+
+        let mut parsed: MerkleTree = "levels: 2\nfilled_subtrees: 0: 11\nroots: 0: 5\ncurrent_root_index: 0\nnext_index: 1".parse().unwrap();
+        assert!(parsed.frontier_only());
+        assert_eq!(parsed.proof(0).unwrap_err().code(), ErrorCode::LeavesNotRetained);
+        assert_eq!(parsed.update(0, 12).unwrap_err().code(), ErrorCode::LeavesNotRetained);
+
+        for input in [
+            "levels: 3",
+            "levels: 3\nnext_index: 0",
+            "levels: 3\ncurrent_root_index: 5\nnext_index: 0",
+            "levels: 3\nroots: 0: 5\ncurrent_root_index: 5\nnext_index: 0",
+            "levels: 3\nroots: 30: 5\ncurrent_root_index: 0\nnext_index: 0",
+            "levels: 3\n  0: 5\nroots: 0: 5\ncurrent_root_index: 0\nnext_index: 0",
+            "levels: 3\nfilled_subtrees: 3: 5\nroots: 0: 5\ncurrent_root_index: 0\nnext_index: 0"
+        ] {
+            assert_eq!(input.parse::<MerkleTree>().unwrap_err().code(), ErrorCode::InvalidEncoding, "{}", input);
+        }
+        assert_eq!("levels: 0\nroots: 0: 5\ncurrent_root_index: 0\nnext_index: 0".parse::<MerkleTree>().unwrap_err().code(), ErrorCode::InvalidParameters);
+        assert_eq!("levels: 2\nroots: 0: 5\ncurrent_root_index: 0\nnext_index: 5".parse::<MerkleTree>().unwrap_err().code(), ErrorCode::LeafIndexOutOfRange);
+    }
+
+    #[test]
+    fn test_display_round_trip() {
+        let mut merkle_tree = MerkleTree::new(4);
+        for leaf in [11, 22, 33] {
+            merkle_tree.insert(leaf).unwrap();
+        }
+        let mut parsed: MerkleTree = merkle_tree.to_string().parse().unwrap();
+        assert_eq!(parsed.to_string(), merkle_tree.to_string());
+        assert_eq!(parsed.get_last_root(), merkle_tree.get_last_root());
+        assert!(parsed.is_known_root(merkle_tree.get_last_root()));
+
+        parsed.insert(44).unwrap();
+        merkle_tree.insert(44).unwrap();
+        assert_eq!(parsed.get_last_root(), merkle_tree.get_last_root());
+    }
+
+    #[test]
+    fn test_insert_deferred() {
+        let mut expected = MerkleTree::new(4);
+        let mut deferred = MerkleTree::new(4);
+        for leaf in 1..=6 {
+            expected.insert(leaf).unwrap();
+            assert_eq!(deferred.insert_deferred(leaf).unwrap(), leaf as u64 - 1);
+        }
+        let initial_root = deferred.get_last_root();
+        assert_eq!(deferred.commit(), expected.get_last_root());
+        assert_eq!(deferred.filled_subtrees, expected.filled_subtrees);
+        assert!(deferred.is_known_root(expected.get_last_root()));
+        assert_eq!(deferred.root_age(initial_root), Some(1));
+        assert_eq!(deferred.commit(), expected.get_last_root());
+
+        for leaf in 7..=9 {
+            expected.insert(leaf).unwrap();
+            deferred.insert_deferred(leaf).unwrap();
+        }
+        deferred.insert(10).unwrap();
+        expected.insert(10).unwrap();
+        assert_eq!(deferred.get_last_root(), expected.get_last_root());
+        assert!(deferred.proof(9).unwrap().verify(expected.get_last_root()));
+
+        let mut full = MerkleTree::new(2);
+        for leaf in 1..=4 {
+            full.insert_deferred(leaf).unwrap();
+        }
+        let mut reference = MerkleTree::new(2);
+        for leaf in 1..=4 {
+            reference.insert(leaf).unwrap();
+        }
+        assert_eq!(full.get_last_root(), reference.get_last_root());
+        assert_eq!(full.commit(), reference.get_last_root());
+    }
+
+    #[test]
+    fn test_consistency_proof() {
+        let mut merkle_tree = MerkleTree::new(4);
+        let mut roots = vec![];
+        for leaf in 1..=11 {
+            merkle_tree.insert(leaf).unwrap();
+            roots.push(merkle_tree.get_last_root());
+        }
+
+        for old_size in 1..=11u64 {
+            for new_size in old_size..=11 {
+                let proof = merkle_tree.consistency_proof(old_size, new_size).unwrap();
+                let (old_root, new_root) = (roots[old_size as usize - 1], roots[new_size as usize - 1]);
+                assert!(proof.verify(old_root, new_root), "{} -> {}", old_size, new_size);
+                if old_size > 1 {
+                    assert!(!proof.verify(roots[old_size as usize - 2], new_root));
+                }
+            }
+        }
+
+        let mut rewritten = merkle_tree.clone();
+        rewritten.update(2, 99).unwrap();
+        let proof = rewritten.consistency_proof(5, 11).unwrap();
+        assert!(!proof.verify(roots[4], rewritten.get_last_root()));
+
+        assert_eq!(merkle_tree.consistency_proof(0, 3).err().unwrap().code(), ErrorCode::InvalidParameters);
+        assert_eq!(merkle_tree.consistency_proof(3, 12).err().unwrap().code(), ErrorCode::LeafIndexOutOfRange);
+    }
+
+    #[test]
+    fn test_insert_leaf() {
+        struct Deposit {
+            amount: u128,
+            owner: u128,
+            blinding: u128
+        }
+
+        impl IntoField for Deposit {
+            fn to_field(&self) -> u128 {
+                [self.amount, self.owner, self.blinding].to_field()
+            }
+        }
+
+        let mut merkle_tree = MerkleTree::new(4);
+        let deposit = Deposit { amount: 100, owner: 7, blinding: 42 };
+        let note = crate::note::Note::new(1, 2).unwrap();
+        merkle_tree.insert_leaf(&deposit).unwrap();
+        merkle_tree.insert_leaf(&note).unwrap();
+        assert_eq!(merkle_tree.leaves(), [Hasher::default().hash_many(&[100, 7, 42]), note.commitment()]);
+    }
+
+    #[test]
+    fn test_insert_bytes() {
+        let mut merkle_tree = MerkleTree::new(4);
+        assert_eq!(merkle_tree.insert_bytes(b"deposit event").unwrap(), 0);
+        merkle_tree.insert_bytes(b"").unwrap();
+        assert_ne!(merkle_tree.leaves()[0], crate::hasher::hash_to_field(b"deposit event"));
+
+        let root = merkle_tree.get_last_root();
+        let proof = merkle_tree.proof(0).unwrap();
+        assert!(proof.verify_bytes(b"deposit event", root));
+        assert!(!proof.verify_bytes(b"deposit event!", root));
+        assert!(merkle_tree.proof(1).unwrap().verify_bytes(b"", root));
+    }
+
+    #[test]
+    fn test_insert_many() {
+        let mut merkle_tree = MerkleTree::new(2);
+        let mut expected = MerkleTree::new(2);
+        for leaf in [1, 2] {
+            expected.insert(leaf).unwrap();
+        }
+        assert_eq!(merkle_tree.insert_many([1, 2]).unwrap(), vec![0, 1]);
+        assert_eq!(merkle_tree.get_last_root(), expected.get_last_root());
+
+        assert_eq!(merkle_tree.insert_many(3..=5).err().unwrap().code(), ErrorCode::LeafIndexOutOfRange);
+        assert_eq!(merkle_tree.leaves(), [1, 2]);
+
+        let mut strict = MerkleTree::new(2).with_strict_leaves();
+        assert_eq!(strict.insert_many([1, FIELD_SIZE]).err().unwrap().code(), ErrorCode::FieldOverflow);
+        assert!(strict.leaves().is_empty());
+    }
+
+    #[test]
+    fn test_index_of() {
+        let mut merkle_tree = MerkleTree::new(4);
+        merkle_tree.insert_many([11, 22, 11, 33]).unwrap();
+        assert_eq!(merkle_tree.index_of(11), Some(0));
+        assert_eq!(merkle_tree.index_of(33), Some(3));
+        assert!(merkle_tree.contains(22));
+        assert!(!merkle_tree.contains(44));
+
+        merkle_tree.update(0, 44).unwrap();
+        assert_eq!(merkle_tree.index_of(11), Some(2));
+        assert_eq!(merkle_tree.index_of(44), Some(0));
+        merkle_tree.update(1, 0).unwrap();
+        assert!(!merkle_tree.contains(22));
+        merkle_tree.update(3, 44).unwrap();
+        assert_eq!(merkle_tree.index_of(44), Some(0));
+        assert!(!merkle_tree.contains(33));
+    }
+
+    #[test]
+    fn test_accessors() {
+        let mut merkle_tree = MerkleTree::new(2);
+        merkle_tree.insert_many([11, 22, 33]).unwrap();
+        assert_eq!(merkle_tree.levels(), 2);
+        assert_eq!(merkle_tree.next_index(), 3);
+        assert_eq!(merkle_tree.get_leaf(2), Some(33));
+        assert_eq!(merkle_tree.get_leaf(3), None);
+
+        let nodes: Vec<(u8, u64, u128)> = merkle_tree.nodes().collect();
+        assert_eq!(nodes.len(), 3 + 2 + 1);
+        assert_eq!(nodes[0], (0, 0, 11));
+        assert_eq!(nodes[4], (1, 1, merkle_tree.hash_left_right(33, MerkleTree::zeros(0))));
+        assert_eq!(nodes[5], (2, 0, merkle_tree.get_last_root()));
+    }
+
+    #[test]
+    fn test_delta() {
+        let mut source = MerkleTree::new(4);
+        source.insert_many([1, 2, 3]).unwrap();
+        let mut mirror = source.clone();
+        source.insert_many([4, 5]).unwrap();
+
+        let delta = source.diff(3).unwrap();
+        assert_eq!(delta.leaves, vec![4, 5]);
+        let bytes = borsh::to_vec(&delta).unwrap();
+        assert_eq!(TreeDelta::try_from_slice(&bytes).unwrap(), delta);
+
+        let mut tampered = delta.clone();
+        tampered.leaves[1] = 6;
+        assert_eq!(mirror.apply_delta(&tampered).err().unwrap().code(), ErrorCode::UnknownRoot);
+        assert_eq!(mirror.next_index(), 3);
+
+        mirror.apply_delta(&delta).unwrap();
+        assert_eq!(mirror.get_last_root(), source.get_last_root());
+        assert_eq!(mirror.apply_delta(&delta).err().unwrap().code(), ErrorCode::InvalidParameters);
+        assert!(source.diff(6).is_err());
+    }
+
+    #[test]
+    fn test_verify_detailed() {
+        let mut merkle_tree = MerkleTree::new(3);
+        merkle_tree.insert_many([11, 22, 33]).unwrap();
+        let root = merkle_tree.get_last_root();
+        let hasher = Hasher::default();
+        let proof = merkle_tree.proof(2).unwrap();
+        assert!(proof.verify_detailed(root, &hasher).is_ok());
+
+        let err = proof.verify_detailed(root + 1, &hasher).err().unwrap();
+        assert_eq!(err.code(), ErrorCode::InvalidProof);
+        assert_eq!(err.compared_values().unwrap(), format!("expected {}, got {}", root + 1, root));
+
+        let mut short = proof.clone();
+        short.path_indices.pop();
+        assert_eq!(short.verify_detailed(root, &hasher).err().unwrap().compared_values(), Some("expected 3, got 2"));
+
+        let mut wrong_side = proof.clone();
+        wrong_side.path_indices[1] = 0;
+        assert!(wrong_side.verify_detailed(root, &hasher).err().unwrap().message().contains("level 1"));
+
+        let mut overflow = proof.clone();
+        overflow.path_elements[0] = FIELD_SIZE;
+        assert_eq!(overflow.verify_detailed(root, &hasher).err().unwrap().code(), ErrorCode::FieldOverflow);
+
+        let mut out_of_range = proof;
+        out_of_range.leaf_index += 8;
+        assert!(out_of_range.verify_detailed(root, &hasher).is_err());
+    }
+
+    #[test]
+    fn test_capacity() {
+        let mut small = MerkleTree::new(2);
+        assert_eq!(small.capacity(), 4);
+        for leaf in 1..=4 {
+            small.insert(leaf).unwrap();
+        }
+        assert_eq!(small.insert(5).err().unwrap().code(), ErrorCode::LeafIndexOutOfRange);
+        assert_eq!(small.insert_deferred(5).err().unwrap().code(), ErrorCode::LeafIndexOutOfRange);
+
+        let mut deep = MerkleTree::new(64);
+        assert_eq!(deep.capacity(), 1 << 64);
+        for leaf in 1..=3 {
+            deep.insert(leaf).unwrap();
+        }
+        let proof = deep.proof(2).unwrap();
+        assert_eq!(proof.path_elements.len(), 64);
+        assert!(proof.verify(deep.get_last_root()));
+    }
+
+    #[test]
+    #[allow(clippy::bool_assert_comparison)]
     fn test_is_known_root() {
         let merkle_tree = MerkleTree::new(MERKLE_TREE_HEIGHT);
         let root = 123;
         let result = merkle_tree.is_known_root(root);
         assert_eq!(result, false);
     }
+
+    #[test]
+    fn test_root_history_eviction() {
+        let mut merkle_tree = MerkleTree::new(6);
+        merkle_tree.insert(1).unwrap();
+        let first = merkle_tree.get_last_root();
+        for leaf in 2..=ROOT_HISTORY_SIZE as u128 {
+            merkle_tree.insert(leaf).unwrap();
+            assert!(merkle_tree.is_known_root(first));
+        }
+        merkle_tree.insert(100).unwrap();
+        assert!(!merkle_tree.is_known_root(first));
+        assert!(merkle_tree.is_known_root(merkle_tree.get_last_root()));
+    }
 }
\ No newline at end of file
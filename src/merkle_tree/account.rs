@@ -0,0 +1,231 @@
+use bytemuck::{Pod, Zeroable};
+
+use crate::hasher::{Hasher, MerkleHasher};
+use crate::merkle_tree::{MerkleTree, ROOT_HISTORY_SIZE};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// Deepest tree a `TreeAccount` can hold.
+pub const ACCOUNT_MAX_LEVELS: usize = 32;
+
+/// Fixed-size account image of a frontier-only `MerkleTree` with the default
+/// `Hasher`. Values are little-endian `[u8; 16]` so the layout has 8-byte
+/// alignment and no padding on every target, and a program can insert
+/// straight into its account data through `load_mut`.
+#[repr(C)]
+#[derive(Debug, Clone, Copy, Pod, Zeroable)]
+pub struct TreeAccount {
+    next_index: u64,
+    levels: u8,
+    current_root_index: u8,
+    root_history_size: u8,
+    _padding: [u8; 5],
+    filled_subtrees: [[u8; 16]; ACCOUNT_MAX_LEVELS],
+    zeros: [[u8; 16]; ACCOUNT_MAX_LEVELS],
+    roots: [[u8; 16]; ROOT_HISTORY_SIZE as usize]
+}
+
+fn layout_error() -> SolanaError {
+    utils::err_with_code(ErrorCode::InvalidParameters, "Account data is too small or misaligned for a tree")
+}
+
+fn header_error() -> SolanaError {
+    utils::err_with_code(ErrorCode::InvalidParameters, "Tree account header is corrupt")
+}
+
+impl TreeAccount {
+    pub const LEN: usize = std::mem::size_of::<TreeAccount>();
+
+    /// Fails on short or misaligned data and on a corrupt header. An all-zero
+    /// account loads as uninitialized, ready for `init`.
+    pub fn load(data: &[u8]) -> Result<&TreeAccount, SolanaError> {
+        let data = data.get(..Self::LEN).ok_or_else(layout_error)?;
+        let account: &TreeAccount = bytemuck::try_from_bytes(data).map_err(|_| layout_error())?;
+        account.check_header()?;
+        Ok(account)
+    }
+
+    pub fn load_mut(data: &mut [u8]) -> Result<&mut TreeAccount, SolanaError> {
+        let data = data.get_mut(..Self::LEN).ok_or_else(layout_error)?;
+        let account: &mut TreeAccount = bytemuck::try_from_bytes_mut(data).map_err(|_| layout_error())?;
+        account.check_header()?;
+        Ok(account)
+    }
+
+    fn is_initialized(&self) -> bool {
+        self.levels != 0
+    }
+
+    fn check_header(&self) -> Result<(), SolanaError> {
+        if !self.is_initialized() {
+            return if self.root_history_size == 0 && self.current_root_index == 0 && self.next_index == 0 {
+                Ok(())
+            } else {
+                Err(header_error())
+            };
+        }
+        if self.levels as usize > ACCOUNT_MAX_LEVELS
+            || self.root_history_size == 0
+            || self.root_history_size > ROOT_HISTORY_SIZE
+            || self.current_root_index >= self.root_history_size
+            || self.next_index > 1u64 << self.levels
+        {
+            return Err(header_error());
+        }
+        Ok(())
+    }
+
+    /// Copies the account into the start of `data`, which may be unaligned.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip_all, fields(next_index = self.next_index), err))]
+    pub fn store(&self, data: &mut [u8]) -> Result<(), SolanaError> {
+        data.get_mut(..Self::LEN).ok_or_else(layout_error)?.copy_from_slice(bytemuck::bytes_of(self));
+        Ok(())
+    }
+
+    /// Resets the account to an empty tree of `levels` levels.
+    pub fn init(&mut self, levels: u8) -> Result<(), SolanaError> {
+        if levels == 0 || levels as usize > ACCOUNT_MAX_LEVELS {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree depth does not fit the account"));
+        }
+        *self = TreeAccount::zeroed();
+        self.levels = levels;
+        self.root_history_size = ROOT_HISTORY_SIZE;
+        let hasher = Hasher::default();
+        for i in 0..levels as usize {
+            self.zeros[i] = hasher.zero(i as u8).to_le_bytes();
+            self.filled_subtrees[i] = self.zeros[i];
+        }
+        self.roots[0] = self.zeros[levels as usize - 1];
+        Ok(())
+    }
+
+    pub fn levels(&self) -> u8 {
+        self.levels
+    }
+
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Current root, or 0 when the header is corrupt.
+    pub fn root(&self) -> u128 {
+        self.roots.get(self.current_root_index as usize).map_or(0, |root| u128::from_le_bytes(*root))
+    }
+
+    pub fn is_known_root(&self, root: u128) -> bool {
+        root != 0
+            && self.roots.get(..self.root_history_size as usize).is_some_and(|roots| roots.contains(&root.to_le_bytes()))
+    }
+
+    /// Same hashing as `MerkleTree::insert`, without keeping the leaf.
+    #[cfg_attr(feature = "tracing", tracing::instrument(level = "debug", skip(self), fields(depth = self.levels), ret, err))]
+    pub fn insert(&mut self, leaf: u128) -> Result<u64, SolanaError> {
+        if !self.is_initialized() {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree account is not initialized"));
+        }
+        self.check_header()?;
+        if self.next_index >= 1u64 << self.levels {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Merkle tree is full, no more leaves can be added"));
+        }
+
+        let hasher = Hasher::default();
+        let mut current_index = self.next_index;
+        let mut current_level_hash = leaf;
+        for i in 0..self.levels as usize {
+            let (left, right) = if current_index.is_multiple_of(2) {
+                self.filled_subtrees[i] = current_level_hash.to_le_bytes();
+                (current_level_hash, u128::from_le_bytes(self.zeros[i]))
+            } else {
+                (u128::from_le_bytes(self.filled_subtrees[i]), current_level_hash)
+            };
+            current_level_hash = hasher.hash_pair(left, right);
+            current_index /= 2;
+        }
+
+        self.current_root_index = (self.current_root_index + 1) % self.root_history_size;
+        self.roots[self.current_root_index as usize] = current_level_hash.to_le_bytes();
+        self.next_index += 1;
+        Ok(self.next_index - 1)
+    }
+
+    /// Fails for trees built with anything but the default `Hasher`, which is
+    /// the only one `insert` can continue.
+    pub fn from_tree(tree: &MerkleTree) -> Result<TreeAccount, SolanaError> {
+        if tree.hasher != Hasher::default() {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree account only holds default-hasher trees"));
+        }
+        let mut account = TreeAccount::zeroed();
+        account.init(tree.levels)?;
+        if tree.root_history_size > ROOT_HISTORY_SIZE {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Root history does not fit the account"));
+        }
+        account.next_index = tree.next_index;
+        account.current_root_index = tree.current_root_index;
+        account.root_history_size = tree.root_history_size;
+        for (slot, value) in account.filled_subtrees.iter_mut().zip(&tree.filled_subtrees) {
+            *slot = value.to_le_bytes();
+        }
+        for (slot, root) in account.roots.iter_mut().zip(&tree.roots) {
+            *slot = root.to_le_bytes();
+        }
+        Ok(account)
+    }
+
+    /// Frontier-only tree with the account's state.
+    pub fn to_tree(&self) -> Result<MerkleTree, SolanaError> {
+        if !self.is_initialized() {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree account is not initialized"));
+        }
+        self.check_header()?;
+        let mut tree = MerkleTree::new(self.levels).with_frontier_only();
+        let history_len = (self.next_index + 1).min(self.root_history_size as u64) as usize;
+        tree.filled_subtrees = self.filled_subtrees[..self.levels as usize].iter().map(|value| u128::from_le_bytes(*value)).collect();
+        tree.roots = self.roots[..history_len].iter().map(|root| u128::from_le_bytes(*root)).collect();
+        tree.current_root_index = self.current_root_index;
+        tree.root_history_size = self.root_history_size;
+        tree.next_index = self.next_index;
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_account() {
+        let mut buffer = vec![0u64; TreeAccount::LEN / 8];
+        let data: &mut [u8] = bytemuck::cast_slice_mut(&mut buffer);
+        let account = TreeAccount::load_mut(data).unwrap();
+        account.init(8).unwrap();
+
+        let mut tree = MerkleTree::new(8);
+        for leaf in 1..=40 {
+            assert_eq!(account.insert(leaf).unwrap(), tree.insert(leaf).unwrap());
+        }
+        assert_eq!(account.root(), tree.get_last_root());
+        assert!(account.is_known_root(tree.get_last_root()));
+
+        let stored = TreeAccount::from_tree(&tree).unwrap();
+        assert_eq!(bytemuck::bytes_of(&stored), bytemuck::bytes_of(account));
+        let mut restored = stored.to_tree().unwrap();
+        tree.insert(41).unwrap();
+        restored.insert(41).unwrap();
+        assert_eq!(restored.get_last_root(), tree.get_last_root());
+
+        assert!(TreeAccount::load(&data[1..]).is_err());
+        assert!(TreeAccount::zeroed().init(33).is_err());
+        assert!(TreeAccount::zeroed().insert(1).is_err());
+        assert!(TreeAccount::zeroed().to_tree().is_err());
+
+        let custom = MerkleTree::with_hasher(8, Hasher::default().with_domain("custom"));
+        assert!(TreeAccount::from_tree(&custom).is_err());
+        for (offset, value) in [(8, 33), (10, 0), (10, 31), (9, 30)] {
+            let mut corrupt = bytemuck::bytes_of(&stored).to_vec();
+            corrupt[offset] = value;
+            let mut aligned = vec![0u64; TreeAccount::LEN / 8];
+            bytemuck::cast_slice_mut::<u64, u8>(&mut aligned).copy_from_slice(&corrupt);
+            assert!(TreeAccount::load(bytemuck::cast_slice(&aligned)).is_err());
+            assert!(TreeAccount::load_mut(bytemuck::cast_slice_mut(&mut aligned)).is_err());
+        }
+    }
+}
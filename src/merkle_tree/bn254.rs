@@ -0,0 +1,134 @@
+use crate::curve::field::Fr;
+use crate::hasher::FrHasher;
+use crate::merkle_tree::ROOT_HISTORY_SIZE;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// Deepest tree accepted, the 32 levels `MerkleTreeWithHistory` supports.
+pub const MAX_LEVELS: u8 = 32;
+
+/// Frontier-only incremental tree whose nodes are BN254 field elements, for
+/// on-chain trees (Tornado Cash, Semaphore) whose hashes do not fit a `u128`.
+/// Insertion follows `MerkleTreeWithHistory.insert`.
+#[derive(Debug, Clone)]
+pub struct Bn254MerkleTree<H: FrHasher> {
+    hasher: H,
+    levels: u8,
+    filled_subtrees: Vec<Fr>,
+    zeros: Vec<Fr>,
+    roots: [Fr; ROOT_HISTORY_SIZE as usize],
+    current_root_index: usize,
+    next_index: u64
+}
+
+impl<H: FrHasher> Bn254MerkleTree<H> {
+    /// Starts from a published zeros table: `zeros[i]` is the empty subtree at
+    /// level `i` and the last entry is the empty root, so a tree of `levels`
+    /// takes `levels + 1` values.
+    pub fn from_zeros(hasher: H, zeros: Vec<Fr>) -> Result<Self, SolanaError> {
+        let levels = zeros.len().saturating_sub(1);
+        if levels == 0 || levels > MAX_LEVELS as usize {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree must have between 1 and 32 levels")
+                .with_compared_values(MAX_LEVELS, levels));
+        }
+        let mut roots = [Fr::zero(); ROOT_HISTORY_SIZE as usize];
+        roots[0] = zeros[levels];
+        Ok(Bn254MerkleTree {
+            hasher,
+            levels: levels as u8,
+            filled_subtrees: zeros[..levels].to_vec(),
+            zeros,
+            roots,
+            current_root_index: 0,
+            next_index: 0
+        })
+    }
+
+    /// Builds the zeros table by hashing `zero_leaf` with itself level by level.
+    pub fn with_zero_leaf(hasher: H, levels: u8, zero_leaf: Fr) -> Result<Self, SolanaError> {
+        if levels == 0 || levels > MAX_LEVELS {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree must have between 1 and 32 levels")
+                .with_compared_values(MAX_LEVELS, levels));
+        }
+        let mut zeros = Vec::with_capacity(levels as usize + 1);
+        zeros.push(zero_leaf);
+        for i in 0..levels as usize {
+            zeros.push(hasher.hash_pair(zeros[i], zeros[i]));
+        }
+        Self::from_zeros(hasher, zeros)
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn levels(&self) -> u8 {
+        self.levels
+    }
+
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    /// Empty subtree at `level`, `None` above the root.
+    pub fn zero(&self, level: u8) -> Option<Fr> {
+        self.zeros.get(level as usize).copied()
+    }
+
+    pub fn insert(&mut self, leaf: Fr) -> Result<u64, SolanaError> {
+        if self.next_index >= 1u64 << self.levels {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Merkle tree is full, no more leaves can be added"));
+        }
+
+        let index = self.next_index;
+        let mut current_index = index;
+        let mut current_level_hash = leaf;
+        for i in 0..self.levels as usize {
+            let (left, right) = if current_index.is_multiple_of(2) {
+                self.filled_subtrees[i] = current_level_hash;
+                (current_level_hash, self.zeros[i])
+            } else {
+                (self.filled_subtrees[i], current_level_hash)
+            };
+            current_level_hash = self.hasher.hash_pair(left, right);
+            current_index /= 2;
+        }
+
+        self.current_root_index = (self.current_root_index + 1) % ROOT_HISTORY_SIZE as usize;
+        self.roots[self.current_root_index] = current_level_hash;
+        self.next_index = index + 1;
+        Ok(index)
+    }
+
+    pub fn get_last_root(&self) -> Fr {
+        self.roots[self.current_root_index]
+    }
+
+    pub fn is_known_root(&self, root: Fr) -> bool {
+        !root.is_zero() && self.roots.contains(&root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::mimc_sponge::{hash_left_right, MimcSponge};
+
+    #[test]
+    fn test_insert() {
+        let mut tree = Bn254MerkleTree::with_zero_leaf(MimcSponge, 2, Fr::zero()).unwrap();
+        let z1 = hash_left_right(Fr::zero(), Fr::zero());
+        assert_eq!(tree.get_last_root(), hash_left_right(z1, z1));
+
+        let (a, b) = (Fr::from(1u64), Fr::from(2u64));
+        tree.insert(a).unwrap();
+        tree.insert(b).unwrap();
+        assert_eq!(tree.get_last_root(), hash_left_right(hash_left_right(a, b), z1));
+        assert!(tree.is_known_root(hash_left_right(z1, z1)));
+        tree.insert(a).unwrap();
+        tree.insert(b).unwrap();
+        assert!(tree.insert(a).is_err());
+
+        assert!(Bn254MerkleTree::with_zero_leaf(MimcSponge, 0, Fr::zero()).is_err());
+        assert!(Bn254MerkleTree::with_zero_leaf(MimcSponge, MAX_LEVELS + 1, Fr::zero()).is_err());
+    }
+}
@@ -0,0 +1,66 @@
+use crate::hasher::{Hasher, MerkleHasher};
+use crate::merkle_tree::MerkleProof;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// Inclusion proof of at most `N` levels (and at most 32) with no heap
+/// storage, for microcontrollers. The sides are packed as in
+/// `MerkleProof::verify_slices`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BoundedMerkleProof<const N: usize> {
+    pub leaf: u128,
+    pub path_elements: heapless::Vec<u128, N>,
+    pub path_indices: u32
+}
+
+impl<const N: usize> BoundedMerkleProof<N> {
+    pub fn verify(&self, root: u128) -> bool {
+        self.verify_with(root, &Hasher::default())
+    }
+
+    pub fn verify_with<H: MerkleHasher>(&self, root: u128, hasher: &H) -> bool {
+        MerkleProof::verify_slices(self.leaf, &self.path_elements, self.path_indices, root, hasher)
+    }
+}
+
+impl<const N: usize> TryFrom<&MerkleProof> for BoundedMerkleProof<N> {
+    type Error = SolanaError;
+
+    fn try_from(proof: &MerkleProof) -> Result<Self, SolanaError> {
+        let invalid = |msg: &'static str| utils::err_with_code(ErrorCode::InvalidProof, msg);
+        if proof.path_indices.len() != proof.path_elements.len() || proof.path_indices.len() > 32 {
+            return Err(invalid("Proof path does not fit 32 levels"));
+        }
+        let mut path_indices = 0u32;
+        for (level, side) in proof.path_indices.iter().enumerate() {
+            match side {
+                0 => {}
+                1 => path_indices |= 1 << level,
+                _ => return Err(invalid("Path index must be 0 or 1"))
+            }
+        }
+        Ok(BoundedMerkleProof {
+            leaf: proof.leaf,
+            path_elements: heapless::Vec::from_slice(&proof.path_elements).map_err(|_| invalid("Proof is deeper than the bound"))?,
+            path_indices
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_bounded_proof() {
+        let mut tree = MerkleTree::new(6);
+        for leaf in 1..=10 {
+            tree.insert(leaf).unwrap();
+        }
+        let proof = tree.proof(9).unwrap();
+        let bounded = BoundedMerkleProof::<8>::try_from(&proof).unwrap();
+        assert_eq!(bounded.path_indices, 9);
+        assert!(bounded.verify(tree.get_last_root()));
+        assert!(BoundedMerkleProof::<4>::try_from(&proof).is_err());
+    }
+}
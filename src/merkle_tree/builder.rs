@@ -0,0 +1,168 @@
+use crate::hasher::presets::{self, PresetHasher};
+use crate::hasher::{Hasher, MerkleHasher};
+use crate::merkle_tree::{MerkleTree, ROOT_HISTORY_SIZE};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// What a tree keeps besides its frontier and root history.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum TreeStorage {
+    /// Every leaf, so proofs and updates are available.
+    #[default]
+    Full,
+    /// Only the frontier, see `MerkleTree::with_frontier_only`.
+    FrontierOnly
+}
+
+/// One place to configure a `MerkleTree`. Nothing is checked until `build`,
+/// which reports every invalid setting in a single `InvalidParameters` error.
+#[derive(Debug, Clone)]
+pub struct MerkleTreeBuilder<H: MerkleHasher = Hasher> {
+    hasher: H,
+    depth: Option<u8>,
+    root_history_size: u8,
+    arity: u8,
+    storage: TreeStorage,
+    strict_leaves: bool,
+    errors: Vec<String>
+}
+
+impl Default for MerkleTreeBuilder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl MerkleTreeBuilder {
+    pub fn new() -> Self {
+        MerkleTreeBuilder {
+            hasher: Hasher::default(),
+            depth: None,
+            root_history_size: ROOT_HISTORY_SIZE,
+            arity: 2,
+            storage: TreeStorage::Full,
+            strict_leaves: false,
+            errors: Vec::new()
+        }
+    }
+
+    /// Uses a hasher from `hasher::presets::by_name`.
+    pub fn with_preset(self, name: &str) -> MerkleTreeBuilder<PresetHasher> {
+        match presets::by_name(name) {
+            Some(hasher) => self.with_hasher(hasher),
+            None => {
+                let mut builder = self.with_hasher(PresetHasher::Mimc(Hasher::default()));
+                builder.errors.push(format!("unknown hasher preset {}", name));
+                builder
+            }
+        }
+    }
+}
+
+impl<H: MerkleHasher> MerkleTreeBuilder<H> {
+    pub fn with_hasher<T: MerkleHasher>(self, hasher: T) -> MerkleTreeBuilder<T> {
+        MerkleTreeBuilder {
+            hasher,
+            depth: self.depth,
+            root_history_size: self.root_history_size,
+            arity: self.arity,
+            storage: self.storage,
+            strict_leaves: self.strict_leaves,
+            errors: self.errors
+        }
+    }
+
+    pub fn with_depth(mut self, depth: u8) -> Self {
+        self.depth = Some(depth);
+        self
+    }
+
+    pub fn with_root_history_size(mut self, size: u8) -> Self {
+        self.root_history_size = size;
+        self
+    }
+
+    /// `MerkleTree` is binary; wider trees are `wide::WideMerkleTree`.
+    pub fn with_arity(mut self, arity: u8) -> Self {
+        self.arity = arity;
+        self
+    }
+
+    pub fn with_storage(mut self, storage: TreeStorage) -> Self {
+        self.storage = storage;
+        self
+    }
+
+    pub fn with_strict_leaves(mut self, strict: bool) -> Self {
+        self.strict_leaves = strict;
+        self
+    }
+
+    pub fn build(self) -> Result<MerkleTree<H>, SolanaError> {
+        let mut errors = self.errors;
+        match self.depth {
+            None => errors.push("depth is not set".to_string()),
+            Some(0) => errors.push("depth must be at least 1".to_string()),
+            Some(_) => {}
+        }
+        if self.root_history_size == 0 {
+            errors.push("root history size must be at least 1".to_string());
+        }
+        if self.arity != 2 {
+            errors.push(format!("arity {} is not supported, use wide::WideMerkleTree", self.arity));
+        }
+        if !errors.is_empty() {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, errors.join("; ")));
+        }
+
+        let mut tree = MerkleTree::with_hasher(self.depth.unwrap_or_default(), self.hasher);
+        tree.root_history_size = self.root_history_size;
+        tree.strict_leaves = self.strict_leaves;
+        if self.storage == TreeStorage::FrontierOnly {
+            tree = tree.with_frontier_only();
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::gmimc::Gmimc;
+    use crate::hasher::poseidon2::Poseidon2;
+
+    #[test]
+    fn test_builder() {
+        let tree = MerkleTreeBuilder::new().with_depth(8).build().unwrap();
+        assert_eq!(tree.get_last_root(), MerkleTree::new(8).get_last_root());
+        assert_eq!(tree.root_history_size(), ROOT_HISTORY_SIZE);
+
+        let mut tree = MerkleTreeBuilder::new()
+            .with_depth(4)
+            .with_root_history_size(2)
+            .with_storage(TreeStorage::FrontierOnly)
+            .with_strict_leaves(true)
+            .build()
+            .unwrap();
+        assert!(tree.frontier_only() && tree.strict_leaves());
+        let first = tree.get_last_root();
+        tree.insert(1).unwrap();
+        assert!(tree.is_known_root(first));
+        tree.insert(2).unwrap();
+        assert!(!tree.is_known_root(first));
+
+        let tree = MerkleTreeBuilder::new().with_hasher(Gmimc::default()).with_depth(4).build().unwrap();
+        assert_eq!(tree.zero_hashes()[1], Gmimc::default().zero(1));
+
+        let tree = MerkleTreeBuilder::new().with_preset(presets::POSEIDON2_T3_BN254).with_depth(4).build().unwrap();
+        assert_eq!(tree.get_last_root(), MerkleTree::with_hasher(4, Poseidon2::bn254()).get_last_root());
+
+        let err = MerkleTreeBuilder::new()
+            .with_preset("poseidon")
+            .with_root_history_size(0)
+            .with_arity(4)
+            .build()
+            .unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidParameters);
+        assert_eq!(err.message().split("; ").count(), 4);
+    }
+}
@@ -0,0 +1,122 @@
+use crate::hasher::{Hasher, MerkleHasher};
+use crate::merkle_tree::{MerkleProof, ProofFormat};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+const HEADER_LEN: usize = 16 + 8 + 1;
+
+/// A proof borrowed from its `ProofFormat::Compact` bytes: little-endian
+/// leaf and index, a depth byte, then the siblings. Lengths are checked once
+/// in `parse` and siblings are read straight from the buffer while hashing.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MerkleProofRef<'a> {
+    leaf: u128,
+    leaf_index: u64,
+    siblings: &'a [u8]
+}
+
+impl<'a> MerkleProofRef<'a> {
+    /// Rejects buffers whose length does not match the depth byte exactly,
+    /// depths past 64 and indices that do not fit the depth.
+    pub fn parse(bytes: &'a [u8]) -> Result<Self, SolanaError> {
+        let invalid = |msg: &'static str| utils::err_with_code(ErrorCode::InvalidEncoding, msg);
+        let header = bytes.get(..HEADER_LEN).ok_or_else(|| invalid("Compact proof is shorter than its header"))?;
+        let depth = header[24] as usize;
+        if depth > 64 {
+            return Err(invalid("Compact proof is deeper than 64 levels"));
+        }
+        if bytes.len() != HEADER_LEN + 16 * depth {
+            return Err(invalid("Compact proof length does not match its depth"));
+        }
+        let leaf_index = u64::from_le_bytes(header[16..24].try_into().unwrap());
+        if depth < 64 && leaf_index >> depth != 0 {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Leaf index does not fit in the proof depth"));
+        }
+        Ok(MerkleProofRef { leaf: u128::from_le_bytes(header[..16].try_into().unwrap()), leaf_index, siblings: &bytes[HEADER_LEN..] })
+    }
+
+    pub fn leaf(&self) -> u128 {
+        self.leaf
+    }
+
+    pub fn leaf_index(&self) -> u64 {
+        self.leaf_index
+    }
+
+    pub fn depth(&self) -> usize {
+        self.siblings.len() / 16
+    }
+
+    pub fn siblings(&self) -> impl Iterator<Item = u128> + 'a {
+        self.siblings.chunks_exact(16).map(|chunk| u128::from_le_bytes(chunk.try_into().unwrap()))
+    }
+
+    pub fn root_with<H: MerkleHasher>(&self, hasher: &H) -> u128 {
+        let mut node = self.leaf;
+        for (level, sibling) in self.siblings().enumerate() {
+            let (left, right) = utils::order_pair(node, sibling, (self.leaf_index >> level) & 1 == 1);
+            node = hasher.hash_pair(left, right);
+        }
+        node
+    }
+
+    pub fn verify(&self, root: u128) -> bool {
+        self.verify_with(root, &Hasher::default())
+    }
+
+    pub fn verify_with<H: MerkleHasher>(&self, root: u128, hasher: &H) -> bool {
+        utils::secure_eq(&self.root_with(hasher).to_le_bytes(), &root.to_le_bytes())
+    }
+
+    pub fn to_proof(&self) -> MerkleProof {
+        MerkleProof {
+            leaf: self.leaf,
+            leaf_index: self.leaf_index,
+            path_elements: self.siblings().collect(),
+            path_indices: (0..self.depth()).map(|level| ((self.leaf_index >> level) & 1) as u8).collect()
+        }
+    }
+}
+
+impl MerkleProof {
+    /// `ProofFormat::Compact` bytes. The path indices are not written, since
+    /// they are the bits of `leaf_index` in any proof `verify_detailed`
+    /// accepts.
+    pub fn to_compact(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.encoded_size(ProofFormat::Compact));
+        bytes.extend_from_slice(&self.leaf.to_le_bytes());
+        bytes.extend_from_slice(&self.leaf_index.to_le_bytes());
+        bytes.push(self.path_elements.len() as u8);
+        for sibling in &self.path_elements {
+            bytes.extend_from_slice(&sibling.to_le_bytes());
+        }
+        bytes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_compact() {
+        let mut tree = MerkleTree::new(8);
+        tree.insert_many(1..=20).unwrap();
+        let root = tree.get_last_root();
+        let proof = tree.proof(13).unwrap();
+        let bytes = proof.to_compact();
+        assert_eq!(bytes.len(), proof.encoded_size(ProofFormat::Compact));
+
+        let parsed = MerkleProofRef::parse(&bytes).unwrap();
+        assert_eq!((parsed.leaf(), parsed.leaf_index(), parsed.depth()), (14, 13, 8));
+        assert!(parsed.verify(root));
+        assert!(!parsed.verify(root ^ 1));
+        assert_eq!(parsed.to_proof(), proof);
+
+        assert_eq!(MerkleProofRef::parse(&bytes[..bytes.len() - 1]).unwrap_err().code(), ErrorCode::InvalidEncoding);
+        assert_eq!(MerkleProofRef::parse(&bytes[..10]).unwrap_err().code(), ErrorCode::InvalidEncoding);
+        let mut moved = bytes.clone();
+        moved[16..24].copy_from_slice(&256u64.to_le_bytes());
+        assert_eq!(MerkleProofRef::parse(&moved).unwrap_err().code(), ErrorCode::LeafIndexOutOfRange);
+    }
+}
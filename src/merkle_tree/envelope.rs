@@ -0,0 +1,166 @@
+use crate::hasher::presets;
+use crate::merkle_tree::multi::{self, MerkleMultiProof};
+use crate::merkle_tree::MerkleProof;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+const MAGIC: [u8; 4] = *b"STME";
+pub const ENVELOPE_VERSION: u8 = 1;
+
+/// A batch of claims against one root, as it moves between client, relayer
+/// and verifier: the root, the `hasher::presets` name and the proofs merged
+/// into one `MerkleMultiProof`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ProofEnvelope {
+    pub preset: String,
+    pub root: u128,
+    pub proof: MerkleMultiProof
+}
+
+/// Reads the envelope wire format front to back, failing on short input.
+struct Cursor<'a> {
+    bytes: &'a [u8]
+}
+
+impl<'a> Cursor<'a> {
+    fn take(&mut self, len: usize) -> Result<&'a [u8], SolanaError> {
+        if self.bytes.len() < len {
+            return Err(utils::err_with_code(ErrorCode::InvalidEncoding, "Proof envelope is truncated"));
+        }
+        let (head, rest) = self.bytes.split_at(len);
+        self.bytes = rest;
+        Ok(head)
+    }
+
+    fn u8(&mut self) -> Result<u8, SolanaError> {
+        Ok(self.take(1)?[0])
+    }
+
+    fn u32(&mut self) -> Result<u32, SolanaError> {
+        Ok(u32::from_le_bytes(self.take(4)?.try_into().unwrap()))
+    }
+
+    fn u64(&mut self) -> Result<u64, SolanaError> {
+        Ok(u64::from_le_bytes(self.take(8)?.try_into().unwrap()))
+    }
+
+    fn u128(&mut self) -> Result<u128, SolanaError> {
+        Ok(u128::from_le_bytes(self.take(16)?.try_into().unwrap()))
+    }
+
+    /// Count prefix for items of `item_len` bytes, checked against the bytes
+    /// left so a forged count cannot force a large allocation.
+    fn count(&mut self, item_len: usize) -> Result<usize, SolanaError> {
+        let count = self.u32()? as usize;
+        if count.saturating_mul(item_len) > self.bytes.len() {
+            return Err(utils::err_with_code(ErrorCode::InvalidEncoding, "Proof envelope is truncated"));
+        }
+        Ok(count)
+    }
+}
+
+impl ProofEnvelope {
+    pub fn new(proofs: &[MerkleProof], root: u128, preset: &str) -> Result<Self, SolanaError> {
+        if presets::by_name(preset).is_none() {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Unknown hasher preset"));
+        }
+        Ok(ProofEnvelope { preset: preset.to_string(), root, proof: MerkleMultiProof::aggregate(proofs)? })
+    }
+
+    /// `STME`, the version byte, the preset name with a 1-byte length, the
+    /// root, the depth, then the `(index, leaf)` pairs and the shared siblings,
+    /// each list behind a `u32` count. Integers are little-endian.
+    pub fn encode(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(4 + 1 + 1 + self.preset.len() + 16 + 1 + 4 + 24 * self.proof.leaves.len() + 4 + 16 * self.proof.siblings.len());
+        bytes.extend_from_slice(&MAGIC);
+        bytes.push(ENVELOPE_VERSION);
+        bytes.push(self.preset.len() as u8);
+        bytes.extend_from_slice(self.preset.as_bytes());
+        bytes.extend_from_slice(&self.root.to_le_bytes());
+        bytes.push(self.proof.depth);
+        bytes.extend_from_slice(&(self.proof.leaves.len() as u32).to_le_bytes());
+        for (index, leaf) in &self.proof.leaves {
+            bytes.extend_from_slice(&index.to_le_bytes());
+            bytes.extend_from_slice(&leaf.to_le_bytes());
+        }
+        bytes.extend_from_slice(&(self.proof.siblings.len() as u32).to_le_bytes());
+        for sibling in &self.proof.siblings {
+            bytes.extend_from_slice(&sibling.to_le_bytes());
+        }
+        bytes
+    }
+
+    pub fn decode(bytes: &[u8]) -> Result<Self, SolanaError> {
+        let invalid = |msg: &'static str| utils::err_with_code(ErrorCode::InvalidEncoding, msg);
+        let mut cursor = Cursor { bytes };
+        if cursor.take(4)? != MAGIC {
+            return Err(invalid("Not a proof envelope"));
+        }
+        let version = cursor.u8()?;
+        if version != ENVELOPE_VERSION {
+            return Err(invalid("Unsupported proof envelope version").with_compared_values(ENVELOPE_VERSION, version));
+        }
+        let preset_len = cursor.u8()? as usize;
+        let preset = std::str::from_utf8(cursor.take(preset_len)?).map_err(|_| invalid("Preset name is not UTF-8"))?.to_string();
+        let root = cursor.u128()?;
+        let depth = cursor.u8()?;
+        let leaves: Vec<(u64, u128)> = (0..cursor.count(24)?).map(|_| Ok((cursor.u64()?, cursor.u128()?))).collect::<Result<_, SolanaError>>()?;
+        if leaves.iter().any(|(index, _)| !multi::fits_depth(*index, depth as usize)) {
+            return Err(invalid("Leaf index is outside the proof depth"));
+        }
+        let siblings = (0..cursor.count(16)?).map(|_| cursor.u128()).collect::<Result<_, _>>()?;
+        if !cursor.bytes.is_empty() {
+            return Err(invalid("Proof envelope has trailing bytes"));
+        }
+        Ok(ProofEnvelope { preset, root, proof: MerkleMultiProof { depth, leaves, siblings } })
+    }
+
+    /// Checks every claim at once against the embedded root with the named
+    /// preset. Fails when the preset is unknown.
+    pub fn verify_all(&self) -> Result<bool, SolanaError> {
+        let hasher = presets::by_name(&self.preset)
+            .ok_or_else(|| utils::err_with_code(ErrorCode::InvalidParameters, "Unknown hasher preset"))?;
+        Ok(self.proof.verify_with(self.root, &hasher))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_envelope() {
+        let mut tree = MerkleTree::with_hasher(6, presets::mimc_sponge_220());
+        tree.insert_many(1..=30).unwrap();
+        let proofs: Vec<MerkleProof> = [2, 3, 17, 29].iter().map(|index| tree.proof(*index).unwrap()).collect();
+        let envelope = ProofEnvelope::new(&proofs, tree.get_last_root(), presets::MIMC_SPONGE_220).unwrap();
+        assert!(envelope.verify_all().unwrap());
+
+        let bytes = envelope.encode();
+        assert_eq!(&bytes[..5], b"STME\x01");
+        assert_eq!(ProofEnvelope::decode(&bytes).unwrap(), envelope);
+        assert!(bytes.len() < proofs.iter().map(|proof| proof.encoded_size(crate::merkle_tree::ProofFormat::Compact)).sum());
+
+        let wrong_preset = ProofEnvelope { preset: presets::STEALTH_MIMC_10.to_string(), ..envelope.clone() };
+        assert!(!wrong_preset.verify_all().unwrap());
+        let unknown = ProofEnvelope { preset: "sha256".to_string(), ..envelope.clone() };
+        assert_eq!(unknown.verify_all().unwrap_err().code(), ErrorCode::InvalidParameters);
+        assert!(ProofEnvelope::new(&proofs, 0, "sha256").is_err());
+
+        assert_eq!(ProofEnvelope::decode(&bytes[..bytes.len() - 1]).unwrap_err().code(), ErrorCode::InvalidEncoding);
+        let mut trailing = bytes.clone();
+        trailing.push(0);
+        assert!(ProofEnvelope::decode(&trailing).is_err());
+        let mut future = bytes.clone();
+        future[4] = 2;
+        assert_eq!(ProofEnvelope::decode(&future).unwrap_err().compared_values(), Some("expected 1, got 2"));
+        let count_at = 4 + 1 + 1 + presets::MIMC_SPONGE_220.len() + 16 + 1;
+        let mut outside = bytes.clone();
+        outside[count_at + 4..count_at + 12].copy_from_slice(&64u64.to_le_bytes());
+        assert_eq!(ProofEnvelope::decode(&outside).unwrap_err().code(), ErrorCode::InvalidEncoding);
+        let mut forged = bytes;
+        forged[count_at..count_at + 4].copy_from_slice(&u32::MAX.to_le_bytes());
+        assert!(ProofEnvelope::decode(&forged).is_err());
+    }
+}
@@ -0,0 +1,95 @@
+use alloy_primitives::{keccak256, LogData, B256, U256};
+
+use crate::constants::FIELD_SIZE;
+use crate::hasher::MerkleHasher;
+use crate::merkle_tree::replay::{replay_into, DepositEvent};
+use crate::merkle_tree::MerkleTree;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// Tornado Cash `Deposit(bytes32 indexed commitment, uint32 leafIndex, uint256 timestamp)`.
+pub const DEPOSIT_EVENT_SIGNATURE: &str = "Deposit(bytes32,uint32,uint256)";
+
+/// `topics[0]` of every deposit log.
+pub fn deposit_topic() -> B256 {
+    keccak256(DEPOSIT_EVENT_SIGNATURE)
+}
+
+/// Decodes a deposit log: `topics = [deposit_topic(), commitment]` and
+/// `data = abi.encode(uint32 leafIndex, uint256 timestamp)`.
+///
+/// Commitments are BN254 field elements and are reduced into `FIELD_SIZE`,
+/// so the rebuilt tree has its own roots rather than the contract's; the logs
+/// carry no root, so `root` is `None`.
+pub fn deposit_event(log: &LogData) -> Result<DepositEvent, SolanaError> {
+    let malformed = |msg: &'static str| utils::err_with_code(ErrorCode::InvalidParameters, msg);
+
+    let topics = log.topics();
+    if topics.len() != 2 || topics[0] != deposit_topic() {
+        return Err(malformed("Log is not a Tornado deposit event"));
+    }
+    if log.data.len() != 64 {
+        return Err(malformed("Deposit log data must be two ABI words"));
+    }
+
+    let commitment = U256::from_be_bytes(topics[1].0) % U256::from(FIELD_SIZE);
+    let leaf_index = U256::from_be_slice(&log.data[..32]);
+    let timestamp = U256::from_be_slice(&log.data[32..]);
+    Ok(DepositEvent {
+        commitment: commitment.to::<u128>(),
+        leaf_index: u32::try_from(leaf_index).map_err(|_| malformed("Deposit leaf index is not a uint32"))? as u64,
+        timestamp: u64::try_from(timestamp).map_err(|_| malformed("Deposit timestamp does not fit in 64 bits"))?,
+        root: None
+    })
+}
+
+/// Decodes every log in order and replays the deposits into `tree`.
+pub fn sync_from_logs<'a, H: MerkleHasher>(
+    tree: &mut MerkleTree<H>,
+    logs: impl IntoIterator<Item = &'a LogData>
+) -> Result<(), SolanaError> {
+    let events = logs.into_iter().map(deposit_event).collect::<Result<Vec<_>, _>>()?;
+    replay_into(tree, events)
+}
+
+#[cfg(test)]
+mod tests {
+    use alloy_primitives::Bytes;
+
+    use super::*;
+
+    fn deposit_log(commitment: B256, leaf_index: u32, timestamp: u64) -> LogData {
+        let mut data = [0u8; 64];
+        data[28..32].copy_from_slice(&leaf_index.to_be_bytes());
+        data[56..64].copy_from_slice(&timestamp.to_be_bytes());
+        LogData::new_unchecked(vec![deposit_topic(), commitment], Bytes::copy_from_slice(&data))
+    }
+
+    #[test]
+    fn test_deposit_event() {
+        assert_eq!(
+            deposit_topic().to_string(),
+            "0xa945e51eec50ab98c161376f0db4cf2aeba3ec92755fe2fcd388bdbbb80ff196"
+        );
+
+        let commitment = B256::with_last_byte(7);
+        let event = deposit_event(&deposit_log(commitment, 3, 1_600_000_000)).unwrap();
+        assert_eq!(event, DepositEvent { commitment: 7, leaf_index: 3, timestamp: 1_600_000_000, root: None });
+
+        let mut large = [0xffu8; 32];
+        large[0] = 0x01;
+        let event = deposit_event(&deposit_log(B256::from(large), 0, 0)).unwrap();
+        assert_eq!(event.commitment, (U256::from_be_bytes(large) % U256::from(FIELD_SIZE)).to::<u128>());
+
+        let wrong_topic = LogData::new_unchecked(vec![B256::ZERO, commitment], Bytes::from(vec![0u8; 64]));
+        assert_eq!(deposit_event(&wrong_topic).err().unwrap().code(), ErrorCode::InvalidParameters);
+    }
+
+    #[test]
+    fn test_sync_from_logs() {
+        let logs: Vec<LogData> = (0..3u32).map(|i| deposit_log(B256::with_last_byte(i as u8 + 1), i, 0)).collect();
+        let mut tree = MerkleTree::new(4);
+        sync_from_logs(&mut tree, &logs).unwrap();
+        assert_eq!(tree.leaves(), [1, 2, 3]);
+        assert!(sync_from_logs(&mut tree, &logs[..1]).is_err());
+    }
+}
@@ -0,0 +1,155 @@
+use crate::hasher::{Hasher, MerkleHasher};
+use crate::merkle_tree::{MerkleProof, ROOT_HISTORY_SIZE};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// Frontier-only incremental tree with its depth fixed at compile time. Every
+/// field is an array, so inserting and checking roots never allocate.
+#[derive(Debug, Clone)]
+pub struct FixedMerkleTree<const DEPTH: usize, H: MerkleHasher = Hasher> {
+    hasher: H,
+    filled_subtrees: [u128; DEPTH],
+    zeros: [u128; DEPTH],
+    roots: [u128; ROOT_HISTORY_SIZE as usize],
+    current_root_index: usize,
+    next_index: u64
+}
+
+/// Inclusion proof with the path held inline, laid out like `MerkleProof`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct FixedMerkleProof<const DEPTH: usize> {
+    pub leaf: u128,
+    pub leaf_index: u64,
+    pub path_elements: [u128; DEPTH],
+    pub path_indices: [u8; DEPTH]
+}
+
+impl<const DEPTH: usize> FixedMerkleProof<DEPTH> {
+    pub fn root_with<H: MerkleHasher>(&self, hasher: &H) -> u128 {
+        let mut current_level_hash = self.leaf;
+        for (sibling, index) in self.path_elements.iter().zip(&self.path_indices) {
+            let (left, right) = utils::order_pair(current_level_hash, *sibling, *index != 0);
+            current_level_hash = hasher.hash_pair(left, right);
+        }
+        current_level_hash
+    }
+
+    pub fn verify(&self, root: u128) -> bool {
+        self.verify_with(root, &Hasher::default())
+    }
+
+    pub fn verify_with<H: MerkleHasher>(&self, root: u128, hasher: &H) -> bool {
+        utils::secure_eq(&self.root_with(hasher).to_le_bytes(), &root.to_le_bytes())
+    }
+}
+
+impl<const DEPTH: usize> TryFrom<&MerkleProof> for FixedMerkleProof<DEPTH> {
+    type Error = SolanaError;
+
+    fn try_from(proof: &MerkleProof) -> Result<Self, SolanaError> {
+        let wrong_depth = || utils::err_with_code(ErrorCode::InvalidParameters, "Proof depth does not match");
+        Ok(FixedMerkleProof {
+            leaf: proof.leaf,
+            leaf_index: proof.leaf_index,
+            path_elements: proof.path_elements.as_slice().try_into().map_err(|_| wrong_depth())?,
+            path_indices: proof.path_indices.as_slice().try_into().map_err(|_| wrong_depth())?
+        })
+    }
+}
+
+impl<const DEPTH: usize> FixedMerkleTree<DEPTH> {
+    pub fn new() -> Self {
+        Self::with_hasher(Hasher::default())
+    }
+}
+
+impl<const DEPTH: usize> Default for FixedMerkleTree<DEPTH> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<const DEPTH: usize, H: MerkleHasher> FixedMerkleTree<DEPTH, H> {
+    /// Starts from the same state as `MerkleTree::with_hasher(DEPTH, hasher)`.
+    pub fn with_hasher(hasher: H) -> Self {
+        assert!(DEPTH > 0, "Tree must have at least one level");
+        let zeros = core::array::from_fn(|i| hasher.zero(i as u8));
+        let mut roots = [0; ROOT_HISTORY_SIZE as usize];
+        roots[0] = zeros[DEPTH - 1];
+        FixedMerkleTree {
+            hasher,
+            filled_subtrees: zeros,
+            zeros,
+            roots,
+            current_root_index: 0,
+            next_index: 0
+        }
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn next_index(&self) -> u64 {
+        self.next_index
+    }
+
+    pub fn insert(&mut self, leaf: u128) -> Result<u64, SolanaError> {
+        if self.next_index as u128 >= 1u128.checked_shl(DEPTH as u32).unwrap_or(u128::MAX) {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Merkle tree is full, no more leaves can be added"));
+        }
+
+        let index = self.next_index;
+        let mut current_index = index;
+        let mut current_level_hash = leaf;
+        for i in 0..DEPTH {
+            let (left, right) = if current_index.is_multiple_of(2) {
+                self.filled_subtrees[i] = current_level_hash;
+                (current_level_hash, self.zeros[i])
+            } else {
+                (self.filled_subtrees[i], current_level_hash)
+            };
+            current_level_hash = self.hasher.hash_pair(left, right);
+            current_index /= 2;
+        }
+
+        self.current_root_index = (self.current_root_index + 1) % ROOT_HISTORY_SIZE as usize;
+        self.roots[self.current_root_index] = current_level_hash;
+        self.next_index = index + 1;
+        Ok(index)
+    }
+
+    pub fn get_last_root(&self) -> u128 {
+        self.roots[self.current_root_index]
+    }
+
+    pub fn is_known_root(&self, root: u128) -> bool {
+        root != 0 && self.roots.contains(&root)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_matches_merkle_tree() {
+        let mut fixed = FixedMerkleTree::<8>::new();
+        let mut tree = MerkleTree::new(8);
+        assert_eq!(fixed.get_last_root(), tree.get_last_root());
+        for leaf in [11, 22, 33] {
+            assert_eq!(fixed.insert(leaf).unwrap(), tree.insert(leaf).unwrap());
+            assert_eq!(fixed.get_last_root(), tree.get_last_root());
+        }
+        assert!(fixed.is_known_root(tree.get_last_root()));
+
+        let proof = FixedMerkleProof::<8>::try_from(&tree.proof(1).unwrap()).unwrap();
+        assert!(proof.verify(fixed.get_last_root()));
+        assert!(FixedMerkleProof::<4>::try_from(&tree.proof(1).unwrap()).is_err());
+
+        let mut full = FixedMerkleTree::<1>::new();
+        full.insert(1).unwrap();
+        full.insert(2).unwrap();
+        assert!(full.insert(3).is_err());
+    }
+}
@@ -0,0 +1,89 @@
+use std::collections::HashSet;
+use std::io::Read;
+
+use primitive_types::U256;
+
+use crate::constants::FIELD_SIZE;
+use crate::merkle_tree::MerkleTree;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+#[derive(serde::Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct DepositRecord {
+    commitment: String,
+    leaf_index: u64
+}
+
+impl MerkleTree {
+    /// Rebuilds a tree from a subgraph dump, `[{"commitment": "0x..", "leafIndex": n}, ...]`.
+    ///
+    /// Records must be in leaf order starting at zero. Commitments are 32-byte
+    /// hex values reduced into `FIELD_SIZE`, as in `merkle_tree::evm`, and each
+    /// reduced leaf may appear only once.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip(reader), err))]
+    pub fn import_json(levels: u8, reader: impl Read) -> Result<MerkleTree, SolanaError> {
+        let records: Vec<DepositRecord> = serde_json::from_reader(reader)
+            .map_err(|e| utils::err_with_code(ErrorCode::InvalidEncoding, format!("Invalid deposit dump: {}", e)))?;
+
+        let mut seen = HashSet::with_capacity(records.len());
+        let mut leaves = Vec::with_capacity(records.len());
+        for (position, record) in records.iter().enumerate() {
+            if record.leaf_index != position as u64 {
+                return Err(utils::err_with_code(
+                    ErrorCode::LeafIndexOutOfRange,
+                    format!("Expected leaf {} but found leaf {}", position, record.leaf_index)
+                ));
+            }
+            let leaf = (parse_commitment(&record.commitment)? % U256::from(FIELD_SIZE)).as_u128();
+            if !seen.insert(leaf) {
+                return Err(utils::err_with_code(
+                    ErrorCode::CommitmentAlreadySubmitted,
+                    format!("Leaf {} repeats an earlier commitment", position)
+                ));
+            }
+            leaves.push(leaf);
+        }
+
+        let mut tree = MerkleTree::new(levels);
+        tree.insert_many(leaves)?;
+        Ok(tree)
+    }
+}
+
+fn parse_commitment(value: &str) -> Result<U256, SolanaError> {
+    let digits = value.strip_prefix("0x").unwrap_or(value);
+    if digits.is_empty() || digits.len() > 64 {
+        return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Commitment must be 1 to 32 bytes of hex"));
+    }
+    U256::from_str_radix(digits, 16)
+        .map_err(|_| utils::err_with_code(ErrorCode::InvalidEncoding, "Commitment is not valid hex"))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_import_json() {
+        let dump = r#"[
+            {"commitment": "0x0b", "leafIndex": 0},
+            {"commitment": "0x16", "leafIndex": 1, "timestamp": "1600000000"}
+        ]"#;
+        let tree = MerkleTree::import_json(4, dump.as_bytes()).unwrap();
+        assert_eq!(tree.leaves(), [11, 22]);
+
+        let out_of_order = r#"[{"commitment": "0x0b", "leafIndex": 1}]"#;
+        let err = MerkleTree::import_json(4, out_of_order.as_bytes()).err().unwrap();
+        assert_eq!(err.code(), ErrorCode::LeafIndexOutOfRange);
+
+        let duplicate = r#"[{"commitment": "0x0b", "leafIndex": 0}, {"commitment": "0x000b", "leafIndex": 1}]"#;
+        let err = MerkleTree::import_json(4, duplicate.as_bytes()).err().unwrap();
+        assert_eq!(err.code(), ErrorCode::CommitmentAlreadySubmitted);
+        let reduced = format!(r#"[{{"commitment": "0x0b", "leafIndex": 0}}, {{"commitment": "0x{:x}", "leafIndex": 1}}]"#, U256::from(FIELD_SIZE) + 11);
+        let err = MerkleTree::import_json(4, reduced.as_bytes()).err().unwrap();
+        assert_eq!(err.code(), ErrorCode::CommitmentAlreadySubmitted);
+
+        assert!(MerkleTree::import_json(4, "{}".as_bytes()).is_err());
+        assert!(MerkleTree::import_json(4, r#"[{"commitment": "0xzz", "leafIndex": 0}]"#.as_bytes()).is_err());
+    }
+}
@@ -0,0 +1,164 @@
+use std::collections::BTreeMap;
+
+use crate::hasher::{Hasher, MerkleHasher};
+use crate::merkle_tree::MerkleProof;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// Several inclusion proofs against one root, sharing their paths. Only the
+/// siblings that cannot be recomputed from the other leaves are kept, level by
+/// level from the leaves up and in increasing node index within a level.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "scale", derive(parity_scale_codec::Encode, parity_scale_codec::Decode))]
+pub struct MerkleMultiProof {
+    pub depth: u8,
+    pub leaves: Vec<(u64, u128)>,
+    pub siblings: Vec<u128>
+}
+
+impl MerkleMultiProof {
+    pub fn aggregate(proofs: &[MerkleProof]) -> Result<Self, SolanaError> {
+        let invalid = |msg: &'static str| utils::err_with_code(ErrorCode::InvalidProof, msg);
+        let depth = proofs.first().ok_or_else(|| invalid("No proofs to aggregate"))?.path_elements.len();
+        if depth > u8::MAX as usize {
+            return Err(invalid("Proof is deeper than 255 levels"));
+        }
+
+        let mut leaves = BTreeMap::new();
+        for proof in proofs {
+            if proof.path_elements.len() != depth || proof.path_indices.len() != depth {
+                return Err(invalid("Proofs have different depths"));
+            }
+            if !fits_depth(proof.leaf_index, depth) {
+                return Err(invalid("Leaf index is outside the proof depth"));
+            }
+            if *leaves.entry(proof.leaf_index).or_insert(proof.leaf) != proof.leaf {
+                return Err(invalid("Proofs disagree on a leaf"));
+            }
+        }
+
+        // Sibling of every known node, taken from any proof passing through it.
+        let mut siblings = Vec::new();
+        let mut known: BTreeMap<u64, &MerkleProof> = proofs.iter().map(|proof| (proof.leaf_index, proof)).collect();
+        for level in 0..depth {
+            let mut parents = BTreeMap::new();
+            for (index, proof) in &known {
+                if !known.contains_key(&(index ^ 1)) {
+                    siblings.push(proof.path_elements[level]);
+                }
+                parents.entry(index / 2).or_insert(*proof);
+            }
+            known = parents;
+        }
+
+        Ok(MerkleMultiProof {
+            depth: depth as u8,
+            leaves: leaves.into_iter().collect(),
+            siblings
+        })
+    }
+
+    pub fn root(&self) -> Option<u128> {
+        self.root_with(&Hasher::default())
+    }
+
+    /// Recomputes the root, or `None` when the sibling list does not fit the
+    /// leaf positions.
+    pub fn root_with<H: MerkleHasher>(&self, hasher: &H) -> Option<u128> {
+        let mut known: BTreeMap<u64, u128> = self.leaves.iter().copied().collect();
+        if known.len() != self.leaves.len() || known.is_empty() {
+            return None;
+        }
+        if known.keys().any(|index| !fits_depth(*index, self.depth as usize)) {
+            return None;
+        }
+        let mut siblings = self.siblings.iter();
+        for _ in 0..self.depth {
+            let mut parents = BTreeMap::new();
+            for (index, hash) in &known {
+                if parents.contains_key(&(index / 2)) {
+                    continue;
+                }
+                let sibling = match known.get(&(index ^ 1)) {
+                    Some(sibling) => *sibling,
+                    None => *siblings.next()?
+                };
+                let (left, right) = utils::order_pair(*hash, sibling, index % 2 == 1);
+                parents.insert(index / 2, hasher.hash_pair(left, right));
+            }
+            known = parents;
+        }
+
+        if known.len() != 1 || siblings.next().is_some() {
+            return None;
+        }
+        known.remove(&0)
+    }
+
+    pub fn verify(&self, root: u128) -> bool {
+        self.verify_with(root, &Hasher::default())
+    }
+
+    pub fn verify_with<H: MerkleHasher>(&self, root: u128, hasher: &H) -> bool {
+        self.root_with(hasher)
+            .is_some_and(|computed| utils::secure_eq(&computed.to_le_bytes(), &root.to_le_bytes()))
+    }
+}
+
+/// Whether `index` addresses a leaf of a tree with `depth` levels.
+pub(crate) fn fits_depth(index: u64, depth: usize) -> bool {
+    depth >= 64 || index < 1 << depth
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_aggregate() {
+        let mut tree = MerkleTree::new(4);
+        tree.insert_many(1..=11).unwrap();
+        let root = tree.get_last_root();
+
+        let proofs: Vec<MerkleProof> = [0, 1, 5, 10].iter().map(|index| tree.proof(*index).unwrap()).collect();
+        let multi = MerkleMultiProof::aggregate(&proofs).unwrap();
+        assert!(multi.verify(root));
+        // Two siblings at level 0 (4, 11), three at level 1, one at level 2,
+        // against sixteen for the separate proofs.
+        assert_eq!(multi.siblings.len(), 6);
+
+        let single = MerkleMultiProof::aggregate(&proofs[2..3]).unwrap();
+        assert_eq!(single.siblings, proofs[2].path_elements);
+        assert!(single.verify(root));
+
+        let mut tampered = multi.clone();
+        tampered.leaves[2].1 = 99;
+        assert!(!tampered.verify(root));
+        let mut truncated = multi.clone();
+        truncated.siblings.pop();
+        assert!(!truncated.verify(root));
+        let mut extended = multi;
+        extended.siblings.push(0);
+        assert!(!extended.verify(root));
+
+        assert!(MerkleMultiProof::aggregate(&[]).is_err());
+        let mut conflicting = proofs[0].clone();
+        conflicting.leaf = 42;
+        assert!(MerkleMultiProof::aggregate(&[proofs[0].clone(), conflicting]).is_err());
+        let mut outside = proofs[0].clone();
+        outside.leaf_index = 16;
+        assert!(MerkleMultiProof::aggregate(&[outside]).is_err());
+
+        // A leaf past the last index must not fold into a second top-level node.
+        let mut small = MerkleTree::new(1);
+        small.insert_many([5, 6]).unwrap();
+        let forged = MerkleMultiProof {
+            depth: 1,
+            leaves: vec![(0, 5), (2, 999)],
+            siblings: vec![6, 123]
+        };
+        assert_eq!(forged.root(), None);
+        assert!(!forged.verify(small.get_last_root()));
+    }
+}
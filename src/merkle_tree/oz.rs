@@ -0,0 +1,118 @@
+use crate::hasher::keccak::keccak256;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+pub type Bytes32 = [u8; 32];
+
+/// `keccak256` of the two nodes in ascending byte order, as in OpenZeppelin's
+/// `Hashes.commutativeKeccak256`.
+pub fn hash_sorted_pair(a: &Bytes32, b: &Bytes32) -> Bytes32 {
+    let (low, high) = if a <= b { (a, b) } else { (b, a) };
+    let mut data = [0u8; 64];
+    data[..32].copy_from_slice(low);
+    data[32..].copy_from_slice(high);
+    keccak256(&data)
+}
+
+/// Leaf hash used by OpenZeppelin's `StandardMerkleTree`:
+/// `keccak256(bytes.concat(keccak256(abi.encode(values))))`. `encoded` is the
+/// ABI encoding of the leaf values.
+pub fn standard_leaf(encoded: &[u8]) -> Bytes32 {
+    keccak256(&keccak256(encoded))
+}
+
+/// Root reached from `leaf` through `proof`, as `MerkleProof.processProof`.
+pub fn process_proof(proof: &[Bytes32], leaf: &Bytes32) -> Bytes32 {
+    proof.iter().fold(*leaf, |node, sibling| hash_sorted_pair(&node, sibling))
+}
+
+/// Same check as OpenZeppelin's `MerkleProof.verify`.
+pub fn verify(proof: &[Bytes32], root: &Bytes32, leaf: &Bytes32) -> bool {
+    process_proof(proof, leaf) == *root
+}
+
+/// Keccak tree with sorted-pair hashing over `bytes32` leaves, whose proofs
+/// verify with OpenZeppelin's `MerkleProof` library. Leaves keep the order
+/// given; an unpaired node on a level is carried up unchanged, so proofs have
+/// no padding siblings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct OzMerkleTree {
+    layers: Vec<Vec<Bytes32>>
+}
+
+impl OzMerkleTree {
+    pub fn new(leaves: Vec<Bytes32>) -> Result<Self, SolanaError> {
+        if leaves.is_empty() {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree needs at least one leaf"));
+        }
+        let mut layers = vec![leaves];
+        while layers[layers.len() - 1].len() > 1 {
+            let next = layers[layers.len() - 1]
+                .chunks(2)
+                .map(|pair| match pair {
+                    [left, right] => hash_sorted_pair(left, right),
+                    [node] => *node,
+                    _ => unreachable!()
+                })
+                .collect();
+            layers.push(next);
+        }
+        Ok(OzMerkleTree { layers })
+    }
+
+    pub fn root(&self) -> Bytes32 {
+        self.layers[self.layers.len() - 1][0]
+    }
+
+    pub fn leaves(&self) -> &[Bytes32] {
+        &self.layers[0]
+    }
+
+    pub fn proof(&self, index: usize) -> Result<Vec<Bytes32>, SolanaError> {
+        if index >= self.layers[0].len() {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Leaf index is out of range"));
+        }
+        let mut proof = Vec::new();
+        let mut index = index;
+        for layer in &self.layers[..self.layers.len() - 1] {
+            if let Some(sibling) = layer.get(index ^ 1) {
+                proof.push(*sibling);
+            }
+            index /= 2;
+        }
+        Ok(proof)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn leaf(i: u8) -> Bytes32 {
+        let mut encoded = [0u8; 32];
+        encoded[31] = i;
+        standard_leaf(&encoded)
+    }
+
+    #[test]
+    fn test_oz_tree() {
+        assert_eq!(
+            hex::encode(keccak256(b"")),
+            "c5d2460186f7233c927e7db2dcc703c0e500b653ca82273b7bfad8045d85a470"
+        );
+        assert_eq!(hash_sorted_pair(&leaf(1), &leaf(2)), hash_sorted_pair(&leaf(2), &leaf(1)));
+
+        let leaves: Vec<Bytes32> = (0..5).map(leaf).collect();
+        let tree = OzMerkleTree::new(leaves.clone()).unwrap();
+        for (i, leaf) in leaves.iter().enumerate() {
+            assert!(verify(&tree.proof(i).unwrap(), &tree.root(), leaf));
+        }
+        assert_eq!(tree.proof(4).unwrap().len(), 1);
+        assert!(!verify(&tree.proof(0).unwrap(), &tree.root(), &leaves[1]));
+        assert_eq!(tree.proof(5).unwrap_err().code(), ErrorCode::LeafIndexOutOfRange);
+
+        let single = OzMerkleTree::new(vec![leaves[0]]).unwrap();
+        assert_eq!(single.root(), leaves[0]);
+        assert!(single.proof(0).unwrap().is_empty());
+        assert!(OzMerkleTree::new(Vec::new()).is_err());
+    }
+}
@@ -0,0 +1,85 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::hasher::MerkleHasher;
+use crate::merkle_tree::MerkleTree;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// One recorded deposit: the inserted commitment, the index it was given and,
+/// when the source records it, the tree root right after the insert.
+#[derive(Debug, Clone, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+#[cfg_attr(feature = "rlp", derive(alloy_rlp::RlpEncodable, alloy_rlp::RlpDecodable), rlp(trailing))]
+#[cfg_attr(all(feature = "defmt", target_os = "none"), derive(defmt::Format))]
+pub struct DepositEvent {
+    pub commitment: u128,
+    pub leaf_index: u64,
+    pub timestamp: u64,
+    pub root: Option<u128>
+}
+
+/// Rebuilds a tree of `levels` levels from events in insertion order.
+pub fn replay(levels: u8, events: impl IntoIterator<Item = DepositEvent>) -> Result<MerkleTree, SolanaError> {
+    let mut tree = MerkleTree::new(levels);
+    replay_into(&mut tree, events)?;
+    Ok(tree)
+}
+
+/// Inserts each event's commitment and checks its index and any recorded root,
+/// stopping at the first event that disagrees with the rebuilt tree.
+#[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(from = tree.next_index()), err))]
+pub fn replay_into<H: MerkleHasher>(
+    tree: &mut MerkleTree<H>,
+    events: impl IntoIterator<Item = DepositEvent>
+) -> Result<(), SolanaError> {
+    for event in events {
+        if event.leaf_index != tree.next_index() {
+            return Err(utils::err_with_code(
+                ErrorCode::LeafIndexOutOfRange,
+                format!("Event for leaf {} arrived when the tree expects leaf {}", event.leaf_index, tree.next_index())
+            ));
+        }
+        tree.insert(event.commitment)?;
+        if event.root.is_some_and(|root| root != tree.get_last_root()) {
+            return Err(utils::err_with_code(
+                ErrorCode::UnknownRoot,
+                format!("Root after leaf {} does not match the recorded root", event.leaf_index)
+            ));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn record(levels: u8, commitments: &[u128]) -> Vec<DepositEvent> {
+        let mut tree = MerkleTree::new(levels);
+        commitments
+            .iter()
+            .map(|commitment| {
+                let leaf_index = tree.insert(*commitment).unwrap();
+                DepositEvent { commitment: *commitment, leaf_index, timestamp: 1_700_000_000 + leaf_index, root: Some(tree.get_last_root()) }
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_replay() {
+        let events = record(4, &[11, 22, 33]);
+        let tree = replay(4, events.clone()).unwrap();
+        assert_eq!(Some(tree.get_last_root()), events[2].root);
+
+        let unrooted = events.iter().map(|event| DepositEvent { root: None, ..event.clone() });
+        assert_eq!(replay(4, unrooted).unwrap().get_last_root(), tree.get_last_root());
+
+        let mut diverged = events.clone();
+        diverged[1].root = Some(5);
+        let err = replay(4, diverged).err().unwrap();
+        assert_eq!(err.code(), ErrorCode::UnknownRoot);
+        assert!(err.message().contains("leaf 1"));
+
+        let err = replay(4, [events[0].clone(), events[2].clone()]).err().unwrap();
+        assert_eq!(err.code(), ErrorCode::LeafIndexOutOfRange);
+    }
+}
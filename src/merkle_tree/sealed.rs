@@ -0,0 +1,61 @@
+use crate::hasher::presets;
+use crate::merkle_tree::MerkleProof;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// A proof that carries everything needed to check it: the target root, the
+/// tree depth and the `hasher::presets` name of the node hash.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SealedProof {
+    pub preset: String,
+    pub depth: u8,
+    pub root: u128,
+    pub proof: MerkleProof
+}
+
+impl SealedProof {
+    pub fn new(proof: MerkleProof, root: u128, preset: &str) -> Result<Self, SolanaError> {
+        if presets::by_name(preset).is_none() {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Unknown hasher preset"));
+        }
+        let depth = u8::try_from(proof.path_elements.len())
+            .map_err(|_| utils::err_with_code(ErrorCode::InvalidParameters, "Proof is deeper than 255 levels"))?;
+        Ok(SealedProof { preset: preset.to_string(), depth, root, proof })
+    }
+
+    /// Verifies against the embedded root with the named preset. Fails when the
+    /// preset is unknown; a depth that disagrees with the path is `false`.
+    pub fn verify(&self) -> Result<bool, SolanaError> {
+        let hasher = presets::by_name(&self.preset)
+            .ok_or_else(|| utils::err_with_code(ErrorCode::InvalidParameters, "Unknown hasher preset"))?;
+        Ok(self.proof.path_elements.len() == self.depth as usize && self.proof.verify_with(self.root, &hasher))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_sealed_proof() {
+        let mut tree = MerkleTree::with_hasher(4, presets::mimc_sponge_220());
+        tree.insert_many([11, 22]).unwrap();
+        let sealed = SealedProof::new(tree.proof(1).unwrap(), tree.get_last_root(), presets::MIMC_SPONGE_220).unwrap();
+        assert_eq!(sealed.depth, 4);
+        assert!(sealed.verify().unwrap());
+
+        let wrong_preset = SealedProof { preset: presets::STEALTH_MIMC_10.to_string(), ..sealed.clone() };
+        assert!(!wrong_preset.verify().unwrap());
+        let wrong_depth = SealedProof { depth: 3, ..sealed.clone() };
+        assert!(!wrong_depth.verify().unwrap());
+        let unknown = SealedProof { preset: "sha256".to_string(), ..sealed.clone() };
+        assert_eq!(unknown.verify().err().unwrap().code(), ErrorCode::InvalidParameters);
+        assert!(SealedProof::new(sealed.proof, 0, "sha256").is_err());
+
+        let mut tree = presets::poseidon2_bn254_tree(4);
+        tree.insert_many([11, 22]).unwrap();
+        let sealed = SealedProof::new(tree.proof(0).unwrap(), tree.get_last_root(), presets::POSEIDON2_T3_BN254).unwrap();
+        assert!(sealed.verify().unwrap());
+    }
+}
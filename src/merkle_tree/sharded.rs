@@ -0,0 +1,109 @@
+use std::thread;
+
+use crate::hasher::MerkleHasher;
+use crate::merkle_tree::MerkleTree;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+impl<H: MerkleHasher + Sync> MerkleTree<H> {
+    /// Builds the tree holding `leaves` by splitting the leaf range into
+    /// `2^shard_bits` subtrees, hashing them on at most
+    /// `available_parallelism` threads and merging their roots into the upper
+    /// levels. The result is the same tree as
+    /// inserting the leaves one by one, with a single root in its history
+    /// after the empty one.
+    pub fn from_leaves_sharded(levels: u8, hasher: H, leaves: &[u128], shard_bits: u8) -> Result<Self, SolanaError> {
+        if shard_bits > levels {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Cannot split into more shards than the tree has leaves"));
+        }
+        let mut tree = MerkleTree::with_hasher(levels, hasher);
+        if leaves.len() as u128 > tree.capacity() {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Merkle tree has no room for every leaf"));
+        }
+        if leaves.is_empty() {
+            return Ok(tree);
+        }
+
+        let shard_height = levels - shard_bits;
+        let shard_size = 1usize.checked_shl(shard_height as u32).unwrap_or(usize::MAX);
+        // Each thread takes a contiguous run of shards, so the results stay in
+        // leaf order.
+        let shard_count = leaves.len().div_ceil(shard_size);
+        let threads = thread::available_parallelism().map_or(1, |n| n.get()).min(shard_count);
+        let run_size = shard_size.saturating_mul(shard_count.div_ceil(threads));
+        let shards: Vec<Vec<Vec<u128>>> = thread::scope(|scope| {
+            let tree = &tree;
+            let handles: Vec<_> = leaves
+                .chunks(run_size)
+                .map(|run| {
+                    scope.spawn(move || {
+                        run.chunks(shard_size).map(|shard| tree.subtree_levels(shard, 0, shard_height)).collect::<Vec<_>>()
+                    })
+                })
+                .collect();
+            handles.into_iter().flat_map(|handle| handle.join().expect("Shard thread panicked")).collect()
+        });
+
+        let mut nodes: Vec<Vec<u128>> = vec![Vec::new(); shard_height as usize + 1];
+        for shard in shards {
+            for (level, shard_nodes) in nodes.iter_mut().zip(shard) {
+                level.extend(shard_nodes);
+            }
+        }
+        let upper = tree.subtree_levels(&nodes[shard_height as usize], shard_height, shard_bits);
+        nodes.extend(upper.into_iter().skip(1));
+
+        for i in 0..levels as usize {
+            tree.filled_subtrees[i] = nodes[i][(nodes[i].len() - 1) & !1];
+        }
+        tree.push_root(nodes[levels as usize][0]);
+        tree.next_index = leaves.len() as u64;
+        for leaf in leaves {
+            tree.push_leaf(*leaf);
+        }
+        Ok(tree)
+    }
+
+    // Nodes from `base`, at level `base_level` of the whole tree, up `height` levels.
+    fn subtree_levels(&self, base: &[u128], base_level: u8, height: u8) -> Vec<Vec<u128>> {
+        let mut nodes = vec![base.to_vec()];
+        for i in 0..height {
+            let zero = self.zeros[(base_level + i) as usize];
+            let parents = nodes[i as usize]
+                .chunks(2)
+                .map(|pair| self.hasher.hash_pair(pair[0], pair.get(1).copied().unwrap_or(zero)))
+                .collect();
+            nodes.push(parents);
+        }
+        nodes
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::hasher::Hasher;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_from_leaves_sharded() {
+        let leaves: Vec<u128> = (1..=37).collect();
+        let mut expected = MerkleTree::new(7);
+        expected.insert_many(leaves.iter().copied()).unwrap();
+
+        for shard_bits in [0, 1, 3, 7] {
+            let mut tree = MerkleTree::from_leaves_sharded(7, Hasher::default(), &leaves, shard_bits).unwrap();
+            assert_eq!(tree.get_last_root(), expected.get_last_root(), "{}", shard_bits);
+            assert_eq!(tree.filled_subtrees, expected.filled_subtrees);
+            assert_eq!(tree.index_of(37), Some(36));
+
+            tree.insert(38).unwrap();
+            let mut next = expected.clone();
+            next.insert(38).unwrap();
+            assert_eq!(tree.get_last_root(), next.get_last_root());
+        }
+
+        let empty = MerkleTree::from_leaves_sharded(3, Hasher::default(), &[], 2).unwrap();
+        assert_eq!(empty.get_last_root(), MerkleTree::new(3).get_last_root());
+        assert!(MerkleTree::from_leaves_sharded(3, Hasher::default(), &leaves, 2).is_err());
+        assert!(MerkleTree::from_leaves_sharded(3, Hasher::default(), &[1], 4).is_err());
+    }
+}
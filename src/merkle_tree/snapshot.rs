@@ -0,0 +1,200 @@
+use std::io::{Read, Write};
+
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::hasher::keccak::keccak256;
+use crate::hasher::{Hasher, MerkleHasher};
+use crate::merkle_tree::MerkleTree;
+use crate::utils::{self, Context, ErrorCode, Map, SolanaError};
+
+const MAGIC: [u8; 8] = *b"STMTSNAP";
+pub const SNAPSHOT_VERSION: u16 = 1;
+const HEADER_LEN: usize = 8 + 2 + 16 + 4;
+const CHECKSUM_LEN: usize = 8;
+
+/// Tree state after the header, in borsh. New fields go into a new version.
+#[derive(BorshSerialize, BorshDeserialize)]
+struct SnapshotBody {
+    levels: u8,
+    root_history_size: u8,
+    current_root_index: u8,
+    next_index: u64,
+    strict_leaves: bool,
+    frontier_only: bool,
+    root_pending: bool,
+    filled_subtrees: Vec<u128>,
+    roots: Vec<u128>,
+    leaves: Vec<u128>
+}
+
+/// Identifies the node hash by its output on a fixed pair, so a snapshot is
+/// never restored under different hash parameters.
+fn hasher_id<H: MerkleHasher>(hasher: &H) -> u128 {
+    hasher.hash_pair(1, 2)
+}
+
+fn checksum(bytes: &[u8]) -> [u8; CHECKSUM_LEN] {
+    keccak256(bytes)[..CHECKSUM_LEN].try_into().unwrap()
+}
+
+fn invalid(msg: &'static str) -> SolanaError {
+    utils::err_with_code(ErrorCode::InvalidEncoding, msg)
+}
+
+impl MerkleTree {
+    pub fn import_snapshot(reader: impl Read) -> Result<MerkleTree, SolanaError> {
+        Self::import_snapshot_with(reader, Hasher::default())
+    }
+}
+
+impl<H: MerkleHasher> MerkleTree<H> {
+    /// Writes `STMTSNAP`, a little-endian `u16` format version, the hasher id,
+    /// the `u32` body length, the borsh body and the first 8 bytes of the
+    /// keccak-256 of everything before them.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, fields(depth = self.levels, leaves = self.next_index), err))]
+    pub fn export_snapshot(&self, mut writer: impl Write) -> Result<(), SolanaError> {
+        let body = SnapshotBody {
+            levels: self.levels,
+            root_history_size: self.root_history_size,
+            current_root_index: self.current_root_index,
+            next_index: self.next_index,
+            strict_leaves: self.strict_leaves,
+            frontier_only: self.frontier_only,
+            root_pending: self.root_pending,
+            filled_subtrees: self.filled_subtrees.clone(),
+            roots: self.roots.clone(),
+            leaves: self.leaves.clone()
+        };
+        let body = borsh::to_vec(&body).expect("Writing to a Vec cannot fail");
+        let body_len = u32::try_from(body.len()).map_err(|_| utils::err_with_code(ErrorCode::InvalidParameters, "Tree is too large for a snapshot"))?;
+
+        let mut bytes = Vec::with_capacity(HEADER_LEN + body.len() + CHECKSUM_LEN);
+        bytes.extend_from_slice(&MAGIC);
+        bytes.extend_from_slice(&SNAPSHOT_VERSION.to_le_bytes());
+        bytes.extend_from_slice(&hasher_id(&self.hasher).to_le_bytes());
+        bytes.extend_from_slice(&body_len.to_le_bytes());
+        bytes.extend_from_slice(&body);
+        let checksum = checksum(&bytes);
+        bytes.extend_from_slice(&checksum);
+        writer.write_all(&bytes).context("Writing tree snapshot")
+    }
+
+    /// Restores a tree written by `export_snapshot` for the same hasher.
+    /// Corrupted, truncated or foreign files and unknown versions are
+    /// rejected before any state is built.
+    #[cfg_attr(feature = "tracing", tracing::instrument(skip_all, err))]
+    pub fn import_snapshot_with(mut reader: impl Read, hasher: H) -> Result<MerkleTree<H>, SolanaError> {
+        let mut bytes = vec![0u8; HEADER_LEN];
+        reader.read_exact(&mut bytes).context("Reading tree snapshot header")?;
+        if bytes[..8] != MAGIC {
+            return Err(invalid("Not a tree snapshot"));
+        }
+        let version = u16::from_le_bytes(bytes[8..10].try_into().unwrap());
+        if version != SNAPSHOT_VERSION {
+            return Err(utils::err_with_code(ErrorCode::InvalidEncoding, format!("Unsupported snapshot version {}", version))
+                .with_compared_values(SNAPSHOT_VERSION, version));
+        }
+        if u128::from_le_bytes(bytes[10..26].try_into().unwrap()) != hasher_id(&hasher) {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Snapshot was written with a different hasher"));
+        }
+        let body_len = u32::from_le_bytes(bytes[26..30].try_into().unwrap()) as u64;
+        reader.take(body_len + CHECKSUM_LEN as u64).read_to_end(&mut bytes).context("Reading tree snapshot body")?;
+        if bytes.len() as u64 != HEADER_LEN as u64 + body_len + CHECKSUM_LEN as u64 {
+            return Err(invalid("Tree snapshot is truncated"));
+        }
+        let (content, expected) = bytes.split_at(bytes.len() - CHECKSUM_LEN);
+        if checksum(content) != expected {
+            return Err(utils::err_with_code(ErrorCode::InvalidChecksum, "Tree snapshot checksum does not match"));
+        }
+
+        let body = SnapshotBody::try_from_slice(&content[HEADER_LEN..]).map_err(|_| invalid("Tree snapshot body is malformed"))?;
+        let history_len = body.roots.len();
+        let capacity = 1u128.checked_shl(body.levels as u32).unwrap_or(u128::MAX);
+        if body.levels == 0
+            || body.next_index as u128 > capacity
+            || body.filled_subtrees.len() != body.levels as usize
+            || body.root_history_size == 0
+            || history_len == 0
+            || history_len > body.root_history_size as usize
+            || body.current_root_index as usize >= history_len
+            || (!body.frontier_only && body.leaves.len() as u64 != body.next_index)
+            || (body.frontier_only && !body.leaves.is_empty())
+        {
+            return Err(invalid("Tree snapshot fields are inconsistent"));
+        }
+
+        let mut tree = MerkleTree::with_hasher(body.levels, hasher);
+        if body.frontier_only {
+            tree = tree.with_frontier_only();
+        }
+        tree.filled_subtrees = body.filled_subtrees;
+        tree.roots = body.roots;
+        tree.root_counts = Map::new();
+        if !body.frontier_only {
+            for root in &tree.roots {
+                *tree.root_counts.entry(*root).or_insert(0) += 1;
+            }
+        }
+        tree.current_root_index = body.current_root_index;
+        tree.root_history_size = body.root_history_size;
+        tree.next_index = body.next_index;
+        tree.strict_leaves = body.strict_leaves;
+        tree.root_pending = body.root_pending;
+        for leaf in body.leaves {
+            tree.push_leaf(leaf);
+        }
+        Ok(tree)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::keccak::Keccak256;
+
+    #[test]
+    fn test_snapshot() {
+        let mut tree = MerkleTree::new(6).with_strict_leaves();
+        tree.insert_many((1..=40).chain([7])).unwrap();
+        let mut bytes = Vec::new();
+        tree.export_snapshot(&mut bytes).unwrap();
+        assert_eq!(&bytes[..8], b"STMTSNAP");
+
+        let mut restored = MerkleTree::import_snapshot(bytes.as_slice()).unwrap();
+        assert_eq!(restored.get_last_root(), tree.get_last_root());
+        assert_eq!(restored.index_of(7), Some(6));
+        assert!(restored.is_known_root(tree.proof(0).unwrap().root()));
+        assert!(restored.strict_leaves());
+        tree.insert(41).unwrap();
+        restored.insert(41).unwrap();
+        assert_eq!(restored.get_last_root(), tree.get_last_root());
+        assert_eq!(restored.proof(40).unwrap(), tree.proof(40).unwrap());
+
+        let mut frontier = MerkleTree::new(6).with_frontier_only();
+        frontier.insert_many([1, 2, 3]).unwrap();
+        let mut frontier_bytes = Vec::new();
+        frontier.export_snapshot(&mut frontier_bytes).unwrap();
+        let restored = MerkleTree::import_snapshot(frontier_bytes.as_slice()).unwrap();
+        assert!(restored.frontier_only());
+        assert_eq!(restored.get_last_root(), frontier.get_last_root());
+        let mut overfull = MerkleTree::new(2).with_frontier_only();
+        overfull.next_index = 5;
+        let mut overfull_bytes = Vec::new();
+        overfull.export_snapshot(&mut overfull_bytes).unwrap();
+        assert_eq!(MerkleTree::import_snapshot(overfull_bytes.as_slice()).unwrap_err().code(), ErrorCode::InvalidEncoding);
+
+        let mut corrupted = bytes.clone();
+        corrupted[HEADER_LEN + 20] ^= 1;
+        assert_eq!(MerkleTree::import_snapshot(corrupted.as_slice()).unwrap_err().code(), ErrorCode::InvalidChecksum);
+        let err = MerkleTree::import_snapshot(&bytes[..bytes.len() - 1]).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidEncoding);
+        let mut future = bytes.clone();
+        future[8] = 2;
+        assert_eq!(MerkleTree::import_snapshot(future.as_slice()).unwrap_err().compared_values(), Some("expected 1, got 2"));
+        assert_eq!(MerkleTree::import_snapshot(&b"not a snapshot at all, just text"[..]).unwrap_err().code(), ErrorCode::InvalidEncoding);
+        let err = MerkleTree::import_snapshot_with(bytes.as_slice(), Keccak256).unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidParameters);
+        let err = MerkleTree::import_snapshot(&bytes[..4]).unwrap_err();
+        assert!(err.to_string().contains("Reading tree snapshot header"));
+    }
+}
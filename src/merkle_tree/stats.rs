@@ -0,0 +1,78 @@
+use crate::merkle_tree::MerkleProof;
+
+/// Rough EVM cost of one MiMC sponge node hash in a Solidity verifier, and of
+/// one calldata byte (the non-zero byte price). Planning figures, not a
+/// measurement of any particular contract.
+pub const EVM_GAS_PER_HASH: u64 = 22_000;
+pub const EVM_GAS_PER_CALLDATA_BYTE: u64 = 16;
+/// Rough Solana compute units for one node hash in a BPF program.
+pub const SOLANA_CU_PER_HASH: u64 = 12_000;
+
+/// Wire formats for a `MerkleProof`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ProofFormat {
+    /// `borsh::to_vec(&proof)`: leaf, index, then both path vectors with
+    /// 4-byte length prefixes.
+    Borsh,
+    /// Leaf, 8-byte index, 1-byte depth and the siblings; the path indices
+    /// are the bits of the index.
+    Compact,
+    /// Solidity calldata for `(uint256 leaf, uint256 index, uint256[] siblings)`.
+    Abi
+}
+
+/// Sizes and estimated verification costs for one proof.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct ProofStats {
+    pub depth: usize,
+    pub borsh_size: usize,
+    pub compact_size: usize,
+    pub abi_size: usize,
+    pub evm_gas: u64,
+    pub solana_compute_units: u64
+}
+
+impl MerkleProof {
+    pub fn encoded_size(&self, format: ProofFormat) -> usize {
+        let depth = self.path_elements.len();
+        match format {
+            ProofFormat::Borsh => 16 + 8 + 4 + 16 * depth + 4 + self.path_indices.len(),
+            ProofFormat::Compact => 16 + 8 + 1 + 16 * depth,
+            ProofFormat::Abi => 32 * 3 + 32 + 32 * depth
+        }
+    }
+
+    pub fn stats(&self) -> ProofStats {
+        let depth = self.path_elements.len();
+        let abi_size = self.encoded_size(ProofFormat::Abi);
+        ProofStats {
+            depth,
+            borsh_size: self.encoded_size(ProofFormat::Borsh),
+            compact_size: self.encoded_size(ProofFormat::Compact),
+            abi_size,
+            evm_gas: depth as u64 * EVM_GAS_PER_HASH + abi_size as u64 * EVM_GAS_PER_CALLDATA_BYTE,
+            solana_compute_units: depth as u64 * SOLANA_CU_PER_HASH
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::merkle_tree::MerkleTree;
+
+    #[test]
+    fn test_stats() {
+        let mut tree = MerkleTree::new(20);
+        tree.insert(11).unwrap();
+        let proof = tree.proof(0).unwrap();
+        assert_eq!(proof.encoded_size(ProofFormat::Borsh), borsh::to_vec(&proof).unwrap().len());
+
+        let stats = proof.stats();
+        assert_eq!(stats.depth, 20);
+        assert_eq!(stats.compact_size, 345);
+        assert_eq!(stats.abi_size, 768);
+        assert_eq!(stats.evm_gas, 20 * EVM_GAS_PER_HASH + 768 * EVM_GAS_PER_CALLDATA_BYTE);
+        assert!(stats.compact_size < stats.borsh_size);
+    }
+}
@@ -0,0 +1,218 @@
+use crate::hasher::{Hasher, MerkleHasher};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+pub const SUPPORTED_ARITIES: [usize; 3] = [2, 4, 8];
+
+/// Merkle tree whose nodes have `arity` children, hashed with
+/// `MerkleHasher::hash_children`. Every node on the changed path is rehashed
+/// on insert, so proofs and the root are always current.
+#[derive(Debug, Clone)]
+pub struct WideMerkleTree<H: MerkleHasher = Hasher> {
+    hasher: H,
+    levels: u8,
+    arity: usize,
+    nodes: Vec<Vec<u128>>,
+    zeros: Vec<u128>
+}
+
+/// Inclusion proof for a wide tree. `path_elements[i]` holds the `arity - 1`
+/// siblings at level `i` in order and `path_indices[i]` is the node's position
+/// among its siblings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WideMerkleProof {
+    pub leaf: u128,
+    pub leaf_index: u64,
+    pub path_elements: Vec<Vec<u128>>,
+    pub path_indices: Vec<u8>
+}
+
+impl WideMerkleProof {
+    /// Recomputes the root, or `None` when the levels do not all have
+    /// `arity - 1` siblings for one supported arity or a position is not
+    /// below the arity.
+    pub fn root_with<H: MerkleHasher>(&self, hasher: &H) -> Option<u128> {
+        if self.path_elements.len() != self.path_indices.len() {
+            return None;
+        }
+        let arity = self.path_elements.first().map_or(2, |siblings| siblings.len() + 1);
+        if !SUPPORTED_ARITIES.contains(&arity) {
+            return None;
+        }
+        let mut current_level_hash = self.leaf;
+        for (siblings, index) in self.path_elements.iter().zip(&self.path_indices) {
+            if siblings.len() != arity - 1 || *index as usize >= arity {
+                return None;
+            }
+            let mut children = siblings.clone();
+            children.insert(*index as usize, current_level_hash);
+            current_level_hash = hasher.hash_children(&children);
+        }
+        Some(current_level_hash)
+    }
+
+    pub fn verify_with<H: MerkleHasher>(&self, root: u128, hasher: &H) -> bool {
+        self.root_with(hasher)
+            .is_some_and(|computed| utils::secure_eq(&computed.to_le_bytes(), &root.to_le_bytes()))
+    }
+}
+
+impl WideMerkleTree {
+    pub fn new(levels: u8, arity: usize) -> Result<Self, SolanaError> {
+        Self::with_hasher(levels, arity, Hasher::default())
+    }
+}
+
+impl<H: MerkleHasher> WideMerkleTree<H> {
+    pub fn with_hasher(levels: u8, arity: usize, hasher: H) -> Result<Self, SolanaError> {
+        if !SUPPORTED_ARITIES.contains(&arity) {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree arity must be 2, 4 or 8"));
+        }
+        if levels == 0 {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Tree must have at least one level"));
+        }
+
+        let mut zeros = vec![0];
+        for i in 0..levels as usize {
+            zeros.push(hasher.hash_children(&vec![zeros[i]; arity]));
+        }
+        Ok(WideMerkleTree {
+            hasher,
+            levels,
+            arity,
+            nodes: vec![Vec::new(); levels as usize + 1],
+            zeros
+        })
+    }
+
+    pub fn hasher(&self) -> &H {
+        &self.hasher
+    }
+
+    pub fn arity(&self) -> usize {
+        self.arity
+    }
+
+    pub fn levels(&self) -> u8 {
+        self.levels
+    }
+
+    pub fn capacity(&self) -> u128 {
+        (self.arity as u128).checked_pow(self.levels as u32).unwrap_or(u128::MAX)
+    }
+
+    pub fn leaves(&self) -> &[u128] {
+        &self.nodes[0]
+    }
+
+    pub fn root(&self) -> u128 {
+        self.nodes[self.levels as usize].first().copied().unwrap_or(self.zeros[self.levels as usize])
+    }
+
+    pub fn insert(&mut self, leaf: u128) -> Result<u64, SolanaError> {
+        let index = self.nodes[0].len();
+        if index as u128 >= self.capacity() {
+            return Err(utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Merkle tree is full"));
+        }
+
+        self.nodes[0].push(leaf);
+        let mut current_index = index;
+        for level in 0..self.levels as usize {
+            let parent_index = current_index / self.arity;
+            let parent = self.hasher.hash_children(&self.children(level, parent_index));
+            let parents = &mut self.nodes[level + 1];
+            if parent_index == parents.len() {
+                parents.push(parent);
+            } else {
+                parents[parent_index] = parent;
+            }
+            current_index = parent_index;
+        }
+        Ok(index as u64)
+    }
+
+    pub fn proof(&self, index: u64) -> Result<WideMerkleProof, SolanaError> {
+        let leaf = *self.nodes[0]
+            .get(index as usize)
+            .ok_or_else(|| utils::err_with_code(ErrorCode::LeafIndexOutOfRange, "Leaf index is out of range"))?;
+
+        let mut path_elements = Vec::with_capacity(self.levels as usize);
+        let mut path_indices = Vec::with_capacity(self.levels as usize);
+        let mut current_index = index as usize;
+        for level in 0..self.levels as usize {
+            let position = current_index % self.arity;
+            let mut siblings = self.children(level, current_index / self.arity);
+            siblings.remove(position);
+            path_elements.push(siblings);
+            path_indices.push(position as u8);
+            current_index /= self.arity;
+        }
+
+        Ok(WideMerkleProof { leaf, leaf_index: index, path_elements, path_indices })
+    }
+
+    // The children of `parent_index`, padded with the empty subtree root.
+    fn children(&self, level: usize, parent_index: usize) -> Vec<u128> {
+        (0..self.arity)
+            .map(|i| self.nodes[level].get(parent_index * self.arity + i).copied().unwrap_or(self.zeros[level]))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hasher::poseidon2::Poseidon2;
+
+    #[test]
+    fn test_wide_tree() {
+        for arity in [4, 8] {
+            let mut tree = WideMerkleTree::new(3, arity).unwrap();
+            assert_eq!(tree.root(), tree.zeros[3]);
+            for leaf in 1..=10 {
+                tree.insert(leaf).unwrap();
+            }
+            for index in 0..10 {
+                let proof = tree.proof(index).unwrap();
+                assert_eq!(proof.path_elements[0].len(), arity - 1);
+                assert!(proof.verify_with(tree.root(), tree.hasher()));
+            }
+            let proof = tree.proof(5).unwrap();
+            let mut moved = proof.clone();
+            moved.path_indices[1] = arity as u8;
+            assert_eq!(moved.root_with(tree.hasher()), None);
+            let mut short = proof.clone();
+            short.path_elements[2].pop();
+            assert_eq!(short.root_with(tree.hasher()), None);
+            let mut bare = proof;
+            bare.path_elements.iter_mut().for_each(Vec::clear);
+            bare.path_indices.iter_mut().for_each(|index| *index = 0);
+            assert_eq!(bare.root_with(tree.hasher()), None);
+            assert!(tree.proof(10).is_err());
+        }
+
+        let mut tree = WideMerkleTree::new(1, 4).unwrap();
+        for leaf in [1, 2] {
+            tree.insert(leaf).unwrap();
+        }
+        assert_eq!(tree.root(), Hasher::default().hash_many(&[1, 2, 0, 0]));
+        tree.insert(3).unwrap();
+        tree.insert(4).unwrap();
+        assert_eq!(tree.insert(5).err().unwrap().code(), ErrorCode::LeafIndexOutOfRange);
+        assert!(WideMerkleTree::new(3, 3).is_err());
+    }
+
+    #[test]
+    fn test_default_hash_children() {
+        let poseidon2 = Poseidon2::default();
+        let mut tree = WideMerkleTree::with_hasher(2, 4, poseidon2.clone()).unwrap();
+        tree.insert(7).unwrap();
+        assert!(tree.proof(0).unwrap().verify_with(tree.root(), &poseidon2));
+        assert_eq!(
+            poseidon2.hash_children(&[1, 2, 3]),
+            poseidon2.hash_pair(poseidon2.hash_pair(1, 2), 3)
+        );
+        assert_eq!(poseidon2.hash_children(&[5]), 5);
+        assert_eq!(poseidon2.hash_children(&[]), poseidon2.zero(0));
+    }
+}
@@ -0,0 +1,139 @@
+use napi::bindgen_prelude::*;
+use napi_derive::napi;
+
+use crate::merkle_tree;
+use crate::note::Note;
+use crate::utils::{parse_field, SolanaError};
+
+fn to_napi(err: SolanaError) -> Error {
+    Error::new(Status::InvalidArg, err.to_string())
+}
+
+fn index_from_js(index: i64) -> Result<u64> {
+    u64::try_from(index).map_err(|_| Error::new(Status::InvalidArg, format!("{} is not a leaf index", index)))
+}
+
+fn side_from_js(side: u32) -> Result<u8> {
+    match side {
+        0 | 1 => Ok(side as u8),
+        _ => Err(Error::new(Status::InvalidArg, format!("{} is not a path side, expected 0 or 1", side)))
+    }
+}
+
+/// Proof as a plain JS object, field values as decimal strings.
+#[napi(object)]
+pub struct JsMerkleProof {
+    pub leaf: String,
+    pub leaf_index: i64,
+    pub path_elements: Vec<String>,
+    pub path_indices: Vec<u32>
+}
+
+impl JsMerkleProof {
+    fn from_proof(proof: &merkle_tree::MerkleProof) -> Self {
+        JsMerkleProof {
+            leaf: proof.leaf.to_string(),
+            leaf_index: proof.leaf_index as i64,
+            path_elements: proof.path_elements.iter().map(u128::to_string).collect(),
+            path_indices: proof.path_indices.iter().map(|side| *side as u32).collect()
+        }
+    }
+
+    fn to_proof(&self) -> Result<merkle_tree::MerkleProof> {
+        Ok(merkle_tree::MerkleProof {
+            leaf: parse_field(&self.leaf).map_err(to_napi)?,
+            leaf_index: index_from_js(self.leaf_index)?,
+            path_elements: self.path_elements.iter().map(|value| parse_field(value).map_err(to_napi)).collect::<Result<_>>()?,
+            path_indices: self.path_indices.iter().map(|side| side_from_js(*side)).collect::<Result<_>>()?
+        })
+    }
+}
+
+#[napi(js_name = "MerkleTree")]
+pub struct NodeMerkleTree {
+    inner: merkle_tree::MerkleTree
+}
+
+#[napi]
+impl NodeMerkleTree {
+    #[napi(constructor)]
+    pub fn new(levels: u32) -> Result<Self> {
+        let levels = u8::try_from(levels).ok().filter(|levels| *levels > 0)
+            .ok_or_else(|| Error::new(Status::InvalidArg, format!("{} is not a tree depth", levels)))?;
+        Ok(NodeMerkleTree { inner: merkle_tree::MerkleTree::new(levels) })
+    }
+
+    #[napi]
+    pub fn insert(&mut self, leaf: String) -> Result<i64> {
+        let index = self.inner.insert(parse_field(&leaf).map_err(to_napi)?).map_err(to_napi)?;
+        Ok(index as i64)
+    }
+
+    #[napi]
+    pub fn root(&self) -> String {
+        self.inner.get_last_root().to_string()
+    }
+
+    #[napi]
+    pub fn is_known_root(&self, root: String) -> Result<bool> {
+        Ok(self.inner.is_known_root(parse_field(&root).map_err(to_napi)?))
+    }
+
+    #[napi]
+    pub fn prove(&self, index: i64) -> Result<JsMerkleProof> {
+        let proof = self.inner.proof(index_from_js(index)?).map_err(to_napi)?;
+        Ok(JsMerkleProof::from_proof(&proof))
+    }
+}
+
+#[napi]
+pub fn verify_proof(proof: JsMerkleProof, root: String) -> Result<bool> {
+    Ok(proof.to_proof()?.verify(parse_field(&root).map_err(to_napi)?))
+}
+
+#[napi(object)]
+pub struct JsNote {
+    pub nullifier: String,
+    pub secret: String,
+    pub commitment: String,
+    pub nullifier_hash: String
+}
+
+impl From<&Note> for JsNote {
+    fn from(note: &Note) -> Self {
+        JsNote {
+            nullifier: note.nullifier().to_string(),
+            secret: note.secret().to_string(),
+            commitment: note.commitment().to_string(),
+            nullifier_hash: note.nullifier_hash().to_string()
+        }
+    }
+}
+
+/// Fresh note from the OS random number generator.
+#[napi]
+pub fn generate_note() -> JsNote {
+    JsNote::from(&Note::random())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proof_object() {
+        let mut tree = NodeMerkleTree::new(4).unwrap();
+        assert_eq!(tree.insert("11".to_string()).unwrap(), 0);
+        assert_eq!(tree.insert("0x16".to_string()).unwrap(), 1);
+        let proof = tree.prove(1).unwrap();
+        assert_eq!(proof.leaf, "22");
+        let inner = tree.inner.proof(1).unwrap();
+        let wide_side = JsMerkleProof { path_indices: vec![257; 4], ..JsMerkleProof::from_proof(&inner) };
+        assert!(verify_proof(wide_side, tree.root()).is_err());
+        let negative = JsMerkleProof { leaf_index: -1, ..JsMerkleProof::from_proof(&inner) };
+        assert!(verify_proof(negative, tree.root()).is_err());
+        assert!(verify_proof(proof, tree.root()).unwrap());
+        assert!(tree.prove(-1).is_err());
+        assert!(NodeMerkleTree::new(0).is_err());
+    }
+}
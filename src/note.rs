@@ -0,0 +1,282 @@
+pub mod hd;
+#[cfg(feature = "mnemonic")]
+pub mod mnemonic;
+
+use std::fmt::Display;
+use std::str::FromStr;
+
+use crate::constants::in_field;
+use crate::hasher::{Hasher, IntoField};
+use crate::utils::{self, ErrorCode, SolanaError};
+
+pub const NOTE_PREFIX: &str = "tornado";
+/// A 31-byte nullifier followed by a 31-byte secret.
+pub const NOTE_PREIMAGE_LEN: usize = 62;
+
+/// Deposit secrets. The pool stores `commitment()` as a leaf and a withdrawal
+/// reveals `nullifier_hash()`.
+#[derive(Clone, Eq)]
+#[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop))]
+pub struct Note {
+    nullifier: u128,
+    secret: u128
+}
+
+impl Note {
+    pub fn new(nullifier: u128, secret: u128) -> Result<Self, SolanaError> {
+        if !in_field(nullifier) || !in_field(secret) {
+            return Err(utils::err("Note nullifier and secret must be below the field size"));
+        }
+        Ok(Note { nullifier, secret })
+    }
+
+    #[cfg(feature = "rng")]
+    pub fn random() -> Self {
+        Self::random_with(&mut crate::rng::OsRng)
+    }
+
+    #[cfg(feature = "rng")]
+    pub fn random_with<R: rand_core::RngCore + rand_core::CryptoRng>(rng: &mut R) -> Self {
+        Note {
+            nullifier: crate::rng::random_field_element(rng),
+            secret: crate::rng::random_field_element(rng)
+        }
+    }
+
+    pub fn nullifier(&self) -> u128 {
+        self.nullifier
+    }
+
+    pub fn secret(&self) -> u128 {
+        self.secret
+    }
+
+    pub fn commitment(&self) -> u128 {
+        Hasher::mimc_sponge(self.nullifier, self.secret, 0)
+    }
+
+    pub fn nullifier_hash(&self) -> u128 {
+        Hasher::mimc_sponge(self.nullifier, 0, 0)
+    }
+
+    /// Little-endian nullifier followed by little-endian secret.
+    pub fn to_bytes(&self) -> [u8; 32] {
+        let mut bytes = [0u8; 32];
+        bytes[..16].copy_from_slice(&self.nullifier.to_le_bytes());
+        bytes[16..].copy_from_slice(&self.secret.to_le_bytes());
+        bytes
+    }
+
+    pub fn from_bytes(bytes: &[u8; 32]) -> Result<Self, SolanaError> {
+        let mut nullifier = [0u8; 16];
+        let mut secret = [0u8; 16];
+        nullifier.copy_from_slice(&bytes[..16]);
+        secret.copy_from_slice(&bytes[16..]);
+        Note::new(u128::from_le_bytes(nullifier), u128::from_le_bytes(secret))
+    }
+}
+
+/// Deposit note in the `tornado-<currency>-<amount>-<netId>-0x<preimage>` format.
+///
+/// Only the canonical spelling is accepted (lowercase currency and hex, no
+/// leading zeros in numbers), so `s.parse::<NoteString>()?.to_string() == s`
+/// holds for every string that parses.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NoteString {
+    currency: String,
+    amount: String,
+    net_id: u32,
+    preimage: Vec<u8>
+}
+
+impl NoteString {
+    pub fn new(currency: &str, amount: &str, net_id: u32, preimage: &[u8]) -> Result<Self, SolanaError> {
+        if currency.is_empty() || !currency.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit()) {
+            return Err(utils::err("Note currency must be lowercase alphanumeric"));
+        }
+        if !Self::is_canonical_amount(amount) {
+            return Err(utils::err("Note amount must be a canonical decimal number"));
+        }
+        if net_id == 0 {
+            return Err(utils::err("Note netId must be non-zero"));
+        }
+        if preimage.len() != NOTE_PREIMAGE_LEN {
+            return Err(utils::err_with_code(ErrorCode::InvalidEncoding, "Note preimage must be 62 bytes")
+                .with_compared_values(NOTE_PREIMAGE_LEN, preimage.len()));
+        }
+
+        Ok(NoteString {
+            currency: currency.to_string(),
+            amount: amount.to_string(),
+            net_id,
+            preimage: preimage.to_vec()
+        })
+    }
+
+    pub fn currency(&self) -> &str {
+        &self.currency
+    }
+
+    pub fn amount(&self) -> &str {
+        &self.amount
+    }
+
+    pub fn net_id(&self) -> u32 {
+        self.net_id
+    }
+
+    pub fn preimage(&self) -> &[u8] {
+        &self.preimage
+    }
+
+    fn is_canonical_amount(amount: &str) -> bool {
+        let (integer, fraction) = match amount.split_once('.') {
+            Some((integer, fraction)) => (integer, Some(fraction)),
+            None => (amount, None)
+        };
+
+        if integer.is_empty() || !integer.chars().all(|c| c.is_ascii_digit()) {
+            return false;
+        }
+        if integer.len() > 1 && integer.starts_with('0') {
+            return false;
+        }
+
+        match fraction {
+            Some(fraction) => !fraction.is_empty()
+                && fraction.chars().all(|c| c.is_ascii_digit())
+                && !fraction.ends_with('0'),
+            None => true
+        }
+    }
+}
+
+/// A note enters the tree as its commitment.
+impl IntoField for Note {
+    fn to_field(&self) -> u128 {
+        self.commitment()
+    }
+}
+
+impl PartialEq for Note {
+    fn eq(&self, other: &Self) -> bool {
+        utils::secure_eq(&self.to_bytes(), &other.to_bytes())
+    }
+}
+
+/// Redacted, so secrets never end up in logs.
+impl std::fmt::Debug for Note {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.debug_struct("Note").finish_non_exhaustive()
+    }
+}
+
+impl Display for NoteString {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{}-{}-{}-{}-0x{}",
+            NOTE_PREFIX,
+            self.currency,
+            self.amount,
+            self.net_id,
+            hex::encode(&self.preimage)
+        )
+    }
+}
+
+impl FromStr for NoteString {
+    type Err = SolanaError;
+
+    fn from_str(s: &str) -> std::result::Result<Self, SolanaError> {
+        let parts: Vec<&str> = s.split('-').collect();
+        if parts.len() != 5 {
+            return Err(utils::err("Note must have exactly five '-' separated parts"));
+        }
+        if parts[0] != NOTE_PREFIX {
+            return Err(utils::err("Note must start with the 'tornado' prefix"));
+        }
+
+        let net_id = parts[3];
+        if net_id.is_empty() || net_id.starts_with('0') || !net_id.chars().all(|c| c.is_ascii_digit()) {
+            return Err(utils::err("Note netId must be a canonical decimal number"));
+        }
+        let net_id: u32 = net_id.parse().map_err(|_| utils::err("Note netId is out of range"))?;
+
+        let preimage = parts[4]
+            .strip_prefix("0x")
+            .ok_or_else(|| utils::err("Note preimage must start with 0x"))?;
+        if preimage.chars().any(|c| c.is_ascii_uppercase()) {
+            return Err(utils::err("Note preimage must be lowercase hex"));
+        }
+        let preimage = hex::decode(preimage).map_err(|_| utils::err("Note preimage is not valid hex"))?;
+
+        NoteString::new(parts[1], parts[2], net_id, &preimage)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::FIELD_SIZE;
+
+    fn sample() -> String {
+        format!("tornado-eth-0.1-1-0x{}", "ab".repeat(62))
+    }
+
+    #[test]
+    fn test_note_bytes() {
+        let note = Note::new(1, 2).unwrap();
+        assert_eq!(Note::from_bytes(&note.to_bytes()).unwrap(), note);
+        assert_ne!(note.commitment(), note.nullifier_hash());
+        assert!(Note::new(FIELD_SIZE, 2).is_err());
+        assert!(Note::from_bytes(&[0xff; 32]).is_err());
+        assert_eq!(format!("{:?}", note), "Note { .. }");
+    }
+
+    #[cfg(feature = "zeroize")]
+    #[test]
+    fn test_zeroize() {
+        use zeroize::Zeroize;
+
+        let mut note = Note::new(1, 2).unwrap();
+        note.zeroize();
+        assert_eq!(note, Note::new(0, 0).unwrap());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let note: NoteString = sample().parse().unwrap();
+        assert_eq!(note.currency(), "eth");
+        assert_eq!(note.amount(), "0.1");
+        assert_eq!(note.net_id(), 1);
+        assert_eq!(note.preimage(), vec![0xab; 62].as_slice());
+        assert_eq!(note.to_string(), sample());
+    }
+
+    #[test]
+    fn test_rejects_non_canonical() {
+        let preimage = "ab".repeat(62);
+        for note in [
+            format!("tornado-ETH-0.1-1-0x{}", preimage),
+            format!("tornado-eth-0.10-1-0x{}", preimage),
+            format!("tornado-eth-01-1-0x{}", preimage),
+            format!("tornado-eth-.1-1-0x{}", preimage),
+            format!("tornado-eth-0.1-01-0x{}", preimage),
+            format!("tornado-eth-0.1-0-0x{}", preimage),
+            format!("tornado-eth-0.1-1-0x{}", preimage.to_uppercase()),
+            format!("tornado-eth-0.1-1-{}", preimage),
+            format!("tornado-eth-0.1-1-0x{}a", preimage),
+            format!("tornado-eth-0.1-1-0x{}-", preimage),
+            format!("tornado-eth-0.1-1-0x{}ab", preimage),
+            format!("tornado-eth-0.1-1-0x{}", &preimage[2..]),
+            format!("tornadocash-eth-0.1-1-0x{}", preimage),
+            "tornado-eth-0.1-1-0x".to_string()
+        ] {
+            assert!(note.parse::<NoteString>().is_err(), "{}", note);
+        }
+        let err = "tornado-eth-0.1-1-0xab".parse::<NoteString>().unwrap_err();
+        assert_eq!(err.code(), ErrorCode::InvalidEncoding);
+        assert_eq!(err.compared_values(), Some("expected 62, got 1"));
+    }
+}
@@ -0,0 +1,79 @@
+use hkdf::Hkdf;
+use primitive_types::U256;
+use sha2::Sha256;
+
+use crate::constants::FIELD_SIZE;
+use crate::note::Note;
+use crate::utils::{self, SolanaError};
+
+pub const HD_SALT: &[u8] = b"stealth-lib/note";
+pub const MIN_SEED_LENGTH: usize = 16;
+
+/// Derives the note at `path` from a master seed.
+///
+/// Each path index is one HKDF-SHA256 step, `key = HKDF(salt, key, "child" || index_be)`,
+/// starting from the seed. The nullifier and secret are 32-byte expansions of
+/// the final key reduced into the field, which keeps the bias below 2^-128.
+pub fn derive_note(seed: &[u8], path: &[u32]) -> Result<Note, SolanaError> {
+    if seed.len() < MIN_SEED_LENGTH {
+        return Err(utils::err("HD seed must be at least 16 bytes"));
+    }
+
+    let mut key = [0u8; 32];
+    key.copy_from_slice(&expand(seed, b"master"));
+    for index in path {
+        let mut info = b"child".to_vec();
+        info.extend_from_slice(&index.to_be_bytes());
+        key = expand(&key, &info);
+    }
+
+    Note::new(to_field(&expand(&key, b"nullifier")), to_field(&expand(&key, b"secret")))
+}
+
+/// Derives the notes at `parent/0 .. parent/count-1`, e.g. every deposit of one account.
+pub fn derive_notes(seed: &[u8], parent: &[u32], count: u32) -> Result<Vec<Note>, SolanaError> {
+    let mut path = parent.to_vec();
+    path.push(0);
+    (0..count)
+        .map(|index| {
+            *path.last_mut().unwrap() = index;
+            derive_note(seed, &path)
+        })
+        .collect()
+}
+
+fn expand(key: &[u8], info: &[u8]) -> [u8; 32] {
+    let mut okm = [0u8; 32];
+    Hkdf::<Sha256>::new(Some(HD_SALT), key)
+        .expand(info, &mut okm)
+        .expect("32 bytes is a valid HKDF-SHA256 output length");
+    okm
+}
+
+fn to_field(bytes: &[u8; 32]) -> u128 {
+    (U256::from_big_endian(bytes) % U256::from(FIELD_SIZE)).as_u128()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_derivation() {
+        let seed = [7u8; 32];
+        let note = derive_note(&seed, &[0, 1]).unwrap();
+        assert_eq!(note.nullifier(), 56357772659712812737791814698175744863);
+        assert_eq!(note.secret(), 234738219276462640895765412433998442945);
+        assert_eq!(derive_note(&seed, &[0, 1]).unwrap(), note);
+        assert_ne!(derive_note(&seed, &[1, 0]).unwrap(), note);
+        assert_ne!(derive_note(&seed, &[0]).unwrap(), note);
+        assert_ne!(derive_note(&[8u8; 32], &[0, 1]).unwrap(), note);
+
+        let notes = derive_notes(&seed, &[0], 3).unwrap();
+        assert_eq!(notes.len(), 3);
+        assert_eq!(notes[1], note);
+        assert_ne!(notes[0], notes[2]);
+
+        assert!(derive_note(&[7u8; 15], &[0]).is_err());
+    }
+}
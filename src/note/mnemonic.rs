@@ -0,0 +1,51 @@
+use bip39::{Language, Mnemonic};
+
+use crate::note::Note;
+use crate::utils::{self, SolanaError};
+
+/// Number of words in a note phrase: 256 bits of entropy plus an 8-bit checksum.
+pub const WORD_COUNT: usize = 24;
+
+/// Encodes `Note::to_bytes()` as an English BIP-39 phrase.
+pub fn to_mnemonic(note: &Note) -> String {
+    Mnemonic::from_entropy_in(Language::English, &note.to_bytes())
+        .expect("32 bytes is a valid BIP-39 entropy length")
+        .to_string()
+}
+
+/// Decodes a phrase produced by `to_mnemonic`, checking the word list and checksum.
+pub fn from_mnemonic(phrase: &str) -> Result<Note, SolanaError> {
+    let mnemonic = Mnemonic::parse_in_normalized(Language::English, phrase)
+        .map_err(|e| utils::err(format!("Invalid note mnemonic: {}", e)))?;
+    if mnemonic.word_count() != WORD_COUNT {
+        return Err(utils::err("Note mnemonic must have 24 words"));
+    }
+
+    let mut bytes = [0u8; 32];
+    bytes.copy_from_slice(&mnemonic.to_entropy());
+    Note::from_bytes(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let zero = Note::new(0, 0).unwrap();
+        let phrase = format!("{} art", "abandon ".repeat(23).trim_end());
+        assert_eq!(to_mnemonic(&zero), phrase);
+        assert_eq!(from_mnemonic(&phrase).unwrap(), zero);
+
+        let note = Note::new(123456789, 987654321).unwrap();
+        assert_eq!(from_mnemonic(&to_mnemonic(&note)).unwrap(), note);
+    }
+
+    #[test]
+    fn test_rejects_invalid_phrases() {
+        let bad_checksum = "abandon ".repeat(24);
+        assert!(from_mnemonic(bad_checksum.trim_end()).is_err());
+        assert!(from_mnemonic("abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon abandon about").is_err());
+        assert!(from_mnemonic("not a real phrase").is_err());
+    }
+}
@@ -0,0 +1,120 @@
+use borsh::{BorshDeserialize, BorshSerialize};
+
+use crate::merkle_tree::{MerkleProof, MerkleTree};
+use crate::utils::{self, ErrorCode, Set, SolanaError};
+
+/// Spent-nullifier tracking kept next to the commitment tree.
+#[derive(Debug, Clone, Default, PartialEq, Eq, BorshSerialize, BorshDeserialize)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct NullifierSet {
+    nullifiers: Set<u128>
+}
+
+impl NullifierSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, nullifier_hash: u128) -> Result<(), SolanaError> {
+        if !self.nullifiers.insert(nullifier_hash) {
+            return Err(utils::err_with_code(ErrorCode::NullifierAlreadySpent, "The note has been already spent"));
+        }
+        Ok(())
+    }
+
+    pub fn contains(&self, nullifier_hash: u128) -> bool {
+        self.nullifiers.contains(&nullifier_hash)
+    }
+
+    pub fn len(&self) -> usize {
+        self.nullifiers.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.nullifiers.is_empty()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &u128> {
+        self.nullifiers.iter()
+    }
+
+    pub fn to_sorted_vec(&self) -> Vec<u128> {
+        let mut nullifiers: Vec<u128> = self.nullifiers.iter().copied().collect();
+        nullifiers.sort_unstable();
+        nullifiers
+    }
+
+    /// Tree over `to_sorted_vec` with the smallest depth (at least 1) that
+    /// holds every nullifier, so two holders of the same set agree on the root.
+    /// Rebuilt on every call.
+    pub fn merkle_tree(&self) -> MerkleTree {
+        let depth = (usize::BITS - self.len().saturating_sub(1).leading_zeros()).max(1) as u8;
+        let mut tree = MerkleTree::new(depth);
+        tree.insert_many(self.to_sorted_vec()).expect("Depth holds every nullifier");
+        tree
+    }
+
+    pub fn root(&self) -> u128 {
+        self.merkle_tree().get_last_root()
+    }
+
+    /// Inclusion proof of a spent nullifier against `root`.
+    pub fn proof(&self, nullifier_hash: u128) -> Option<MerkleProof> {
+        let index = self.to_sorted_vec().binary_search(&nullifier_hash).ok()?;
+        self.merkle_tree().proof(index as u64).ok()
+    }
+}
+
+impl FromIterator<u128> for NullifierSet {
+    fn from_iter<I: IntoIterator<Item = u128>>(iter: I) -> Self {
+        NullifierSet {
+            nullifiers: iter.into_iter().collect()
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_double_insert() {
+        let mut nullifiers = NullifierSet::new();
+        assert!(nullifiers.insert(123).is_ok());
+        assert!(nullifiers.contains(123));
+        assert_eq!(nullifiers.insert(123).unwrap_err().code(), ErrorCode::NullifierAlreadySpent);
+        assert_eq!(nullifiers.len(), 1);
+    }
+
+    #[test]
+    fn test_borsh_round_trip() {
+        let nullifiers: NullifierSet = [3, 1, 2].into_iter().collect();
+        let bytes = borsh::to_vec(&nullifiers).unwrap();
+        let decoded = NullifierSet::try_from_slice(&bytes).unwrap();
+        assert_eq!(decoded, nullifiers);
+        assert_eq!(decoded.to_sorted_vec(), vec![1, 2, 3]);
+    }
+
+    #[test]
+    fn test_merkle_root() {
+        let mut nullifiers: NullifierSet = [30, 10, 20].into_iter().collect();
+        let reordered: NullifierSet = [20, 30, 10].into_iter().collect();
+        assert_eq!(nullifiers.root(), reordered.root());
+        assert_eq!(nullifiers.merkle_tree().levels(), 2);
+
+        let mut expected = MerkleTree::new(2);
+        expected.insert_many([10, 20, 30]).unwrap();
+        assert_eq!(nullifiers.root(), expected.get_last_root());
+
+        let proof = nullifiers.proof(20).unwrap();
+        assert_eq!(proof.leaf_index, 1);
+        assert!(proof.verify(nullifiers.root()));
+        assert!(nullifiers.proof(40).is_none());
+
+        let before = nullifiers.root();
+        nullifiers.insert(40).unwrap();
+        assert_ne!(nullifiers.root(), before);
+        assert!(nullifiers.proof(40).unwrap().verify(nullifiers.root()));
+        assert_eq!(NullifierSet::new().root(), MerkleTree::new(1).get_last_root());
+    }
+}
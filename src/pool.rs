@@ -0,0 +1,272 @@
+use crate::{
+    merkle_tree::MerkleTree,
+    nullifier_set::NullifierSet,
+    root_policy::RootPolicy,
+    utils::{self, ErrorCode, Set, SolanaError}
+};
+
+/// Public inputs of a withdrawal together with the serialized SNARK proof.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct WithdrawProof {
+    pub root: u128,
+    pub nullifier_hash: u128,
+    pub recipient: [u8; 32],
+    pub relayer: [u8; 32],
+    pub fee: u128,
+    pub refund: u128,
+    pub proof: Vec<u8>
+}
+
+/// Everything needed to restore a `Pool`: the deposited commitments in leaf
+/// order, the spent nullifiers and the root rules. The verifying key is not
+/// included and must be set again.
+#[derive(Debug, Clone, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct PoolState {
+    pub levels: u8,
+    pub commitments: Vec<u128>,
+    pub nullifiers: NullifierSet,
+    pub root_policy: RootPolicy
+}
+
+/// Mixer state: the commitment tree, spent nullifiers and the root acceptance rules.
+#[derive(Debug, Clone)]
+pub struct Pool {
+    tree: MerkleTree,
+    commitments: Set<u128>,
+    nullifiers: NullifierSet,
+    root_policy: RootPolicy,
+    #[cfg(feature = "groth16")]
+    verifying_key: Option<ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254>>
+}
+
+impl Pool {
+    pub fn new(levels: u8) -> Self {
+        Pool {
+            tree: MerkleTree::new(levels),
+            commitments: Set::new(),
+            nullifiers: NullifierSet::new(),
+            root_policy: RootPolicy::default(),
+            #[cfg(feature = "groth16")]
+            verifying_key: None
+        }
+    }
+
+    /// Replays the deposits, so the tree and its root history match the
+    /// pool the state was taken from.
+    pub fn from_state(state: PoolState) -> Result<Self, SolanaError> {
+        if state.levels == 0 {
+            return Err(utils::err_with_code(ErrorCode::InvalidParameters, "Pool state has no tree levels"));
+        }
+        let mut pool = Pool::new(state.levels).with_root_policy(state.root_policy);
+        for commitment in state.commitments {
+            pool.deposit(commitment)?;
+        }
+        pool.nullifiers = state.nullifiers;
+        Ok(pool)
+    }
+
+    pub fn state(&self) -> PoolState {
+        PoolState {
+            levels: self.tree.levels(),
+            commitments: self.tree.leaves().to_vec(),
+            nullifiers: self.nullifiers.clone(),
+            root_policy: self.root_policy.clone()
+        }
+    }
+
+    pub fn with_root_policy(mut self, root_policy: RootPolicy) -> Self {
+        self.root_policy = root_policy;
+        self
+    }
+
+    #[cfg(feature = "groth16")]
+    pub fn with_verifying_key(mut self, verifying_key: &ark_groth16::VerifyingKey<ark_bn254::Bn254>) -> Self {
+        self.verifying_key = Some(ark_groth16::prepare_verifying_key(verifying_key));
+        self
+    }
+
+    pub fn deposit(&mut self, commitment: u128) -> Result<u64, SolanaError> {
+        if self.commitments.contains(&commitment) {
+            return Err(utils::err_with_code(
+                ErrorCode::CommitmentAlreadySubmitted,
+                "The commitment has been submitted"
+            ));
+        }
+
+        let leaf_index = self.tree.insert(commitment)?;
+        self.commitments.insert(commitment);
+        Ok(leaf_index)
+    }
+
+    pub fn withdraw(&mut self, nullifier_hash: u128, root: u128) -> Result<(), SolanaError> {
+        self.check_withdrawal(nullifier_hash, root)?;
+        self.nullifiers.insert(nullifier_hash)
+    }
+
+    /// Runs every check `withdraw` would, plus the SNARK check when the
+    /// `groth16` feature is enabled. With that feature a pool without a
+    /// verifying key rejects every withdrawal.
+    pub fn verify_withdrawal(&self, proof: &WithdrawProof) -> Result<(), SolanaError> {
+        self.check_withdrawal(proof.nullifier_hash, proof.root)?;
+
+        #[cfg(feature = "groth16")]
+        {
+            let verifying_key = self.verifying_key.as_ref().ok_or_else(|| {
+                utils::err_with_code(ErrorCode::InvalidParameters, "No verifying key set for withdraw proofs")
+            })?;
+            Self::verify_snark(verifying_key, proof)?;
+        }
+
+        Ok(())
+    }
+
+    fn check_withdrawal(&self, nullifier_hash: u128, root: u128) -> Result<(), SolanaError> {
+        if self.nullifiers.contains(nullifier_hash) {
+            return Err(utils::err_with_code(
+                ErrorCode::NullifierAlreadySpent,
+                "The note has been already spent"
+            ));
+        }
+        if !self.root_policy.accepts(&self.tree, root) {
+            return Err(utils::err_with_code(ErrorCode::UnknownRoot, "Cannot find your merkle root"));
+        }
+        Ok(())
+    }
+
+    #[cfg(feature = "groth16")]
+    fn verify_snark(
+        verifying_key: &ark_groth16::PreparedVerifyingKey<ark_bn254::Bn254>,
+        proof: &WithdrawProof
+    ) -> Result<(), SolanaError> {
+        use ark_bn254::Fr;
+        use ark_ff::PrimeField;
+        use ark_serialize::CanonicalDeserialize;
+
+        let invalid_proof = || utils::err_with_code(ErrorCode::InvalidProof, "Invalid withdraw proof");
+
+        let snark_proof = ark_groth16::Proof::<ark_bn254::Bn254>::deserialize_compressed(proof.proof.as_slice())
+            .map_err(|_| invalid_proof())?;
+        let public_inputs = [
+            Fr::from(proof.root),
+            Fr::from(proof.nullifier_hash),
+            Fr::from_be_bytes_mod_order(&proof.recipient),
+            Fr::from_be_bytes_mod_order(&proof.relayer),
+            Fr::from(proof.fee),
+            Fr::from(proof.refund)
+        ];
+
+        match ark_groth16::Groth16::<ark_bn254::Bn254>::verify_proof(verifying_key, &snark_proof, &public_inputs) {
+            Ok(true) => Ok(()),
+            _ => Err(invalid_proof())
+        }
+    }
+
+    pub fn is_spent(&self, nullifier_hash: u128) -> bool {
+        self.nullifiers.contains(nullifier_hash)
+    }
+
+    pub fn tree(&self) -> &MerkleTree {
+        &self.tree
+    }
+
+    pub fn nullifiers(&self) -> &NullifierSet {
+        &self.nullifiers
+    }
+
+    pub fn root_policy(&self) -> &RootPolicy {
+        &self.root_policy
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const MERKLE_TREE_HEIGHT: u8 = 4;
+
+    #[test]
+    #[cfg(feature = "serde")]
+    fn test_bincode() {
+        let withdraw = WithdrawProof { root: 1, nullifier_hash: 2, recipient: [3; 32], relayer: [4; 32], fee: 5, refund: 6, proof: vec![7] };
+        let bytes = bincode::serialize(&withdraw).unwrap();
+        assert_eq!(bytes.len(), 16 + 16 + 32 + 32 + 16 + 16 + 8 + 1);
+        assert_eq!(bytes[32..64], [3; 32]);
+        assert_eq!(bytes[96..112], 5u128.to_le_bytes());
+        assert_eq!(bytes[128..], [1, 0, 0, 0, 0, 0, 0, 0, 7]);
+        assert_eq!(bincode::deserialize::<WithdrawProof>(&bytes).unwrap(), withdraw);
+    }
+
+    #[test]
+    fn test_deposit_and_withdraw() {
+        let mut pool = Pool::new(MERKLE_TREE_HEIGHT);
+        assert_eq!(pool.deposit(123).unwrap(), 0);
+        let root = pool.tree().get_last_root();
+
+        assert!(pool.withdraw(456, root).is_ok());
+        assert!(pool.is_spent(456));
+        assert_eq!(pool.withdraw(456, root).unwrap_err().code(), ErrorCode::NullifierAlreadySpent);
+    }
+
+    #[test]
+    fn test_errors() {
+        let mut pool = Pool::new(MERKLE_TREE_HEIGHT);
+        pool.deposit(123).unwrap();
+
+        assert_eq!(pool.deposit(123).unwrap_err().code(), ErrorCode::CommitmentAlreadySubmitted);
+        assert_eq!(pool.withdraw(456, 789).unwrap_err().code(), ErrorCode::UnknownRoot);
+        assert!(!pool.is_spent(456));
+
+        let flat = PoolState { levels: 0, ..pool.state() };
+        assert_eq!(Pool::from_state(flat).unwrap_err().code(), ErrorCode::InvalidParameters);
+        let overfull = PoolState { levels: 1, commitments: vec![1, 2, 3], ..pool.state() };
+        assert_eq!(Pool::from_state(overfull).unwrap_err().code(), ErrorCode::LeafIndexOutOfRange);
+    }
+
+    #[test]
+    fn test_verify_withdrawal() {
+        let mut pool = Pool::new(MERKLE_TREE_HEIGHT);
+        pool.deposit(123).unwrap();
+        let proof = WithdrawProof {
+            root: pool.tree().get_last_root(),
+            nullifier_hash: 456,
+            ..WithdrawProof::default()
+        };
+
+        #[cfg(not(feature = "groth16"))]
+        assert!(pool.verify_withdrawal(&proof).is_ok());
+        #[cfg(feature = "groth16")]
+        assert_eq!(pool.verify_withdrawal(&proof).unwrap_err().code(), ErrorCode::InvalidParameters);
+        pool.withdraw(proof.nullifier_hash, proof.root).unwrap();
+        assert_eq!(pool.verify_withdrawal(&proof).unwrap_err().code(), ErrorCode::NullifierAlreadySpent);
+
+        let unknown_root = WithdrawProof { root: 789, nullifier_hash: 1, ..proof };
+        assert_eq!(pool.verify_withdrawal(&unknown_root).unwrap_err().code(), ErrorCode::UnknownRoot);
+    }
+
+    #[cfg(feature = "groth16")]
+    #[test]
+    fn test_verify_withdrawal_rejects_malformed_snark() {
+        let mut pool = Pool::new(MERKLE_TREE_HEIGHT).with_verifying_key(&ark_groth16::VerifyingKey::default());
+        pool.deposit(123).unwrap();
+        let proof = WithdrawProof {
+            root: pool.tree().get_last_root(),
+            nullifier_hash: 456,
+            proof: vec![1, 2, 3],
+            ..WithdrawProof::default()
+        };
+
+        assert_eq!(pool.verify_withdrawal(&proof).unwrap_err().code(), ErrorCode::InvalidProof);
+    }
+
+    #[test]
+    fn test_root_policy() {
+        let mut pool = Pool::new(MERKLE_TREE_HEIGHT).with_root_policy(RootPolicy::new().with_max_age(0));
+        pool.deposit(1).unwrap();
+        let old_root = pool.tree().get_last_root();
+        pool.deposit(2).unwrap();
+
+        assert_eq!(pool.withdraw(456, old_root).unwrap_err().code(), ErrorCode::UnknownRoot);
+        assert!(pool.withdraw(456, pool.tree().get_last_root()).is_ok());
+    }
+}
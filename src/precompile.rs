@@ -0,0 +1,84 @@
+use std::sync::OnceLock;
+
+use primitive_types::U256;
+
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// `a * b mod modulus` on 256-bit operands, e.g. SP1's or RISC Zero's bigint
+/// multiplication syscall wrapped by the guest.
+pub type MulModFn = fn(U256, U256, U256) -> U256;
+/// Keccak-256 of a byte string, e.g. built on the zkVM's keccak permutation syscall.
+pub type Keccak256Fn = fn(&[u8]) -> [u8; 32];
+
+static MUL_MOD: OnceLock<MulModFn> = OnceLock::new();
+static KECCAK256: OnceLock<Keccak256Fn> = OnceLock::new();
+
+fn already_installed() -> SolanaError {
+    utils::err_with_code(ErrorCode::InvalidParameters, "Precompile is already installed")
+}
+
+/// Routes every BN254 field multiplication (`curve::field::mul_mod`) through
+/// `f`. A guest installs its accelerated version once, before hashing.
+pub fn install_mul_mod(f: MulModFn) -> Result<(), SolanaError> {
+    MUL_MOD.set(f).map_err(|_| already_installed())
+}
+
+/// Routes `hasher::keccak::keccak256`, and with it the keccak-derived round
+/// constants, through `f`.
+pub fn install_keccak256(f: Keccak256Fn) -> Result<(), SolanaError> {
+    KECCAK256.set(f).map_err(|_| already_installed())
+}
+
+pub(crate) fn mul_mod() -> Option<MulModFn> {
+    MUL_MOD.get().copied()
+}
+
+pub(crate) fn keccak256() -> Option<Keccak256Fn> {
+    KECCAK256.get().copied()
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    use super::*;
+    use crate::curve::field::{self, Fr};
+    use crate::hasher::keccak;
+
+    static CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    fn counting_mul_mod(a: U256, b: U256, modulus: U256) -> U256 {
+        CALLS.fetch_add(1, Ordering::SeqCst);
+        let product = a.full_mul(b) % primitive_types::U512::from(modulus);
+        U256::try_from(product).unwrap()
+    }
+
+    static KECCAK_CALLS: AtomicUsize = AtomicUsize::new(0);
+
+    // Both hooks keep the native results, since other tests share the process.
+    fn counting_keccak(data: &[u8]) -> [u8; 32] {
+        KECCAK_CALLS.fetch_add(1, Ordering::SeqCst);
+        let mut keccak = tiny_keccak::Keccak::v256();
+        let mut output = [0u8; 32];
+        tiny_keccak::Hasher::update(&mut keccak, data);
+        tiny_keccak::Hasher::finalize(keccak, &mut output);
+        output
+    }
+
+    #[test]
+    fn test_install() {
+        install_mul_mod(counting_mul_mod).unwrap();
+        assert!(install_mul_mod(counting_mul_mod).is_err());
+        let product = Fr::from(6u128) * Fr::from(7u128);
+        assert_eq!(product, Fr::from(42u128));
+        assert!(CALLS.load(Ordering::SeqCst) > 0);
+        assert_eq!(field::mul_mod(U256::from(6), U256::from(7), U256::from(5)), U256::from(2));
+
+        install_keccak256(counting_keccak).unwrap();
+        assert_eq!(
+            hex::encode(keccak::keccak256(b"abc")),
+            "4e03657aea45a94fc7d47ba826c8d667c0d1e6e33a64a036ec44f58fa12d6c45"
+        );
+        assert!(KECCAK_CALLS.load(Ordering::SeqCst) > 0);
+    }
+}
@@ -0,0 +1,50 @@
+use primitive_types::U256;
+use rand_core::{CryptoRng, RngCore};
+
+use crate::constants::in_field;
+use crate::curve::babyjubjub::SUBGROUP_ORDER;
+
+pub use rand_core::OsRng;
+
+/// Uniform element of the `Hasher` field, resampling the one out-of-range value.
+pub fn random_field_element<R: RngCore + CryptoRng>(rng: &mut R) -> u128 {
+    loop {
+        let mut bytes = [0u8; 16];
+        rng.fill_bytes(&mut bytes);
+        let value = u128::from_le_bytes(bytes);
+        if in_field(value) {
+            return value;
+        }
+    }
+}
+
+/// Uniform non-zero BabyJubJub scalar. Candidates are masked to the bit length
+/// of the subgroup order and rejected when out of range, so fewer than half are
+/// discarded.
+pub fn random_scalar<R: RngCore + CryptoRng>(rng: &mut R) -> U256 {
+    let bits = SUBGROUP_ORDER.bits();
+    loop {
+        let mut bytes = [0u8; 32];
+        rng.fill_bytes(&mut bytes);
+        let candidate = U256::from_little_endian(&bytes) & ((U256::one() << bits) - 1);
+        if !candidate.is_zero() && candidate < SUBGROUP_ORDER {
+            return candidate;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::FIELD_SIZE;
+
+    #[test]
+    fn test_ranges() {
+        for _ in 0..32 {
+            assert_ne!(random_field_element(&mut OsRng), FIELD_SIZE);
+            let scalar = random_scalar(&mut OsRng);
+            assert!(!scalar.is_zero() && scalar < SUBGROUP_ORDER);
+        }
+        assert_ne!(random_scalar(&mut OsRng), random_scalar(&mut OsRng));
+    }
+}
@@ -0,0 +1,96 @@
+use crate::{hasher::MerkleHasher, merkle_tree::MerkleTree};
+
+/// Decides which Merkle roots a withdrawal is allowed to reference.
+///
+/// By default every root still held in the tree's history is accepted, which
+/// matches `MerkleTree::is_known_root`. A maximum age (counted in insertions
+/// since the root was current) narrows that window, and pinned roots are
+/// accepted regardless of the history.
+#[derive(Debug, Clone, Default, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct RootPolicy {
+    max_age: Option<u8>,
+    pinned_roots: Vec<u128>
+}
+
+impl RootPolicy {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_max_age(mut self, insertions: u8) -> Self {
+        self.max_age = Some(insertions);
+        self
+    }
+
+    pub fn pin(mut self, root: u128) -> Self {
+        if !self.pinned_roots.contains(&root) {
+            self.pinned_roots.push(root);
+        }
+        self
+    }
+
+    pub fn max_age(&self) -> Option<u8> {
+        self.max_age
+    }
+
+    pub fn pinned_roots(&self) -> &[u128] {
+        &self.pinned_roots
+    }
+
+    pub fn is_pinned(&self, root: u128) -> bool {
+        root != 0 && self.pinned_roots.contains(&root)
+    }
+
+    pub fn accepts<H: MerkleHasher>(&self, tree: &MerkleTree<H>, root: u128) -> bool {
+        if self.is_pinned(root) {
+            return true;
+        }
+
+        match tree.root_age(root) {
+            Some(age) => self.max_age.is_none_or(|max_age| age <= max_age),
+            None => false
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    const MERKLE_TREE_HEIGHT: u8 = 4;
+
+    #[test]
+    fn test_default_policy_matches_history() {
+        let mut merkle_tree = MerkleTree::new(MERKLE_TREE_HEIGHT);
+        merkle_tree.insert(1).unwrap();
+        let old_root = merkle_tree.get_last_root();
+        merkle_tree.insert(2).unwrap();
+
+        let policy = RootPolicy::new();
+        assert!(policy.accepts(&merkle_tree, old_root));
+        assert!(policy.accepts(&merkle_tree, merkle_tree.get_last_root()));
+        assert!(!policy.accepts(&merkle_tree, 123));
+        assert!(!policy.accepts(&merkle_tree, 0));
+    }
+
+    #[test]
+    fn test_max_age() {
+        let mut merkle_tree = MerkleTree::new(MERKLE_TREE_HEIGHT);
+        merkle_tree.insert(1).unwrap();
+        let old_root = merkle_tree.get_last_root();
+        merkle_tree.insert(2).unwrap();
+        merkle_tree.insert(3).unwrap();
+
+        assert_eq!(merkle_tree.root_age(old_root), Some(2));
+        assert!(RootPolicy::new().with_max_age(2).accepts(&merkle_tree, old_root));
+        assert!(!RootPolicy::new().with_max_age(1).accepts(&merkle_tree, old_root));
+    }
+
+    #[test]
+    fn test_pinned_root() {
+        let merkle_tree = MerkleTree::new(MERKLE_TREE_HEIGHT);
+        let policy = RootPolicy::new().with_max_age(0).pin(123);
+        assert!(policy.accepts(&merkle_tree, 123));
+        assert!(!RootPolicy::new().pin(0).accepts(&merkle_tree, 0));
+    }
+}
@@ -0,0 +1,202 @@
+use blake_hash::{Blake512, Digest};
+use primitive_types::{U256, U512};
+
+use crate::curve::babyjubjub::{self, Point, COFACTOR, SUBGROUP_ORDER};
+use crate::curve::field::Fr;
+use crate::hasher::mimc7;
+use crate::utils::{self, ErrorCode, SolanaError};
+
+/// EdDSA private key: 32 raw bytes, expanded with BLAKE-512 as circomlib does.
+#[derive(Clone, Eq)]
+#[cfg_attr(feature = "zeroize", derive(zeroize::Zeroize, zeroize::ZeroizeOnDrop))]
+pub struct PrivateKey([u8; 32]);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct PublicKey(pub Point);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Signature {
+    pub r8: Point,
+    pub s: U256
+}
+
+impl PartialEq for PrivateKey {
+    fn eq(&self, other: &Self) -> bool {
+        utils::secure_eq(&self.0, &other.0)
+    }
+}
+
+/// Redacted, so the key never ends up in logs.
+impl std::fmt::Debug for PrivateKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("PrivateKey(..)")
+    }
+}
+
+impl PrivateKey {
+    pub fn from_bytes(bytes: [u8; 32]) -> Self {
+        PrivateKey(bytes)
+    }
+
+    #[cfg(feature = "rng")]
+    pub fn random() -> Self {
+        let mut bytes = [0u8; 32];
+        rand_core::RngCore::fill_bytes(&mut crate::rng::OsRng, &mut bytes);
+        PrivateKey(bytes)
+    }
+
+    pub fn as_bytes(&self) -> &[u8; 32] {
+        &self.0
+    }
+
+    /// The pruned secret scalar `s`; the public key is `Base8 * (s >> 3)`.
+    pub fn scalar(&self) -> U256 {
+        let hash = Blake512::digest(&self.0);
+        let mut s = [0u8; 32];
+        s.copy_from_slice(&hash[..32]);
+        s[0] &= 0xf8;
+        s[31] &= 0x7f;
+        s[31] |= 0x40;
+        U256::from_little_endian(&s)
+    }
+
+    pub fn public_key(&self) -> PublicKey {
+        PublicKey(Point::base8().mul_scalar(self.scalar() >> 3))
+    }
+
+    /// ECDH with another key, using the same `s >> 3` scalar as the public key
+    /// (MACI's `genEcdhSharedKey`).
+    pub fn derive_shared_secret(&self, public_key: &PublicKey) -> Result<Point, SolanaError> {
+        babyjubjub::derive_shared_secret(self.scalar() >> 3, &public_key.0)
+    }
+
+    /// circomlib `signMiMC`: the challenge is `mimc7.multiHash([R8.x, R8.y, A.x, A.y, msg])`.
+    pub fn sign(&self, message: Fr) -> Signature {
+        let hash = Blake512::digest(&self.0);
+        let s = self.scalar();
+        let public_key = self.public_key();
+
+        let mut nonce_input = [0u8; 64];
+        nonce_input[..32].copy_from_slice(&hash[32..]);
+        nonce_input[32..].copy_from_slice(&message.to_le_bytes());
+        let nonce = Blake512::digest(&nonce_input);
+        let r = U512::from_little_endian(&nonce) % U512::from(SUBGROUP_ORDER);
+        let r = U256::try_from(r).expect("Reduced nonce fits in 256 bits");
+
+        let r8 = Point::base8().mul_scalar(r);
+        let hm = challenge(&r8, &public_key.0, message);
+        let s = (U512::from(r) + hm.to_u256().full_mul(s)) % U512::from(SUBGROUP_ORDER);
+
+        Signature {
+            r8,
+            s: U256::try_from(s).expect("Reduced scalar fits in 256 bits")
+        }
+    }
+}
+
+impl PublicKey {
+    /// circomlib `verifyMiMC`.
+    pub fn verify(&self, message: Fr, signature: &Signature) -> bool {
+        if !signature.r8.is_on_curve() || !self.0.is_on_curve() {
+            return false;
+        }
+        if signature.s >= SUBGROUP_ORDER {
+            return false;
+        }
+
+        let hm = challenge(&signature.r8, &self.0, message);
+        let left = Point::base8().mul_scalar(signature.s);
+        let right = signature.r8.add(&self.0.mul_scalar(U256::from(COFACTOR)).mul_scalar(hm.to_u256()));
+        left == right
+    }
+
+    pub fn compress(&self) -> [u8; 32] {
+        self.0.compress()
+    }
+
+    pub fn decompress(bytes: &[u8; 32]) -> Result<PublicKey, SolanaError> {
+        Point::decompress(bytes).map(PublicKey)
+    }
+}
+
+impl Signature {
+    /// circomlib `packSignature`: packed `R8` followed by little-endian `S`.
+    pub fn compress(&self) -> [u8; 64] {
+        let mut bytes = [0u8; 64];
+        bytes[..32].copy_from_slice(&self.r8.compress());
+        self.s.to_little_endian(&mut bytes[32..]);
+        bytes
+    }
+
+    pub fn decompress(bytes: &[u8; 64]) -> Result<Signature, SolanaError> {
+        let mut r8 = [0u8; 32];
+        r8.copy_from_slice(&bytes[..32]);
+        let s = U256::from_little_endian(&bytes[32..]);
+        if s >= SUBGROUP_ORDER {
+            return Err(utils::err_with_code(ErrorCode::InvalidSignature, "Signature scalar is out of range"));
+        }
+
+        Ok(Signature {
+            r8: Point::decompress(&r8)?,
+            s
+        })
+    }
+}
+
+fn challenge(r8: &Point, public_key: &Point, message: Fr) -> Fr {
+    mimc7::multi_hash(&[r8.x, r8.y, public_key.x, public_key.y, message], Fr::zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn private_key() -> PrivateKey {
+        let mut bytes = [0u8; 32];
+        hex::decode_to_slice("0001020304050607080900010203040506070809000102030405060708090001", &mut bytes).unwrap();
+        PrivateKey::from_bytes(bytes)
+    }
+
+    fn dec(value: &str) -> U256 {
+        U256::from_dec_str(value).unwrap()
+    }
+
+    #[test]
+    fn test_circomlib_vector() {
+        let mut message = [0u8; 32];
+        message[..12].copy_from_slice(&[0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 0, 0]);
+        let message = Fr::from_le_bytes(&message).unwrap();
+        let public_key = private_key().public_key();
+        assert_eq!(public_key.0.x.to_u256(), dec("13277427435165878497778222415993513565335242147425444199013288855685581939618"));
+        assert_eq!(public_key.0.y.to_u256(), dec("13622229784656158136036771217484571176836296686641868549125388198837476602820"));
+
+        let signature = private_key().sign(message);
+        assert_eq!(signature.r8.x.to_u256(), dec("11384336176656855268977457483345535180380036354188103142384839473266348197733"));
+        assert_eq!(signature.r8.y.to_u256(), dec("15383486972088797283337779941324724402501462225528836549661220478783371668959"));
+        assert_eq!(signature.s, dec("2523202440825208709475937830811065542425109372212752003460238913256192595070"));
+        assert!(public_key.verify(message, &signature));
+    }
+
+    #[test]
+    fn test_rejects_tampering() {
+        let message = Fr::from(42u64);
+        let public_key = private_key().public_key();
+        let signature = private_key().sign(message);
+
+        assert!(!public_key.verify(Fr::from(43u64), &signature));
+        let forged = Signature { s: signature.s + U256::one(), ..signature };
+        assert!(!public_key.verify(message, &forged));
+
+        let decoded = Signature::decompress(&signature.compress()).unwrap();
+        assert_eq!(decoded, signature);
+        assert_eq!(PublicKey::decompress(&public_key.compress()).unwrap(), public_key);
+    }
+
+    #[test]
+    fn test_shared_secret() {
+        let other = PrivateKey::from_bytes([7u8; 32]);
+        let shared = private_key().derive_shared_secret(&other.public_key()).unwrap();
+        assert_eq!(format!("{:?}", other), "PrivateKey(..)");
+        assert_eq!(shared, other.derive_shared_secret(&private_key().public_key()).unwrap());
+    }
+}
@@ -0,0 +1,201 @@
+use primitive_types::{U256, U512};
+
+use crate::curve::babyjubjub::{self, reduce_scalar, Point, SUBGROUP_ORDER};
+use crate::curve::field::Fr;
+use crate::hasher::mimc7;
+use crate::utils::{self, SolanaError};
+
+/// Public half of a recipient's keys, published once so senders can derive
+/// fresh one-time addresses.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MetaAddress {
+    pub spending_public_key: Point,
+    pub viewing_public_key: Point
+}
+
+/// What a sender publishes next to a payment.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Announcement {
+    pub ephemeral_public_key: Point,
+    pub view_tag: u8,
+    pub stealth_public_key: Point
+}
+
+/// A payment the scanner recognised. `tweak` turns the spending key into the
+/// one-time key, see `stealth_private_key`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StealthPayment {
+    pub stealth_public_key: Point,
+    pub tweak: U256
+}
+
+#[derive(Clone, Eq)]
+pub struct ViewingKey(U256);
+
+impl PartialEq for ViewingKey {
+    fn eq(&self, other: &Self) -> bool {
+        let mut a = [0u8; 32];
+        let mut b = [0u8; 32];
+        self.0.to_little_endian(&mut a);
+        other.0.to_little_endian(&mut b);
+        utils::secure_eq(&a, &b)
+    }
+}
+
+/// Redacted, so the key never ends up in logs.
+impl std::fmt::Debug for ViewingKey {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        f.write_str("ViewingKey(..)")
+    }
+}
+
+impl ViewingKey {
+    pub fn from_scalar(secret: U256) -> Self {
+        ViewingKey(reduce_scalar(secret))
+    }
+
+    #[cfg(feature = "rng")]
+    pub fn random() -> Self {
+        ViewingKey(crate::rng::random_scalar(&mut crate::rng::OsRng))
+    }
+
+    pub fn scalar(&self) -> U256 {
+        self.0
+    }
+
+    pub fn public_key(&self) -> Point {
+        babyjubjub::public_key(self.0)
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::Zeroize for ViewingKey {
+    fn zeroize(&mut self) {
+        self.0 .0.zeroize();
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl Drop for ViewingKey {
+    fn drop(&mut self) {
+        zeroize::Zeroize::zeroize(self);
+    }
+}
+
+#[cfg(feature = "zeroize")]
+impl zeroize::ZeroizeOnDrop for ViewingKey {}
+
+/// Sender side: derives the one-time address for `meta_address` from a fresh
+/// ephemeral secret.
+pub fn generate_stealth_address(meta_address: &MetaAddress, ephemeral_secret: U256) -> Result<Announcement, SolanaError> {
+    let shared_secret = babyjubjub::derive_shared_secret(ephemeral_secret, &meta_address.viewing_public_key)?;
+    let (tweak, view_tag) = hash_shared_secret(&shared_secret);
+
+    Ok(Announcement {
+        ephemeral_public_key: babyjubjub::public_key(ephemeral_secret),
+        view_tag,
+        stealth_public_key: meta_address.spending_public_key.add(&babyjubjub::public_key(tweak))
+    })
+}
+
+/// Private key controlling a payment found by the scanner.
+pub fn stealth_private_key(spending_secret: U256, tweak: U256) -> U256 {
+    let key = (U512::from(reduce_scalar(spending_secret)) + U512::from(tweak)) % U512::from(SUBGROUP_ORDER);
+    U256::try_from(key).expect("Reduced scalar fits in 256 bits")
+}
+
+// tweak = mimc7.multiHash([S.x, S.y]) mod l, view tag = its lowest byte.
+fn hash_shared_secret(shared_secret: &Point) -> (U256, u8) {
+    let hash = mimc7::multi_hash(&[shared_secret.x, shared_secret.y], Fr::zero());
+    (reduce_scalar(hash.to_u256()), hash.to_le_bytes()[0])
+}
+
+/// Recipient side: finds announcements addressed to one meta-address using
+/// only the viewing key.
+#[derive(Debug, Clone)]
+pub struct Scanner {
+    viewing_key: ViewingKey,
+    spending_public_key: Point
+}
+
+impl Scanner {
+    pub fn new(viewing_key: ViewingKey, spending_public_key: Point) -> Self {
+        Scanner {
+            viewing_key,
+            spending_public_key
+        }
+    }
+
+    /// One ECDH per announcement; the view tag rejects ~255/256 of foreign
+    /// announcements before the more expensive stealth-key derivation.
+    pub fn check(&self, announcement: &Announcement) -> Option<StealthPayment> {
+        let shared_secret = babyjubjub::derive_shared_secret(self.viewing_key.0, &announcement.ephemeral_public_key).ok()?;
+        let (tweak, view_tag) = hash_shared_secret(&shared_secret);
+        if view_tag != announcement.view_tag {
+            return None;
+        }
+
+        let stealth_public_key = self.spending_public_key.add(&babyjubjub::public_key(tweak));
+        if stealth_public_key != announcement.stealth_public_key {
+            return None;
+        }
+
+        Some(StealthPayment {
+            stealth_public_key,
+            tweak
+        })
+    }
+
+    /// Scans a batch, returning the position of each matching announcement.
+    pub fn scan<'a, I>(&self, announcements: I) -> Vec<(usize, StealthPayment)>
+    where
+        I: IntoIterator<Item = &'a Announcement>
+    {
+        announcements
+            .into_iter()
+            .enumerate()
+            .filter_map(|(index, announcement)| self.check(announcement).map(|payment| (index, payment)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scan() {
+        let spending_secret = U256::from(1111u64);
+        let viewing_key = ViewingKey::from_scalar(U256::from(2222u64));
+        assert_eq!(format!("{:?}", viewing_key), "ViewingKey(..)");
+        let meta_address = MetaAddress {
+            spending_public_key: babyjubjub::public_key(spending_secret),
+            viewing_public_key: viewing_key.public_key()
+        };
+        let other = MetaAddress {
+            spending_public_key: babyjubjub::public_key(U256::from(3333u64)),
+            viewing_public_key: babyjubjub::public_key(U256::from(4444u64))
+        };
+
+        let announcements = [
+            generate_stealth_address(&other, U256::from(5u64)).unwrap(),
+            generate_stealth_address(&meta_address, U256::from(6u64)).unwrap(),
+            generate_stealth_address(&other, U256::from(7u64)).unwrap()
+        ];
+
+        let scanner = Scanner::new(viewing_key, meta_address.spending_public_key);
+        let found = scanner.scan(&announcements);
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, 1);
+
+        let payment = found[0].1;
+        let private_key = stealth_private_key(spending_secret, payment.tweak);
+        assert_eq!(babyjubjub::public_key(private_key), announcements[1].stealth_public_key);
+
+        let tampered = Announcement {
+            view_tag: announcements[1].view_tag.wrapping_add(1),
+            ..announcements[1]
+        };
+        assert!(scanner.check(&tampered).is_none());
+    }
+}
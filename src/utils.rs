@@ -1,36 +1,285 @@
+use std::borrow::Cow;
 use std::fmt::Display;
 
-#[derive(Debug, PartialEq)]
+/// Map and set behind the tree and pool indexes. The `zkvm` feature swaps in
+/// the ordered collections, which need no random seed inside a guest and
+/// iterate the same way on every run.
+#[cfg(not(feature = "zkvm"))]
+pub(crate) type Map<K, V> = std::collections::HashMap<K, V>;
+#[cfg(feature = "zkvm")]
+pub(crate) type Map<K, V> = std::collections::BTreeMap<K, V>;
+#[cfg(not(feature = "zkvm"))]
+pub(crate) type Set<T> = std::collections::HashSet<T>;
+#[cfg(feature = "zkvm")]
+pub(crate) type Set<T> = std::collections::BTreeSet<T>;
+
+/// Literal messages are borrowed, so the common error paths never allocate;
+/// only messages built with `format!` own their text.
+#[derive(Debug)]
 pub struct SolanaError {
-    error_msg: String,
-    error_name: String,
-    #[allow(unused)]
+    error_msg: Cow<'static, str>,
     error_code_number: u32,
-    #[allow(unused)]
-    error_origin: Option<String>,
-    #[allow(unused)]
-    compared_values: Option<String>
+    context: Vec<&'static str>,
+    compared_values: Option<String>,
+    source: Option<Box<dyn std::error::Error + Send + Sync>>
 }
 
+/// Compares everything but the wrapped source error.
+impl PartialEq for SolanaError {
+    fn eq(&self, other: &Self) -> bool {
+        self.error_msg == other.error_msg
+            && self.error_code_number == other.error_code_number
+            && self.context == other.context
+            && self.compared_values == other.compared_values
+    }
+}
+
+/// Outermost context first, e.g.
+/// `Error: InvalidEncoding - Parsing levels: Invalid integer: ...`.
 impl Display for SolanaError {
     fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
-        write!(f, "Error: {} - {}", self.error_name, self.error_msg)
+        write!(f, "Error: {:?} - ", self.code())?;
+        for context in self.context.iter().rev() {
+            write!(f, "{}: ", context)?;
+        }
+        write!(f, "{}", self.error_msg)
+    }
+}
+
+impl std::error::Error for SolanaError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.source.as_deref().map(|source| source as &(dyn std::error::Error + 'static))
+    }
+}
+
+impl SolanaError {
+    pub fn code(&self) -> ErrorCode {
+        ErrorCode::from_u32(self.error_code_number).unwrap_or(ErrorCode::Exception)
+    }
+
+    pub fn kind(&self) -> ErrorKind {
+        self.code().kind()
+    }
+
+    /// Whether the same call can succeed later without changing its input,
+    /// e.g. a root the tree has not synced yet. Rejections such as a spent
+    /// nullifier or a bad proof are final.
+    pub fn is_recoverable(&self) -> bool {
+        matches!(self.code(), ErrorCode::UnknownRoot | ErrorCode::MemberNotFound)
+    }
+
+    pub fn message(&self) -> &str {
+        &self.error_msg
+    }
+
+    /// The values whose mismatch caused the error, when the error records them.
+    pub fn compared_values(&self) -> Option<&str> {
+        self.compared_values.as_deref()
+    }
+
+    /// Describes what was being done when the error happened. Contexts stack,
+    /// so each layer a failure passes through can add its own.
+    pub fn context(mut self, context: &'static str) -> Self {
+        self.context.push(context);
+        self
+    }
+
+    pub fn with_compared_values(mut self, expected: impl Display, actual: impl Display) -> Self {
+        self.compared_values = Some(format!("expected {}, got {}", expected, actual));
+        self
+    }
+}
+
+/// Discriminants are stable: a code keeps its number across releases and new
+/// variants only take unused ones.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(all(feature = "defmt", target_os = "none"), derive(defmt::Format))]
+pub enum ErrorCode {
+    Exception = 0,
+    UnknownRoot = 1,
+    NullifierAlreadySpent = 2,
+    CommitmentAlreadySubmitted = 3,
+    InvalidProof = 4,
+    LeafIndexOutOfRange = 5,
+    MemberAlreadyExists = 6,
+    MemberNotFound = 7,
+    InvalidPoint = 8,
+    InvalidSignature = 9,
+    InvalidParameters = 10,
+    FieldOverflow = 11,
+    LeavesNotRetained = 12,
+    InvalidEncoding = 13,
+    InvalidChecksum = 14
+}
+
+/// Coarse grouping of error codes for generic retry and reject policies.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorKind {
+    InvalidInput,
+    State,
+    Capacity,
+    Serialization,
+    Crypto
+}
+
+impl ErrorCode {
+    pub fn as_u32(self) -> u32 {
+        self as u32
+    }
+
+    pub fn kind(self) -> ErrorKind {
+        match self {
+            ErrorCode::InvalidParameters | ErrorCode::FieldOverflow => ErrorKind::InvalidInput,
+            ErrorCode::Exception
+            | ErrorCode::UnknownRoot
+            | ErrorCode::NullifierAlreadySpent
+            | ErrorCode::CommitmentAlreadySubmitted
+            | ErrorCode::MemberAlreadyExists
+            | ErrorCode::MemberNotFound
+            | ErrorCode::LeavesNotRetained => ErrorKind::State,
+            ErrorCode::LeafIndexOutOfRange => ErrorKind::Capacity,
+            ErrorCode::InvalidEncoding | ErrorCode::InvalidChecksum => ErrorKind::Serialization,
+            ErrorCode::InvalidProof | ErrorCode::InvalidPoint | ErrorCode::InvalidSignature => ErrorKind::Crypto
+        }
+    }
+
+    pub fn from_u32(code: u32) -> Option<ErrorCode> {
+        match code {
+            0 => Some(ErrorCode::Exception),
+            1 => Some(ErrorCode::UnknownRoot),
+            2 => Some(ErrorCode::NullifierAlreadySpent),
+            3 => Some(ErrorCode::CommitmentAlreadySubmitted),
+            4 => Some(ErrorCode::InvalidProof),
+            5 => Some(ErrorCode::LeafIndexOutOfRange),
+            6 => Some(ErrorCode::MemberAlreadyExists),
+            7 => Some(ErrorCode::MemberNotFound),
+            8 => Some(ErrorCode::InvalidPoint),
+            9 => Some(ErrorCode::InvalidSignature),
+            10 => Some(ErrorCode::InvalidParameters),
+            11 => Some(ErrorCode::FieldOverflow),
+            12 => Some(ErrorCode::LeavesNotRetained),
+            13 => Some(ErrorCode::InvalidEncoding),
+            14 => Some(ErrorCode::InvalidChecksum),
+            _ => None
+        }
+    }
+}
+
+/// `result.context("...")` for any error that converts into `SolanaError`.
+pub trait Context<T> {
+    fn context(self, context: &'static str) -> Result<T, SolanaError>;
+}
+
+impl<T, E: Into<SolanaError>> Context<T> for Result<T, E> {
+    fn context(self, context: &'static str) -> Result<T, SolanaError> {
+        self.map_err(|e| e.into().context(context))
+    }
+}
+
+fn wrap(code: ErrorCode, msg: String, source: impl std::error::Error + Send + Sync + 'static) -> SolanaError {
+    SolanaError { source: Some(Box::new(source)), ..err_with_code(code, msg) }
+}
+
+impl From<hex::FromHexError> for SolanaError {
+    fn from(e: hex::FromHexError) -> Self {
+        wrap(ErrorCode::InvalidEncoding, format!("Invalid hex: {}", e), e)
+    }
+}
+
+impl From<std::num::ParseIntError> for SolanaError {
+    fn from(e: std::num::ParseIntError) -> Self {
+        wrap(ErrorCode::InvalidEncoding, format!("Invalid integer: {}", e), e)
+    }
+}
+
+impl From<std::io::Error> for SolanaError {
+    fn from(e: std::io::Error) -> Self {
+        wrap(ErrorCode::Exception, format!("I/O error: {}", e), e)
+    }
+}
+
+/// `ProgramError::Custom` with the error code, so a failed instruction shows
+/// the same number on every explorer.
+#[cfg(feature = "solana")]
+impl From<SolanaError> for solana_program_error::ProgramError {
+    fn from(err: SolanaError) -> Self {
+        solana_program_error::ProgramError::Custom(err.error_code_number)
+    }
+}
+
+/// Same text as `Display`, for logging over RTT without `core::fmt`.
+#[cfg(all(feature = "defmt", target_os = "none"))]
+impl defmt::Format for SolanaError {
+    fn format(&self, f: defmt::Formatter) {
+        defmt::write!(f, "Error: {} - {=str}", self.code(), &*self.error_msg)
     }
 }
 
-pub fn err(msg: &str) -> SolanaError {
+pub fn err(msg: impl Into<Cow<'static, str>>) -> SolanaError {
+    err_with_code(ErrorCode::Exception, msg)
+}
+
+pub fn err_with_code(code: ErrorCode, msg: impl Into<Cow<'static, str>>) -> SolanaError {
     SolanaError {
-        error_msg: msg.to_string(),
-        error_name: "Exception".to_string(),
-        error_code_number: 0,
-        error_origin: None,
-        compared_values: None
+        error_msg: msg.into(),
+        error_code_number: code as u32,
+        context: Vec::new(),
+        compared_values: None,
+        source: None
+    }
+}
+
+/// Equality for secrets and untrusted proof data. With the `constant-time`
+/// feature the comparison does not stop at the first differing byte.
+pub fn secure_eq(a: &[u8], b: &[u8]) -> bool {
+    #[cfg(feature = "constant-time")]
+    {
+        subtle::ConstantTimeEq::ct_eq(a, b).into()
+    }
+    #[cfg(not(feature = "constant-time"))]
+    {
+        a == b
+    }
+}
+
+/// Returns `(left, right)` for a Merkle path step; branch-free with the
+/// `constant-time` feature.
+pub fn order_pair(node: u128, sibling: u128, node_is_right: bool) -> (u128, u128) {
+    #[cfg(feature = "constant-time")]
+    {
+        let mut left = node;
+        let mut right = sibling;
+        subtle::ConditionallySelectable::conditional_swap(&mut left, &mut right, subtle::Choice::from(node_is_right as u8));
+        (left, right)
+    }
+    #[cfg(not(feature = "constant-time"))]
+    {
+        if node_is_right {
+            (sibling, node)
+        } else {
+            (node, sibling)
+        }
     }
 }
 
+/// Field value from a decimal or `0x`-prefixed hex string, the form used by
+/// the JavaScript bindings since a JS `Number` cannot hold 128 bits.
+pub fn parse_field(value: &str) -> Result<u128, SolanaError> {
+    let parsed = match value.strip_prefix("0x") {
+        Some(hex) => u128::from_str_radix(hex, 16),
+        None => value.parse()
+    };
+    parsed.map_err(|_| err_with_code(ErrorCode::InvalidParameters, format!("{} is not a field value", value)))
+}
+
+pub fn field_to_hex(value: u128) -> String {
+    format!("0x{:x}", value)
+}
+
+#[allow(clippy::ptr_arg)]
 pub fn vec_to_u128(vec: &Vec<u8>) -> u128 {
     let mut array = [0u8; 16];
-    array.copy_from_slice(&vec);
+    array.copy_from_slice(vec);
     u128::from_be_bytes(array)
 }
 
@@ -39,4 +288,56 @@ pub fn bytes_to_binary(i: &[u8; 32], r: &mut Vec<u8>) {
     for m in i.iter() {
         format!("{:8b}", m).chars().for_each(|b| if b == '1' { r.push(1); } else { r.push(0) } );
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_secure_helpers() {
+        assert!(secure_eq(&[1, 2, 3], &[1, 2, 3]));
+        assert!(!secure_eq(&[1, 2, 3], &[1, 2, 4]));
+        assert!(!secure_eq(&[1, 2], &[1, 2, 3]));
+        assert_eq!(order_pair(1, 2, false), (1, 2));
+        assert_eq!(order_pair(1, 2, true), (2, 1));
+    }
+
+    #[test]
+    fn test_error_codes() {
+        for number in 0..=14 {
+            assert_eq!(ErrorCode::from_u32(number).unwrap().as_u32(), number);
+        }
+        assert_eq!(ErrorCode::LeavesNotRetained.as_u32(), 12);
+        assert_eq!(ErrorCode::from_u32(15), None);
+        assert_eq!(err_with_code(ErrorCode::InvalidProof, "bad").code().as_u32(), 4);
+        assert!(matches!(err_with_code(ErrorCode::InvalidProof, "bad").error_msg, Cow::Borrowed("bad")));
+        assert_eq!(err_with_code(ErrorCode::UnknownRoot, format!("root {}", 7)).to_string(), "Error: UnknownRoot - root 7");
+
+        let unknown_root = err_with_code(ErrorCode::UnknownRoot, "unknown");
+        assert_eq!(unknown_root.kind(), ErrorKind::State);
+        assert!(unknown_root.is_recoverable());
+        let spent = err_with_code(ErrorCode::NullifierAlreadySpent, "spent");
+        assert_eq!(spent.kind(), ErrorKind::State);
+        assert!(!spent.is_recoverable());
+        assert_eq!(ErrorCode::LeafIndexOutOfRange.kind(), ErrorKind::Capacity);
+        assert_eq!(ErrorCode::InvalidSignature.kind(), ErrorKind::Crypto);
+
+        let parsed: Result<u8, SolanaError> = "256".parse::<u8>().map_err(SolanaError::from);
+        assert_eq!(parsed.unwrap_err().kind(), ErrorKind::Serialization);
+        assert_eq!(SolanaError::from(hex::decode("zz").unwrap_err()).code(), ErrorCode::InvalidEncoding);
+        let io = SolanaError::from(std::io::Error::new(std::io::ErrorKind::UnexpectedEof, "short read"));
+        assert_eq!(io.message(), "I/O error: short read");
+
+        let nested = "x".parse::<u8>().context("Parsing levels").context("Restoring tree");
+        let nested = nested.unwrap_err();
+        assert_eq!(nested.to_string(), "Error: InvalidEncoding - Restoring tree: Parsing levels: Invalid integer: invalid digit found in string");
+        assert!(std::error::Error::source(&nested).unwrap().is::<std::num::ParseIntError>());
+
+        #[cfg(feature = "solana")]
+        assert_eq!(
+            solana_program_error::ProgramError::from(err_with_code(ErrorCode::UnknownRoot, "unknown")),
+            solana_program_error::ProgramError::Custom(1)
+        );
+    }
+}
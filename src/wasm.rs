@@ -0,0 +1,212 @@
+use js_sys::BigInt;
+use serde::{Deserialize, Serialize};
+use wasm_bindgen::prelude::*;
+
+use crate::hasher::{self, keccak, MerkleHasher};
+use crate::merkle_tree;
+use crate::utils::{self, field_to_hex, parse_field, ErrorCode, SolanaError};
+
+/// Exact conversion from a JS `BigInt`, rejecting negative or wider values.
+fn from_bigint(value: BigInt) -> Result<u128, JsError> {
+    u128::try_from(value).map_err(|_| JsError::new("BigInt is negative or wider than 128 bits"))
+}
+
+fn to_js(err: SolanaError) -> JsError {
+    JsError::new(&err.to_string())
+}
+
+/// Path sides must be 0 or 1 and spell out `leaf_index` bit by bit.
+fn sides_from_json(sides: Vec<u8>, leaf_index: u64) -> Result<Vec<u8>, SolanaError> {
+    let invalid = |msg: String| utils::err_with_code(ErrorCode::InvalidProof, msg);
+    for (level, side) in sides.iter().enumerate() {
+        if *side > 1 {
+            return Err(invalid(format!("{} is not a path side, expected 0 or 1", side)));
+        }
+        let bit = leaf_index.checked_shr(level as u32).unwrap_or(0) & 1;
+        if *side as u64 != bit {
+            return Err(invalid(format!("Path index at level {} does not match leaf index {}", level, leaf_index)).with_compared_values(bit, side));
+        }
+    }
+    if leaf_index.checked_shr(sides.len() as u32).unwrap_or(0) != 0 {
+        return Err(invalid(format!("Leaf index {} does not fit in the proof depth", leaf_index)));
+    }
+    Ok(sides)
+}
+
+#[wasm_bindgen(js_name = MerkleTree)]
+pub struct MerkleTree {
+    inner: merkle_tree::MerkleTree
+}
+
+#[wasm_bindgen(js_class = MerkleTree)]
+impl MerkleTree {
+    #[wasm_bindgen(constructor)]
+    pub fn new(levels: u8) -> Result<MerkleTree, JsError> {
+        if levels == 0 {
+            return Err(to_js(utils::err_with_code(ErrorCode::InvalidParameters, "Tree must have at least one level")));
+        }
+        Ok(MerkleTree { inner: merkle_tree::MerkleTree::new(levels) })
+    }
+
+    pub fn insert(&mut self, leaf: &str) -> Result<u64, JsError> {
+        self.inner.insert(parse_field(leaf).map_err(to_js)?).map_err(to_js)
+    }
+
+    #[wasm_bindgen(js_name = insertBytes)]
+    pub fn insert_bytes(&mut self, data: &[u8]) -> Result<u64, JsError> {
+        self.inner.insert_bytes(data).map_err(to_js)
+    }
+
+    #[wasm_bindgen(js_name = insertBigInt)]
+    pub fn insert_bigint(&mut self, leaf: BigInt) -> Result<u64, JsError> {
+        self.inner.insert(from_bigint(leaf)?).map_err(to_js)
+    }
+
+    pub fn root(&self) -> String {
+        self.inner.get_last_root().to_string()
+    }
+
+    #[wasm_bindgen(js_name = rootBigInt)]
+    pub fn root_bigint(&self) -> BigInt {
+        BigInt::from(self.inner.get_last_root())
+    }
+
+    #[wasm_bindgen(js_name = isKnownRoot)]
+    pub fn is_known_root(&self, root: &str) -> Result<bool, JsError> {
+        Ok(self.inner.is_known_root(parse_field(root).map_err(to_js)?))
+    }
+
+    pub fn proof(&self, index: u64) -> Result<MerkleProof, JsError> {
+        Ok(MerkleProof { inner: self.inner.proof(index).map_err(to_js)? })
+    }
+}
+
+#[wasm_bindgen(js_name = MerkleProof)]
+pub struct MerkleProof {
+    inner: merkle_tree::MerkleProof
+}
+
+/// JSON shape of an exported proof, with every field value as a decimal string.
+#[derive(Serialize, Deserialize)]
+struct ProofJson {
+    leaf: String,
+    leaf_index: String,
+    path_elements: Vec<String>,
+    path_indices: Vec<u8>
+}
+
+impl MerkleProof {
+    fn export(&self) -> String {
+        let json = ProofJson {
+            leaf: self.inner.leaf.to_string(),
+            leaf_index: self.inner.leaf_index.to_string(),
+            path_elements: self.inner.path_elements.iter().map(u128::to_string).collect(),
+            path_indices: self.inner.path_indices.clone()
+        };
+        serde_json::to_string(&json).expect("Proof JSON has no maps with non-string keys")
+    }
+
+    fn import(json: &str) -> Result<merkle_tree::MerkleProof, SolanaError> {
+        let invalid = |msg: String| utils::err_with_code(ErrorCode::InvalidProof, msg);
+        let json: ProofJson = serde_json::from_str(json).map_err(|e| invalid(format!("Proof JSON is invalid: {}", e)))?;
+        let leaf_index = json.leaf_index.parse().map_err(|_| invalid(format!("{} is not a leaf index", json.leaf_index)))?;
+        Ok(merkle_tree::MerkleProof {
+            leaf: parse_field(&json.leaf)?,
+            leaf_index,
+            path_elements: json.path_elements.iter().map(|value| parse_field(value)).collect::<Result<_, _>>()?,
+            path_indices: sides_from_json(json.path_indices, leaf_index)?
+        })
+    }
+}
+
+#[wasm_bindgen(js_class = MerkleProof)]
+impl MerkleProof {
+    #[wasm_bindgen(getter)]
+    pub fn leaf(&self) -> String {
+        self.inner.leaf.to_string()
+    }
+
+    #[wasm_bindgen(getter, js_name = leafBigInt)]
+    pub fn leaf_bigint(&self) -> BigInt {
+        BigInt::from(self.inner.leaf)
+    }
+
+    #[wasm_bindgen(getter, js_name = leafIndex)]
+    pub fn leaf_index(&self) -> u64 {
+        self.inner.leaf_index
+    }
+
+    pub fn verify(&self, root: &str) -> Result<bool, JsError> {
+        Ok(self.inner.verify(parse_field(root).map_err(to_js)?))
+    }
+
+    #[wasm_bindgen(js_name = toJSON)]
+    pub fn to_json(&self) -> String {
+        self.export()
+    }
+
+    #[wasm_bindgen(js_name = fromJSON)]
+    pub fn from_json(json: &str) -> Result<MerkleProof, JsError> {
+        Ok(MerkleProof { inner: Self::import(json).map_err(to_js)? })
+    }
+}
+
+#[wasm_bindgen(js_name = toBigInt)]
+pub fn to_bigint(value: &str) -> Result<BigInt, JsError> {
+    Ok(BigInt::from(parse_field(value).map_err(to_js)?))
+}
+
+/// Decimal string for a `BigInt` field value.
+#[wasm_bindgen(js_name = fromBigInt)]
+pub fn from_bigint_string(value: BigInt) -> Result<String, JsError> {
+    Ok(from_bigint(value)?.to_string())
+}
+
+#[wasm_bindgen(js_name = toHex)]
+pub fn to_hex(value: &str) -> Result<String, JsError> {
+    Ok(field_to_hex(parse_field(value).map_err(to_js)?))
+}
+
+#[wasm_bindgen(js_name = hashPair)]
+pub fn hash_pair(left: &str, right: &str) -> Result<String, JsError> {
+    let hasher = hasher::Hasher::default();
+    Ok(hasher.hash_pair(parse_field(left).map_err(to_js)?, parse_field(right).map_err(to_js)?).to_string())
+}
+
+#[wasm_bindgen(js_name = hashToField)]
+pub fn hash_to_field(data: &[u8]) -> String {
+    hasher::hash_to_field(data).to_string()
+}
+
+#[wasm_bindgen(js_name = keccak256)]
+pub fn keccak256(data: &[u8]) -> Vec<u8> {
+    keccak::keccak256(data).to_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_proof_json() {
+        assert_eq!(parse_field("0xff").unwrap(), 255);
+        assert_eq!(parse_field(&u128::MAX.to_string()).unwrap(), u128::MAX);
+        assert!(parse_field("0xzz").is_err());
+        assert_eq!(field_to_hex(255), "0xff");
+        assert_eq!(parse_field(&field_to_hex(u128::MAX)).unwrap(), u128::MAX);
+        assert_eq!(to_hex("0").unwrap(), "0x0");
+
+        let mut tree = MerkleTree::new(4).unwrap();
+        tree.insert_bytes(b"note").unwrap();
+        let proof = tree.proof(0).unwrap();
+        let json = proof.to_json();
+        assert!(json.contains(&format!("\"leaf\":\"{}\"", proof.leaf())));
+        assert_eq!(MerkleProof::import(&json).unwrap(), proof.inner);
+        assert!(MerkleProof::import("{}").is_err());
+        for (leaf_index, path_indices) in [("0", "[0,0,0,2]"), ("0", "[0,1,0,0]"), ("16", "[0,0,0,0]")] {
+            let json = format!("{{\"leaf\":\"1\",\"leaf_index\":\"{}\",\"path_elements\":[\"0\",\"0\",\"0\",\"0\"],\"path_indices\":{}}}", leaf_index, path_indices);
+            assert_eq!(MerkleProof::import(&json).unwrap_err().code(), ErrorCode::InvalidProof, "{}", json);
+        }
+        assert_eq!(hash_pair("1", "2").unwrap(), hasher::Hasher::default().hash_pair(1, 2).to_string());
+    }
+}